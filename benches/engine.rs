@@ -0,0 +1,45 @@
+use chess_lib::{
+    bench::{perft, POSITIONS},
+    chess::Board,
+    engine::{Engine, Limits, StopToken},
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn boards() -> Vec<Board> {
+    POSITIONS
+        .iter()
+        .map(|fen| {
+            let mut board = Board::new().unwrap();
+            board.from_fen(fen).unwrap();
+            board
+        })
+        .collect()
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let boards = boards();
+
+    c.bench_function("perft 2", |b| {
+        b.iter(|| boards.iter().map(|board| perft(board, 2)).sum::<u64>())
+    });
+}
+
+fn bench_search(c: &mut Criterion) {
+    let boards = boards();
+    let engine = Engine::default();
+
+    c.bench_function("search depth 3", |b| {
+        b.iter(|| {
+            for board in boards.iter() {
+                engine.search(board, &Limits::depth(3), &StopToken::new());
+            }
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_perft, bench_search
+}
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+fn main() {
+    // the C header is only regenerated when building with the ffi feature
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(cbindgen::Config::from_root_or_default(&crate_dir))
+        .generate()
+        .expect("failed to generate C header")
+        .write_to_file(format!("{}/include/chess_lib.h", crate_dir));
+}
@@ -0,0 +1,14 @@
+#![no_main]
+
+use chess_lib::{chess::Board, fen::ToFen};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    // a position that loads has to write out and load again
+    if let Ok(board) = Board::try_from_fen(data) {
+        let fen = board.to_fen();
+        let again = Board::try_from_fen(&fen).expect("written FEN doesn't load");
+        assert_eq!(fen, again.to_fen());
+        let _ = board.legal_moves();
+    }
+});
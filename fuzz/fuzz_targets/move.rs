@@ -0,0 +1,16 @@
+#![no_main]
+
+use chess_lib::{chess::Board, mover::Move, notation, pgn::parse_san};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let board = Board::default_board().unwrap();
+
+    if let Ok(m) = Move::try_from(data) {
+        let _ = board.is_legal(&m);
+    }
+
+    let _ = parse_san(&board, data);
+    let _ = notation::from_lan(&board, data);
+    let _ = notation::from_iccf(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use chess_lib::pgn::PgnReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for game in PgnReader::new(data) {
+        let _ = game;
+    }
+});
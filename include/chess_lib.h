@@ -0,0 +1,172 @@
+#ifndef CHESS_LIB_H
+#define CHESS_LIB_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Loss in centipawns from which a move is an inaccuracy
+ */
+#define INACCURACY 50
+
+/**
+ * Loss in centipawns from which a move is a mistake
+ */
+#define MISTAKE 100
+
+/**
+ * Score of a checkmate, reduced by the number of plies it takes
+ */
+#define MATE 100000
+
+/**
+ * Loss in centipawns from which a move counts as a blunder
+ */
+#define BLUNDER 200
+
+/**
+ * Skill level of an engine playing at full strength
+ */
+#define MAX_SKILL 20
+
+/**
+ * Phase of the starting position
+ */
+#define MAX_PHASE 24
+
+/**
+ * Scores beyond this many centipawns either way are drawn at the edge
+ */
+#define SCALE 500
+
+/**
+ * The rating of a new profile
+ */
+#define INITIAL_RATING 1200
+
+/**
+ * Runs in a row without a mistake for a line to count as mastered
+ */
+#define MASTERED 3
+
+typedef enum ChessColor {
+  /**
+   * Returned for a null board
+   */
+  CHESS_COLOR_INVALID = -1,
+  CHESS_COLOR_WHITE = 0,
+  CHESS_COLOR_BLACK = 1,
+} ChessColor;
+
+typedef enum ChessStatus {
+  /**
+   * Returned for a null board
+   */
+  CHESS_STATUS_INVALID = -1,
+  CHESS_STATUS_ONGOING = 0,
+  CHESS_STATUS_CHECK = 1,
+  CHESS_STATUS_CHECKMATE = 2,
+  CHESS_STATUS_STALEMATE = 3,
+} ChessStatus;
+
+/**
+ * Opaque handle to a board
+ */
+typedef struct ChessBoard ChessBoard;
+
+typedef struct Class Class;
+
+/**
+ * The eight directions a line can run in, north being towards black's side
+ */
+typedef struct Direction Direction;
+
+
+
+
+
+/**
+ * Creates a board with the default starting position, or null on failure
+ */
+struct ChessBoard *chess_board_new(void);
+
+/**
+ * Releases a board created by `chess_board_new`
+ *
+ * # Safety
+ *
+ * `board` must be null or a pointer returned by `chess_board_new` that has
+ * not been freed yet.
+ */
+void chess_board_free(struct ChessBoard *board);
+
+/**
+ * Replaces the position with the given FEN. Returns 0 on success and -1 if
+ * the FEN is invalid, in which case the board keeps its position.
+ *
+ * # Safety
+ *
+ * `board` must be a valid board pointer and `fen` a valid C string.
+ */
+int chess_board_load_fen(struct ChessBoard *board, const char *fen);
+
+/**
+ * Returns the position as FEN
+ *
+ * # Safety
+ *
+ * `board` must be a valid board pointer.
+ */
+char *chess_board_fen(const struct ChessBoard *board);
+
+/**
+ * Plays a move in coordinate notation such as "e2e4". Returns 0 on success
+ * and -1 if the move is not valid.
+ *
+ * # Safety
+ *
+ * `board` must be a valid board pointer and `m` a valid C string.
+ */
+int chess_board_make_move(struct ChessBoard *board, const char *m);
+
+/**
+ * Returns every legal move as a space separated string
+ *
+ * # Safety
+ *
+ * `board` must be a valid board pointer.
+ */
+char *chess_board_legal_moves(const struct ChessBoard *board);
+
+/**
+ * Returns the side to move, or `Invalid` for a null board
+ *
+ * # Safety
+ *
+ * `board` must be null or a valid board pointer.
+ */
+enum ChessColor chess_board_turn(const struct ChessBoard *board);
+
+/**
+ * Returns whether the side to move is in check, mated or stalemated, or
+ * `Invalid` for a null board
+ *
+ * # Safety
+ *
+ * `board` must be null or a valid board pointer.
+ */
+enum ChessStatus chess_board_status(const struct ChessBoard *board);
+
+/**
+ * Releases a string returned by one of the functions above
+ *
+ * # Safety
+ *
+ * `s` must be null or a string returned by this library that has not been
+ * freed yet.
+ */
+void chess_string_free(char *s);
+
+#endif  /* CHESS_LIB_H */
@@ -0,0 +1,264 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    chess::{Board, Color, Error, DEFAULT_BOARD},
+    engine::{self, Backend, Evaluation, MoveAnalysis},
+    fen::ToFen,
+    game::{Game, GameResult},
+    graph,
+    mover::Move,
+    pgn::{self, PgnMove},
+};
+
+/// Loss in centipawns from which a move is an inaccuracy
+pub const INACCURACY: i32 = 50;
+
+/// Loss in centipawns from which a move is a mistake
+pub const MISTAKE: i32 = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Classifies a move by the centipawns it lost
+    pub fn from_loss(loss: i32) -> Option<Judgement> {
+        if loss >= engine::BLUNDER {
+            Some(Judgement::Blunder)
+        } else if loss >= MISTAKE {
+            Some(Judgement::Mistake)
+        } else if loss >= INACCURACY {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// The move suffix used in annotated games
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+impl Display for Judgement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Judgement::Inaccuracy => write!(f, "inaccuracy"),
+            Judgement::Mistake => write!(f, "mistake"),
+            Judgement::Blunder => write!(f, "blunder"),
+        }
+    }
+}
+
+/// A move of the game with the engine's verdict on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedMove {
+    pub m: Move,
+    pub san: String,
+    pub color: Color,
+    /// The engine's preferred move in SAN, when it differs from the one played
+    pub best: Option<String>,
+    pub analysis: MoveAnalysis,
+    pub judgement: Option<Judgement>,
+}
+
+/// The annotated moves of a game with the final result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// The FEN of the position the moves start from
+    pub start: String,
+    pub moves: Vec<AnnotatedMove>,
+    pub result: GameResult,
+}
+
+impl Report {
+    /// Counts the inaccuracies, mistakes and blunders of one side
+    pub fn count(&self, color: Color, judgement: Judgement) -> usize {
+        self.moves
+            .iter()
+            .filter(|m| m.color == color && m.judgement == Some(judgement))
+            .count()
+    }
+
+    /// Returns the evaluation before the first move and after every move, in
+    /// centipawns from white's point of view
+    pub fn scores(&self) -> Vec<i32> {
+        let first = self.moves.first().map(|m| m.analysis.before.score);
+
+        first
+            .into_iter()
+            .chain(self.moves.iter().map(|m| m.analysis.after.score))
+            .collect()
+    }
+
+    /// Returns the ply of the first move, see `pgn::start_ply`
+    fn first_ply(&self) -> usize {
+        Board::try_from_fen(&self.start).map_or(0, |board| pgn::start_ply(&board))
+    }
+
+    /// Names the move that led to score `ply` of `scores`, e.g. `14... Nxd4`
+    fn move_label(&self, ply: usize) -> String {
+        let m = &self.moves[ply - 1];
+        let number = (self.first_ply() + ply - 1) / 2 + 1;
+
+        match m.color {
+            Color::White => format!("{}. {}", number, m.san),
+            Color::Black => format!("{}... {}", number, m.san),
+        }
+    }
+
+    /// Returns the `count` largest swings in the evaluation, each with the
+    /// move that caused it and the change in pawns, e.g. `14... Nxd4 -3.2`
+    pub fn swings(&self, count: usize) -> Vec<String> {
+        graph::swings(&self.scores(), count)
+            .into_iter()
+            .map(|(ply, change)| format!("{} {:+.1}", self.move_label(ply), change as f64 / 100.0))
+            .collect()
+    }
+
+    /// Draws the evaluation over the game as bars, white's advantage up and
+    /// black's down, with the largest swings marked underneath
+    pub fn graph(&self, height: usize) -> Vec<String> {
+        let scores = self.scores();
+        let mut lines = graph::bars(&scores, height);
+
+        let mut marks = vec![' '; scores.len()];
+        for (ply, _) in graph::swings(&scores, 3) {
+            marks[ply] = '^';
+        }
+        lines.push(marks.into_iter().collect());
+        lines.push(self.swings(3).join(", "));
+
+        lines
+    }
+
+    /// Writes the game as PGN with an `[%eval]` comment after every move
+    pub fn to_pgn(&self) -> String {
+        let moves: Vec<PgnMove> = self
+            .moves
+            .iter()
+            .map(|m| {
+                let mut comment = Vec::new();
+
+                // nothing to evaluate once the game is mated
+                if m.analysis.after.mate_in() != Some(0) {
+                    comment.push(format!("[%eval {}]", eval_comment(&m.analysis.after)));
+                }
+
+                if let Some(judgement) = m.judgement {
+                    match &m.best {
+                        Some(best) => comment.push(format!("{}, best was {}", judgement, best)),
+                        None => comment.push(judgement.to_string()),
+                    }
+                }
+
+                PgnMove {
+                    san: format!("{}{}", m.san, m.judgement.map_or("", |j| j.symbol())),
+                    nags: Vec::new(),
+                    comment: Some(comment.join(" ")).filter(|c| !c.is_empty()),
+                    variations: Vec::new(),
+                }
+            })
+            .collect();
+
+        let mut tags = vec![
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Annotator", "chess-lib"),
+        ];
+        if self.start != DEFAULT_BOARD {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", &self.start));
+        }
+
+        pgn::write(&tags, &moves, self.first_ply(), self.result)
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        let file = File::create(filename)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(self.to_pgn().as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Formats an evaluation the way `[%eval]` expects, in pawns or as `#N`
+fn eval_comment(evaluation: &Evaluation) -> String {
+    match evaluation.mate_in() {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:.2}", evaluation.score as f64 / 100.0),
+    }
+}
+
+/// Replays the moves from the starting position and judges every one of them
+pub fn annotate<B: Backend + ?Sized>(moves: &[Move], engine: &mut B) -> Result<Report, Error> {
+    annotate_from(Board::default_board()?, moves, engine)
+}
+
+/// Replays the moves from the given position and judges every one of them
+pub fn annotate_from<B: Backend + ?Sized>(
+    mut board: Board,
+    moves: &[Move],
+    engine: &mut B,
+) -> Result<Report, Error> {
+    let start = board.to_fen();
+    let mut before = engine.evaluate(&board)?;
+    let mut annotated = Vec::new();
+
+    for m in moves {
+        let san = pgn::san(&board, m)?;
+        let color = board.turn();
+
+        let best = match before.line.first() {
+            Some(best) if best != m => Some(pgn::san(&board, best)?),
+            _ => None,
+        };
+
+        board.move_piece(&m.to_string())?;
+
+        let after = engine.evaluate(&board)?;
+        let analysis = MoveAnalysis::new(color, before, after.clone());
+
+        annotated.push(AnnotatedMove {
+            m: *m,
+            san,
+            color,
+            best,
+            judgement: Judgement::from_loss(analysis.loss),
+            analysis,
+        });
+
+        before = after;
+    }
+
+    Ok(Report {
+        start,
+        moves: annotated,
+        result: board.result(),
+    })
+}
+
+/// Annotates a game written by `Board::save` or `Game::save`
+pub fn annotate_file<B: Backend + ?Sized>(filename: &str, engine: &mut B) -> Result<Report, Error> {
+    let game = Game::load(filename)?;
+    let moves: Vec<Move> = game.history().iter().map(|entry| entry.m).collect();
+
+    annotate_from(Board::try_from_fen(game.start_fen())?, &moves, engine)
+}
@@ -0,0 +1,117 @@
+//! Moves told as full sentences, e.g. "White knight from g1 to f3, check",
+//! for players using a screen reader or a text to speech program
+
+use std::{io::Write, process::Stdio, thread};
+
+use crate::{
+    chess::{Class, Color, Error, Status},
+    game::GameResult,
+    geometry::SquareGeometry,
+    mover::MoveOutcome,
+    notify::shell,
+};
+
+fn name(class: Class) -> String {
+    format!("{:?}", class).to_lowercase()
+}
+
+fn side(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+/// Describes a move in a sentence, with the check, mate or end of the game
+/// it brings
+pub fn describe(outcome: &MoveOutcome) -> String {
+    let m = outcome.m;
+    let from = (m.from_file, m.from_rank).name();
+    let to = (m.to_file, m.to_rank).name();
+
+    let mut sentence = match (m.drop, outcome.castled) {
+        (Some(class), _) => format!("{} drops a {} on {}", side(outcome.color), name(class), to),
+        (None, true) => match m.to_file {
+            6 => format!("{} castles kingside", side(outcome.color)),
+            _ => format!("{} castles queenside", side(outcome.color)),
+        },
+        (None, false) => {
+            let mut sentence = format!(
+                "{} {} from {}",
+                side(outcome.color),
+                name(outcome.piece),
+                from
+            );
+            match outcome.capture {
+                Some(_) if outcome.en_passant => {
+                    sentence.push_str(&format!(" takes the pawn en passant on {}", to))
+                }
+                Some(victim) => {
+                    sentence.push_str(&format!(" takes {} on {}", name(victim.class), to))
+                }
+                None => sentence.push_str(&format!(" to {}", to)),
+            }
+            sentence
+        }
+    };
+
+    if let Some(class) = outcome.promotion {
+        sentence.push_str(&format!(" and promotes to {}", name(class)));
+    }
+
+    match outcome.status {
+        Status::Check => sentence.push_str(", check"),
+        Status::Checkmate => sentence.push_str(", checkmate"),
+        Status::Stalemate => sentence.push_str(", stalemate"),
+        Status::Ongoing => {}
+    }
+
+    // mate and stalemate already tell how a standard game ended
+    let told = matches!(outcome.status, Status::Checkmate | Status::Stalemate);
+    match outcome.result {
+        GameResult::Ongoing => {}
+        GameResult::Draw if !told => sentence.push_str(", draw"),
+        GameResult::WhiteWins if outcome.status != Status::Checkmate => {
+            sentence.push_str(", White wins")
+        }
+        GameResult::BlackWins if outcome.status != Status::Checkmate => {
+            sentence.push_str(", Black wins")
+        }
+        _ => {}
+    }
+
+    sentence
+}
+
+/// Where move announcements go
+#[derive(Debug, Clone, PartialEq)]
+pub enum Announcer {
+    /// Along with the answer to the move
+    Print,
+    /// Piped into a text to speech command such as `espeak`, which runs in the
+    /// background
+    Command(String),
+}
+
+impl Announcer {
+    /// Announces a move, returning the sentence when it is to be printed
+    pub fn announce(&self, outcome: &MoveOutcome) -> Result<Option<String>, Error> {
+        let sentence = describe(outcome);
+
+        match self {
+            Announcer::Print => Ok(Some(sentence)),
+            Announcer::Command(command) => {
+                let mut child = shell(command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    writeln!(stdin, "{}", sentence)?;
+                }
+                thread::spawn(move || child.wait());
+
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,560 @@
+use std::{fmt::Display, fs::File, io::BufReader};
+
+use crate::{
+    chess::{Board, Color, Error, DEFAULT_BOARD},
+    engine::{Backend, Engine, Limits, StopToken},
+    epd,
+    fen::ToFen,
+    game::{Game, GameResult, Termination},
+    mover::Move,
+    pgn::{self, san, PgnMove, PgnReader},
+};
+
+/// Games still going after this many plies are scored as draws
+const MAX_PLIES: usize = 400;
+
+/// An engine configuration taking part in a match
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contender {
+    pub name: String,
+    pub engine: Engine,
+    pub limits: Limits,
+}
+
+impl Contender {
+    /// Creates a contender searching to the engine's own depth
+    pub fn new(name: &str, engine: Engine) -> Contender {
+        Contender {
+            name: name.to_string(),
+            engine,
+            limits: Limits::depth(engine.depth()),
+        }
+    }
+}
+
+/// Rules for deciding match games early, from the scores the engines give
+/// their moves. A move count of zero turns a rule off.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Adjudication {
+    /// Centipawns both engines have to see for the same side to win
+    pub win_score: i32,
+    /// Moves in a row each engine has to see the win for
+    pub win_moves: usize,
+    /// Centipawns within which a position counts as even
+    pub draw_score: i32,
+    /// Moves in a row each engine has to see it even, without a capture or
+    /// pawn move, for a draw
+    pub draw_moves: usize,
+    /// The move number before which no draw is adjudicated
+    pub draw_after: usize,
+}
+
+impl Default for Adjudication {
+    fn default() -> Adjudication {
+        Adjudication {
+            win_score: 1000,
+            win_moves: 3,
+            draw_score: 10,
+            draw_moves: 8,
+            draw_after: 40,
+        }
+    }
+}
+
+impl Adjudication {
+    /// Returns the result the scores of every move so far call for, the
+    /// latest last and from white's point of view
+    pub fn judge(&self, scores: &[i32], board: &Board) -> Option<GameResult> {
+        // both engines have their moves in the last plies
+        let last = |moves: usize| scores.len().checked_sub(2 * moves).map(|i| &scores[i..]);
+
+        if let Some(window) = last(self.win_moves).filter(|_| self.win_moves > 0) {
+            if window.iter().all(|score| *score >= self.win_score) {
+                return Some(GameResult::WhiteWins);
+            }
+            if window.iter().all(|score| *score <= -self.win_score) {
+                return Some(GameResult::BlackWins);
+            }
+        }
+
+        let shuffling = self.draw_moves > 0
+            && board.fullmove_number() > self.draw_after
+            && board.halfmove_clock() >= 2 * self.draw_moves;
+        if let Some(window) = last(self.draw_moves).filter(|_| shuffling) {
+            if window.iter().all(|score| score.abs() <= self.draw_score) {
+                return Some(GameResult::Draw);
+            }
+        }
+
+        None
+    }
+}
+
+/// A game played in a match, with its moves in SAN for writing the PGN
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchGame {
+    pub white: String,
+    pub black: String,
+    pub start: String,
+    pub moves: Vec<String>,
+    pub result: GameResult,
+    /// How the game ended, none when it ran into the ply limit
+    pub termination: Option<Termination>,
+}
+
+impl MatchGame {
+    pub fn to_pgn(&self, round: usize) -> String {
+        let round = round.to_string();
+        let mut tags = vec![
+            ("Event", "Engine match"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", round.as_str()),
+            ("White", self.white.as_str()),
+            ("Black", self.black.as_str()),
+        ];
+
+        if self.start != DEFAULT_BOARD {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", self.start.as_str()));
+        }
+
+        let termination = self.termination.map(|t| t.pgn_tags()).unwrap_or_default();
+        for (name, value) in &termination {
+            tags.push((name, value));
+        }
+
+        let moves: Vec<PgnMove> = self
+            .moves
+            .iter()
+            .map(|san| PgnMove {
+                san: san.clone(),
+                nags: Vec::new(),
+                comment: None,
+                variations: Vec::new(),
+            })
+            .collect();
+
+        // the start was played from, so it is a valid FEN
+        let ply = Board::try_from_fen(&self.start).map_or(0, |board| pgn::start_ply(&board));
+        pgn::write(&tags, &moves, ply, self.result)
+    }
+}
+
+/// How `simulate` plays a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulateOptions {
+    /// The FEN of the position the game starts from
+    pub start: String,
+    /// Limits for each side's searches, none to use the engine's own
+    pub white_limits: Option<Limits>,
+    pub black_limits: Option<Limits>,
+    /// Plies after which a game still going is scored as a draw
+    pub max_plies: usize,
+    pub adjudication: Option<Adjudication>,
+}
+
+impl Default for SimulateOptions {
+    fn default() -> SimulateOptions {
+        SimulateOptions {
+            start: DEFAULT_BOARD.to_string(),
+            white_limits: None,
+            black_limits: None,
+            max_plies: MAX_PLIES,
+            adjudication: None,
+        }
+    }
+}
+
+/// A game played by `simulate`, with the score each engine gave its move
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub white: String,
+    pub black: String,
+    pub start: String,
+    pub moves: Vec<Move>,
+    pub san: Vec<String>,
+    /// The score of every move, from white's point of view
+    pub scores: Vec<i32>,
+    pub result: GameResult,
+    /// How the game ended, none when it ran into the ply limit
+    pub termination: Option<Termination>,
+}
+
+impl GameRecord {
+    pub fn to_pgn(&self) -> String {
+        MatchGame::from(self.clone()).to_pgn(1)
+    }
+}
+
+impl From<GameRecord> for MatchGame {
+    fn from(record: GameRecord) -> MatchGame {
+        MatchGame {
+            white: record.white,
+            black: record.black,
+            start: record.start,
+            moves: record.san,
+            result: record.result,
+            termination: record.termination,
+        }
+    }
+}
+
+/// Plays a whole game between two engines without any input or output,
+/// ending it early when the adjudication rules call it
+pub fn simulate(
+    white: &mut dyn Backend,
+    black: &mut dyn Backend,
+    options: &SimulateOptions,
+) -> Result<GameRecord, Error> {
+    let mut game = Game::from_board(Board::try_from_fen(&options.start)?);
+    game.set_players(&white.name(), &black.name());
+
+    let mut record = GameRecord {
+        white: white.name(),
+        black: black.name(),
+        start: options.start.clone(),
+        moves: Vec::new(),
+        san: Vec::new(),
+        scores: Vec::new(),
+        result: GameResult::Ongoing,
+        termination: None,
+    };
+    let mut adjudicated = None;
+
+    while !game.is_over() && record.moves.len() < options.max_plies {
+        let (player, limits): (&mut dyn Backend, _) = match game.board().turn() {
+            Color::White => (&mut *white, options.white_limits),
+            Color::Black => (&mut *black, options.black_limits),
+        };
+
+        let limits = limits.unwrap_or_else(|| player.limits());
+        let evaluation = player.analyze(game.board(), &limits, &StopToken::new())?;
+
+        // the engine only comes back empty handed without legal moves
+        let Some(m) = evaluation.line.first().copied() else {
+            break;
+        };
+
+        record.san.push(san(game.board(), &m)?);
+        record.moves.push(m);
+        record.scores.push(evaluation.score);
+        game.move_piece(&m.to_string())?;
+
+        if game.is_over() {
+            break;
+        }
+        let judged = options
+            .adjudication
+            .and_then(|a| a.judge(&record.scores, game.board()));
+        if let Some(result) = judged {
+            adjudicated = Some(result);
+            break;
+        }
+    }
+
+    (record.result, record.termination) = match (adjudicated, game.result()) {
+        (Some(result), _) => (result, Some(Termination::Adjudication)),
+        (None, GameResult::Ongoing) => (GameResult::Draw, None),
+        (None, result) => (result, game.termination()),
+    };
+
+    Ok(record)
+}
+
+/// Plays one game between two contenders from the given position, ending
+/// it early when the adjudication rules call it
+pub fn play_game(
+    white: &Contender,
+    black: &Contender,
+    start: &str,
+    adjudication: Option<&Adjudication>,
+) -> Result<MatchGame, Error> {
+    let options = SimulateOptions {
+        start: start.to_string(),
+        white_limits: Some(white.limits),
+        black_limits: Some(black.limits),
+        adjudication: adjudication.copied(),
+        ..Default::default()
+    };
+
+    let mut record = simulate(
+        &mut white.engine.clone(),
+        &mut black.engine.clone(),
+        &options,
+    )?;
+    record.white = white.name.clone();
+    record.black = black.name.clone();
+
+    Ok(record.into())
+}
+
+/// One of the two hypotheses of a `Sprt`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Hypothesis {
+    /// The first contender is `elo0` stronger, usually no stronger at all
+    H0,
+    /// The first contender is `elo1` stronger
+    H1,
+}
+
+impl Display for Hypothesis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hypothesis::H0 => write!(f, "H0"),
+            Hypothesis::H1 => write!(f, "H1"),
+        }
+    }
+}
+
+/// A sequential probability ratio test of the first contender's Elo
+/// advantage, which ends a match as soon as the results are strong enough
+/// evidence for one of two hypotheses
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// The chance of accepting H1 when H0 is true
+    pub alpha: f64,
+    /// The chance of accepting H0 when H1 is true
+    pub beta: f64,
+}
+
+impl Sprt {
+    /// Creates a test between the two advantages with error rates of 5%
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Returns the log-likelihood ratios at which H0 and H1 are accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Returns the log-likelihood ratio of H1 against H0 for the results,
+    /// using the normal approximation of the score. Half a game of every
+    /// result is added, which keeps the variance from being zero while only
+    /// one kind of result has come up.
+    pub fn llr(&self, wins: usize, draws: usize, losses: usize) -> f64 {
+        if wins + draws + losses == 0 {
+            return 0.0;
+        }
+
+        let (wins, draws, losses) = (wins as f64 + 0.5, draws as f64 + 0.5, losses as f64 + 0.5);
+        let n = wins + draws + losses;
+        let (w, d) = (wins / n, draws / n);
+        let score = w + d / 2.0;
+        let variance = (w + d / 4.0 - score * score) / n;
+
+        let expected = |elo: f64| 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+        let (s0, s1) = (expected(self.elo0), expected(self.elo1));
+
+        (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Returns the hypothesis the results accept, if any yet
+    pub fn verdict(&self, wins: usize, draws: usize, losses: usize) -> Option<Hypothesis> {
+        let llr = self.llr(wins, draws, losses);
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            Some(Hypothesis::H1)
+        } else if llr <= lower {
+            Some(Hypothesis::H0)
+        } else {
+            None
+        }
+    }
+}
+
+/// The outcome of a match, counted from the first contender's side
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchReport {
+    pub first: String,
+    pub second: String,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub games: Vec<MatchGame>,
+    /// The test the match was played under, if any
+    pub sprt: Option<Sprt>,
+}
+
+impl MatchReport {
+    /// Returns the log-likelihood ratio of the match's test
+    pub fn llr(&self) -> Option<f64> {
+        self.sprt
+            .map(|sprt| sprt.llr(self.wins, self.draws, self.losses))
+    }
+
+    /// Returns the hypothesis the match's test has accepted, if any
+    pub fn verdict(&self) -> Option<Hypothesis> {
+        self.sprt
+            .and_then(|sprt| sprt.verdict(self.wins, self.draws, self.losses))
+    }
+
+    /// Returns the first contender's share of the points, from 0 to 1
+    pub fn score(&self) -> f64 {
+        let games = self.games.len().max(1) as f64;
+        (self.wins as f64 + self.draws as f64 / 2.0) / games
+    }
+
+    /// Estimates the first contender's Elo advantage along with the margin
+    /// of its 95% confidence interval. There is no estimate when either side
+    /// scored every point.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let n = self.games.len() as f64;
+        let p = self.score();
+
+        if self.games.is_empty() || p <= 0.0 || p >= 1.0 {
+            return None;
+        }
+
+        let variance = (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * p.powi(2))
+            / n;
+        let error = 1.96 * (variance / n).sqrt();
+
+        let elo = |p: f64| -400.0 * (1.0 / p.clamp(0.001, 0.999) - 1.0).log10();
+        let margin = (elo(p + error) - elo(p - error)) / 2.0;
+
+        Some((elo(p), margin))
+    }
+
+    /// Writes every game as one PGN database
+    pub fn to_pgn(&self) -> String {
+        self.games
+            .iter()
+            .enumerate()
+            .map(|(i, game)| game.to_pgn(i + 1))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        std::fs::write(filename, self.to_pgn())?;
+        Ok(())
+    }
+}
+
+impl Display for MatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vs {}: +{} ={} -{} ({:.1}%)",
+            self.first,
+            self.second,
+            self.wins,
+            self.draws,
+            self.losses,
+            self.score() * 100.0
+        )?;
+
+        match self.elo() {
+            Some((elo, margin)) => write!(f, ", Elo {:+.0} ± {:.0}", elo, margin)?,
+            None => write!(f, ", Elo n/a")?,
+        }
+
+        if let (Some(sprt), Some(llr)) = (self.sprt, self.llr()) {
+            let (lower, upper) = sprt.bounds();
+            write!(f, ", LLR {:.2} ({:.2}, {:.2})", llr, lower, upper)?;
+        }
+        if let Some(hypothesis) = self.verdict() {
+            write!(f, ", {} accepted", hypothesis)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the positions of an opening suite: an EPD file with a position on
+/// every line, or a PGN file whose games stop where their openings end.
+/// Files ending in `.pgn` are read as PGN.
+pub fn load_openings(filename: &str) -> Result<Vec<String>, Error> {
+    if filename.to_lowercase().ends_with(".pgn") {
+        let reader = PgnReader::new(BufReader::new(File::open(filename)?));
+        return reader.map(|game| Ok(game?.board().to_fen())).collect();
+    }
+
+    epd::load(filename)?
+        .iter()
+        .map(|record| Ok(record.board()?.to_fen()))
+        .collect()
+}
+
+/// How a match is played
+#[derive(Default)]
+pub struct MatchOptions<'a> {
+    /// Opening positions, each played twice, once with either contender as
+    /// white. Without any every game starts from the default position.
+    pub openings: &'a [&'a str],
+    pub adjudication: Option<Adjudication>,
+    /// Ends the match early once the test accepts a hypothesis
+    pub sprt: Option<Sprt>,
+    /// Called with the standings after every game
+    pub progress: Option<&'a mut dyn FnMut(&MatchReport)>,
+}
+
+/// Plays a match of up to `games` games, alternating colors
+pub fn play_match(
+    first: &Contender,
+    second: &Contender,
+    games: usize,
+    mut options: MatchOptions,
+) -> Result<MatchReport, Error> {
+    let mut report = MatchReport {
+        first: first.name.clone(),
+        second: second.name.clone(),
+        wins: 0,
+        draws: 0,
+        losses: 0,
+        games: Vec::new(),
+        sprt: options.sprt,
+    };
+    let adjudication = options.adjudication.as_ref();
+
+    for i in 0..games {
+        let start = match options.openings.len() {
+            0 => DEFAULT_BOARD,
+            n => options.openings[(i / 2) % n],
+        };
+
+        let first_is_white = i % 2 == 0;
+        let game = match first_is_white {
+            true => play_game(first, second, start, adjudication)?,
+            false => play_game(second, first, start, adjudication)?,
+        };
+
+        let first_color = if first_is_white {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        match game.result {
+            GameResult::Draw => report.draws += 1,
+            result if result == GameResult::win(first_color) => report.wins += 1,
+            _ => report.losses += 1,
+        }
+
+        report.games.push(game);
+
+        if let Some(progress) = options.progress.as_mut() {
+            progress(&report);
+        }
+        if report.verdict().is_some() {
+            break;
+        }
+    }
+
+    Ok(report)
+}
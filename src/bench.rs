@@ -0,0 +1,121 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    chess::{Board, Error},
+    engine::{Engine, Limits, StopToken},
+};
+
+/// Standard positions used to measure the move generator and the search
+pub const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+/// Counts the leaf nodes of the legal move tree to the given depth
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = board.legal_moves();
+
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .filter_map(|m| board.with_move(m).ok())
+        .map(|next| perft(&next, depth - 1))
+        .sum()
+}
+
+/// Measurements for a single position
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub fen: String,
+    pub perft_nodes: u64,
+    pub perft_time: Duration,
+    pub search_nodes: u64,
+    pub search_time: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub results: Vec<BenchResult>,
+}
+
+fn per_second(nodes: u64, time: Duration) -> u64 {
+    (nodes as f64 / time.as_secs_f64().max(f64::EPSILON)) as u64
+}
+
+impl Report {
+    pub fn perft_nodes_per_second(&self) -> u64 {
+        let nodes = self.results.iter().map(|r| r.perft_nodes).sum();
+        let time = self.results.iter().map(|r| r.perft_time).sum();
+        per_second(nodes, time)
+    }
+
+    pub fn search_nodes_per_second(&self) -> u64 {
+        let nodes = self.results.iter().map(|r| r.search_nodes).sum();
+        let time = self.results.iter().map(|r| r.search_time).sum();
+        per_second(nodes, time)
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, r) in self.results.iter().enumerate() {
+            writeln!(
+                f,
+                "position {}: perft {} nodes, {} nps; search {} nodes, {} nps",
+                i + 1,
+                r.perft_nodes,
+                per_second(r.perft_nodes, r.perft_time),
+                r.search_nodes,
+                per_second(r.search_nodes, r.search_time),
+            )?;
+        }
+
+        write!(
+            f,
+            "total: perft {} nps, search {} nps",
+            self.perft_nodes_per_second(),
+            self.search_nodes_per_second()
+        )
+    }
+}
+
+/// Runs perft and a fixed depth search on every standard position
+pub fn run(perft_depth: u32, search_depth: u32) -> Result<Report, Error> {
+    let engine = Engine::default();
+    let mut results = Vec::new();
+
+    for fen in POSITIONS {
+        let board = Board::try_from_fen(fen)?;
+
+        let start = Instant::now();
+        let perft_nodes = perft(&board, perft_depth);
+        let perft_time = start.elapsed();
+
+        let start = Instant::now();
+        let evaluation = engine.search(&board, &Limits::depth(search_depth), &StopToken::new());
+        let search_time = start.elapsed();
+
+        results.push(BenchResult {
+            fen: fen.to_string(),
+            perft_nodes,
+            perft_time,
+            search_nodes: evaluation.nodes,
+            search_time,
+        });
+    }
+
+    Ok(Report { results })
+}
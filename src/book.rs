@@ -0,0 +1,353 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+};
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    game::{Game, GameResult},
+    mover::Move,
+    pgn::PgnReader,
+    rng::Rng,
+    variant::Variant,
+    zobrist::polyglot_key,
+};
+
+/// The promotion pieces in the order of their Polyglot codes, from 1
+const POLYGLOT_PROMOTIONS: [Class; 4] = [Class::Knight, Class::Bishop, Class::Rook, Class::Queen];
+
+/// A single Polyglot book entry
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Entry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl Entry {
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+/// An opening book in the Polyglot `.bin` format
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    pub fn open(filename: &str) -> Result<Book, Error> {
+        let file = File::open(filename)?;
+        let mut reader = BufReader::new(file);
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Book::from_bytes(&data)
+    }
+
+    /// Reads a book from the raw file contents, 16 bytes per entry
+    pub fn from_bytes(data: &[u8]) -> Result<Book, Error> {
+        if !data.len().is_multiple_of(16) {
+            return Err(Error::InvalidInput);
+        }
+
+        let mut entries: Vec<Entry> = data
+            .chunks_exact(16)
+            .map(|chunk| Entry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect();
+
+        // books are supposed to be sorted, but lookups rely on it
+        entries.sort_by_key(|e| e.key);
+
+        Ok(Book { entries })
+    }
+
+    /// Creates a book from entries in any order
+    pub fn from_entries(mut entries: Vec<Entry>) -> Book {
+        entries.sort_by_key(|e| (e.key, std::cmp::Reverse(e.weight)));
+        Book { entries }
+    }
+
+    /// Writes the book in the Polyglot `.bin` format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|e| e.to_bytes()).collect()
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        std::fs::write(filename, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the raw entries stored for a position
+    pub fn entries(&self, board: &Board) -> &[Entry] {
+        let key = polyglot_key(board);
+
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+
+        &self.entries[start..end]
+    }
+
+    /// Returns the book moves for a position with their weights, heaviest
+    /// first
+    pub fn probe(&self, board: &Board) -> Vec<(Move, u16)> {
+        let mut moves: Vec<(Move, u16)> = self
+            .entries(board)
+            .iter()
+            .filter_map(|e| decode_move(board, e.raw_move).map(|m| (m, e.weight)))
+            .filter(|(m, _)| m.validate(board).is_ok())
+            .collect();
+
+        moves.sort_by_key(|(_, weight)| std::cmp::Reverse(*weight));
+
+        moves
+    }
+
+    /// Returns the book move with the highest weight
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        self.probe(board).first().map(|(m, _)| *m)
+    }
+
+    /// Picks a book move at random, as often as its weight says
+    pub fn pick(&self, board: &Board, rng: &mut Rng) -> Option<Move> {
+        let moves = self.probe(board);
+        let total: usize = moves.iter().map(|(_, weight)| *weight as usize).sum();
+        if total == 0 {
+            return moves.first().map(|(m, _)| *m);
+        }
+
+        let mut n = rng.below(total);
+        for (m, weight) in moves {
+            match n.checked_sub(weight as usize) {
+                Some(rest) => n = rest,
+                None => return Some(m),
+            }
+        }
+
+        None
+    }
+}
+
+/// Encodes a move for a book entry, writing castling as the king capturing
+/// its own rook. Drops can't be stored.
+pub fn encode_move(board: &Board, m: &Move) -> Option<u16> {
+    if m.is_drop() {
+        return None;
+    }
+
+    let is_king = board
+        .get_piece(m.from_file, m.from_rank)
+        .is_some_and(|p| p.class == Class::King);
+
+    let to_file = match (is_king, m.from_file, m.to_file) {
+        (true, 4, 6) => 7,
+        (true, 4, 2) => 0,
+        _ => m.to_file,
+    };
+
+    let promotion = match m.promotion {
+        None => 0,
+        Some(class) => POLYGLOT_PROMOTIONS.iter().position(|c| *c == class)? as u16 + 1,
+    };
+
+    Some(
+        (to_file as u16)
+            | (m.to_rank as u16) << 3
+            | (m.from_file as u16) << 6
+            | (m.from_rank as u16) << 9
+            | promotion << 12,
+    )
+}
+
+/// How the moves of a built book are weighted
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Weighting {
+    /// By how often the move was played
+    Frequency,
+    /// By the points the mover scored with it, two for a win and one for a
+    /// draw, so moves that only ever lost are left out
+    #[default]
+    Score,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Tally {
+    games: u32,
+    points: u32,
+}
+
+/// Collects the moves played in a game collection into an opening book
+pub struct BookBuilder {
+    weighting: Weighting,
+    max_plies: usize,
+    min_games: u32,
+    tallies: HashMap<(u64, u16), Tally>,
+}
+
+impl Default for BookBuilder {
+    fn default() -> BookBuilder {
+        BookBuilder::new()
+    }
+}
+
+impl BookBuilder {
+    /// Creates a builder that takes the first 24 plies of every game and
+    /// weights moves by score
+    pub fn new() -> BookBuilder {
+        BookBuilder {
+            weighting: Weighting::default(),
+            max_plies: 24,
+            min_games: 1,
+            tallies: HashMap::new(),
+        }
+    }
+
+    pub fn set_weighting(&mut self, weighting: Weighting) {
+        self.weighting = weighting;
+    }
+
+    /// Sets how many plies from the start of every game are taken
+    pub fn set_max_plies(&mut self, max_plies: usize) {
+        self.max_plies = max_plies;
+    }
+
+    /// Sets how many games a move has to appear in to make the book
+    pub fn set_min_games(&mut self, min_games: u32) {
+        self.min_games = min_games;
+    }
+
+    /// Adds the opening moves of a game. Games of other variants are
+    /// skipped, as Polyglot books only hold standard chess.
+    pub fn add_game(&mut self, game: &Game) -> Result<(), Error> {
+        if game.board().variant() != Variant::Standard {
+            return Ok(());
+        }
+
+        let points = |color: Color| match game.result() {
+            GameResult::Draw => 1,
+            result if result == GameResult::win(color) => 2,
+            _ => 0,
+        };
+
+        let positions = game.positions()?;
+
+        for (board, entry) in positions.iter().zip(game.history()).take(self.max_plies) {
+            let Some(raw_move) = encode_move(board, &entry.m) else {
+                continue;
+            };
+
+            let tally = self
+                .tallies
+                .entry((polyglot_key(board), raw_move))
+                .or_default();
+            tally.games += 1;
+            tally.points += points(board.turn());
+        }
+
+        Ok(())
+    }
+
+    /// Adds every game of a PGN database, skipping games that fail to parse.
+    /// Returns the number of games added.
+    pub fn add_pgn<R: BufRead>(&mut self, reader: R) -> Result<usize, Error> {
+        let mut added = 0;
+
+        for game in PgnReader::new(reader) {
+            let game = match game {
+                Ok(game) => game,
+                Err(e @ Error::Io(_)) => return Err(e),
+                Err(_) => continue,
+            };
+
+            self.add_game(&game)?;
+            added += 1;
+        }
+
+        Ok(added)
+    }
+
+    /// Builds the book. Weights are scaled down to fit in 16 bits when the
+    /// collection is large.
+    pub fn build(&self) -> Book {
+        let weight = |tally: &Tally| match self.weighting {
+            Weighting::Frequency => tally.games,
+            Weighting::Score => tally.points,
+        };
+
+        let kept: Vec<(&(u64, u16), u32)> = self
+            .tallies
+            .iter()
+            .filter(|(_, tally)| tally.games >= self.min_games)
+            .map(|(key, tally)| (key, weight(tally)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+
+        let heaviest = kept.iter().map(|(_, weight)| *weight).max().unwrap_or(0);
+        let scale = |weight: u32| match heaviest > u16::MAX as u32 {
+            true => (weight as u64 * u16::MAX as u64 / heaviest as u64).max(1) as u16,
+            false => weight as u16,
+        };
+
+        let entries = kept
+            .into_iter()
+            .map(|(&(key, raw_move), weight)| Entry {
+                key,
+                raw_move,
+                weight: scale(weight),
+                learn: 0,
+            })
+            .collect();
+
+        Book::from_entries(entries)
+    }
+}
+
+fn decode_move(board: &Board, raw: u16) -> Option<Move> {
+    let to_file = (raw & 0x7) as usize;
+    let to_rank = ((raw >> 3) & 0x7) as usize;
+    let from_file = ((raw >> 6) & 0x7) as usize;
+    let from_rank = ((raw >> 9) & 0x7) as usize;
+    let promotion = match (raw >> 12) & 0x7 {
+        0 => None,
+        code => Some(*POLYGLOT_PROMOTIONS.get(code as usize - 1)?),
+    };
+
+    // castling is stored as the king capturing its own rook
+    let is_king = board
+        .get_piece(from_file, from_rank)
+        .is_some_and(|p| p.class == Class::King);
+
+    let to_file = match (is_king, from_file, to_file) {
+        (true, 4, 7) => 6,
+        (true, 4, 0) => 2,
+        _ => to_file,
+    };
+
+    let m = Move::new(from_file, from_rank, to_file, to_rank);
+
+    Some(match promotion {
+        Some(class) => m.with_promotion(class),
+        None => m,
+    })
+}
@@ -2,38 +2,48 @@ use std::{
     fmt::Display,
     fs::File,
     io::{BufReader, BufWriter, Read, Write},
+    str::FromStr,
 };
 
-use crate::{fen::ToFen, mover::Move};
+use crate::{
+    fen::ToFen,
+    game::GameResult,
+    geometry::{ray, Direction, SquareGeometry},
+    mover::{parse_square, IllegalReason, Move, MoveOutcome},
+    variant::{Pocket, Variant},
+};
 
-pub const DEFAULT_BOARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+pub use crate::error::{Error, FenError, MoveError};
 
-#[derive(Debug)]
-pub enum Error {
-    InvalidInput,
-    InvalidFen(String),
-    InvalidMove(String),
-    SaveFailed(String),
-}
+pub const DEFAULT_BOARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
-impl From<std::io::Error> for Error {
-    fn from(e: std::io::Error) -> Self {
-        Error::SaveFailed(e.to_string())
-    }
+/// A square as file and rank, both counted from 0
+pub type Square = (usize, usize);
+
+pub(crate) const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+/// A set of squares with one bit per square, a1 first
+fn bit(file: usize, rank: usize) -> u64 {
+    1 << (rank * 8 + file)
 }
 
-impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::InvalidInput => write!(f, "Invalid input"),
-            Error::InvalidFen(fen) => write!(f, "Invalid FEN: {}", fen),
-            Error::InvalidMove(m) => write!(f, "Invalid move: {}", m),
-            Error::SaveFailed(file) => write!(f, "Failed to save game to file: {}", file),
-        }
-    }
+fn squares(set: u64) -> Vec<Square> {
+    (0..64)
+        .filter(|i| set & (1 << i) != 0)
+        .map(|i| (i % 8, i / 8))
+        .collect()
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Class {
     Pawn,
     Knight,
@@ -43,26 +53,49 @@ pub enum Class {
     King,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Color {
     White,
     Black,
 }
 
-impl ToString for Color {
-    fn to_string(&self) -> String {
+impl Color {
+    /// Returns the other color
+    pub fn opponent(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Color::White => "white".to_string(),
-            Color::Black => "black".to_string(),
+            Color::White => write!(f, "white"),
+            Color::Black => write!(f, "black"),
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Status {
+    Ongoing,
+    Check,
+    Checkmate,
+    Stalemate,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Piece {
     pub class: Class,
     pub color: Color,
+    /// How often the piece moved on this board. The rules don't depend on
+    /// it, as it is unknown for pieces placed from a FEN.
     pub moves: usize,
+    /// Whether the piece is a promoted pawn, which goes back into a pocket
+    /// as a pawn when it is captured in crazyhouse. FEN marks it with `~`.
+    pub promoted: bool,
 }
 
 impl Piece {
@@ -71,6 +104,7 @@ impl Piece {
             class,
             color,
             moves: 0,
+            promoted: false,
         }
     }
 }
@@ -106,42 +140,110 @@ impl ToFen for Piece {
     }
 }
 
-impl ToString for Piece {
-    fn to_string(&self) -> String {
-        match self.class {
+impl Display for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self.class {
             Class::Pawn => match self.color {
-                Color::White => "♙".to_string(),
-                Color::Black => "♟".to_string(),
+                Color::White => "♙",
+                Color::Black => "♟",
             },
             Class::Knight => match self.color {
-                Color::White => "♘".to_string(),
-                Color::Black => "♞".to_string(),
+                Color::White => "♘",
+                Color::Black => "♞",
             },
             Class::Bishop => match self.color {
-                Color::White => "♗".to_string(),
-                Color::Black => "♝".to_string(),
+                Color::White => "♗",
+                Color::Black => "♝",
             },
             Class::Rook => match self.color {
-                Color::White => "♖".to_string(),
-                Color::Black => "♜".to_string(),
+                Color::White => "♖",
+                Color::Black => "♜",
             },
             Class::Queen => match self.color {
-                Color::White => "♕".to_string(),
-                Color::Black => "♛".to_string(),
+                Color::White => "♕",
+                Color::Black => "♛",
             },
             Class::King => match self.color {
-                Color::White => "♔".to_string(),
-                Color::Black => "♚".to_string(),
+                Color::White => "♔",
+                Color::Black => "♚",
             },
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+/// How many pieces of each class a side has on the board
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MaterialCount {
+    pub pawns: u8,
+    pub knights: u8,
+    pub bishops: u8,
+    pub rooks: u8,
+    pub queens: u8,
+    pub kings: u8,
+}
+
+impl MaterialCount {
+    pub fn get(&self, class: Class) -> u8 {
+        match class {
+            Class::Pawn => self.pawns,
+            Class::Knight => self.knights,
+            Class::Bishop => self.bishops,
+            Class::Rook => self.rooks,
+            Class::Queen => self.queens,
+            Class::King => self.kings,
+        }
+    }
+
+    fn count_mut(&mut self, class: Class) -> &mut u8 {
+        match class {
+            Class::Pawn => &mut self.pawns,
+            Class::Knight => &mut self.knights,
+            Class::Bishop => &mut self.bishops,
+            Class::Rook => &mut self.rooks,
+            Class::Queen => &mut self.queens,
+            Class::King => &mut self.kings,
         }
     }
+
+    /// The number of pieces besides pawns and the king
+    pub fn pieces(&self) -> u8 {
+        self.knights + self.bishops + self.rooks + self.queens
+    }
+
+    pub fn total(&self) -> u8 {
+        self.pawns + self.pieces() + self.kings
+    }
+
+    /// The material in pawns, counting minor pieces as 3, rooks as 5 and
+    /// queens as 9
+    pub fn value(&self) -> u32 {
+        self.pawns as u32
+            + 3 * (self.knights + self.bishops) as u32
+            + 5 * self.rooks as u32
+            + 9 * self.queens as u32
+    }
 }
 
+#[derive(Clone)]
 pub struct Board {
     pieces: [[Option<Piece>; 8]; 8],
+    /// Piece counts per color, kept up to date as pieces are placed and
+    /// removed
+    material: [MaterialCount; 2],
+    /// Pieces checking the side to move, its pieces pinned to the king and
+    /// the squares that end a single check, refreshed after every move
+    checkers: u64,
+    pinned: u64,
+    evasions: u64,
     turn: Color,
     captured: Vec<Piece>,
     moves: Vec<String>,
+    /// The FEN the move list starts from, when it is not the variant's start
+    /// position
+    start: Option<String>,
+    last_move: Option<Move>,
 
     white_can_castle_kingside: bool,
     white_can_castle_queenside: bool,
@@ -153,6 +255,9 @@ pub struct Board {
 
     halfmove_clock: usize,
     fullmove_number: usize,
+
+    variant: Variant,
+    pockets: [Pocket; 2],
 }
 
 impl Board {
@@ -160,9 +265,15 @@ impl Board {
     pub fn new() -> Result<Board, Error> {
         let board = Board {
             pieces: [[Option::None; 8]; 8],
+            material: [MaterialCount::default(); 2],
+            checkers: 0,
+            pinned: 0,
+            evasions: 0,
             turn: Color::White,
             captured: Vec::new(),
             moves: Vec::new(),
+            start: None,
+            last_move: None,
             white_can_castle_kingside: true,
             white_can_castle_queenside: true,
             black_can_castle_kingside: true,
@@ -170,6 +281,8 @@ impl Board {
             en_passant: None,
             halfmove_clock: 0,
             fullmove_number: 1,
+            variant: Variant::Standard,
+            pockets: [Pocket::default(); 2],
         };
 
         Ok(board)
@@ -177,19 +290,51 @@ impl Board {
 
     /// Creates a board with default pieces
     pub fn default_board() -> Result<Board, Error> {
+        Board::try_from_fen(DEFAULT_BOARD)
+    }
+
+    /// Creates a board holding the position of a FEN
+    pub fn try_from_fen(fen: &str) -> Result<Board, Error> {
         let mut board = Board::new()?;
-        board.from_fen(DEFAULT_BOARD)?;
+        board.from_fen(fen)?;
         Ok(board)
     }
 
     fn clear_piece(&mut self, file: usize, rank: usize) {
-        self.pieces[file][rank] = Option::None;
+        if let Some(old) = self.pieces[file][rank].take() {
+            *self.material[old.color as usize].count_mut(old.class) -= 1;
+        }
     }
 
     fn set_piece(&mut self, piece: Piece, file: usize, rank: usize) {
+        self.clear_piece(file, rank);
+        *self.material[piece.color as usize].count_mut(piece.class) += 1;
         self.pieces[file][rank] = Option::Some(piece);
     }
 
+    fn revoke_castling(&mut self, file: usize, rank: usize) {
+        match (file, rank) {
+            (4, 0) => {
+                self.white_can_castle_kingside = false;
+                self.white_can_castle_queenside = false;
+            }
+            (7, 0) => self.white_can_castle_kingside = false,
+            (0, 0) => self.white_can_castle_queenside = false,
+            (4, 7) => {
+                self.black_can_castle_kingside = false;
+                self.black_can_castle_queenside = false;
+            }
+            (7, 7) => self.black_can_castle_kingside = false,
+            (0, 7) => self.black_can_castle_queenside = false,
+            _ => {}
+        }
+    }
+
+    /// Returns the pieces a side has on the board, without scanning it
+    pub fn material(&self, color: Color) -> MaterialCount {
+        self.material[color as usize]
+    }
+
     pub fn get_piece(&self, file: usize, rank: usize) -> Option<Piece> {
         self.pieces[file][rank]
     }
@@ -201,46 +346,476 @@ impl Board {
         }
     }
 
+    /// Returns the square a pawn can be captured on en passant, if any
+    pub fn en_passant(&self) -> Option<(usize, usize)> {
+        self.en_passant
+    }
+
+    /// Returns the en passant square only when a pawn of the side to move
+    /// stands next to it, which is when it makes a difference to the
+    /// position
+    pub fn capturable_en_passant(&self) -> Option<(usize, usize)> {
+        let (file, rank) = self.en_passant?;
+        let pawn_rank = match self.turn {
+            Color::White => rank - 1,
+            Color::Black => rank + 1,
+        };
+
+        [file.wrapping_sub(1), file + 1]
+            .into_iter()
+            .any(|f| {
+                f < 8
+                    && self
+                        .get_piece(f, pawn_rank)
+                        .is_some_and(|p| p.class == Class::Pawn && p.color == self.turn)
+            })
+            .then_some((file, rank))
+    }
+
+    pub fn can_castle_kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_can_castle_kingside,
+            Color::Black => self.black_can_castle_kingside,
+        }
+    }
+
+    pub fn can_castle_queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_can_castle_queenside,
+            Color::Black => self.black_can_castle_queenside,
+        }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Creates a board with the start position of the variant
+    pub fn start(variant: Variant) -> Result<Board, Error> {
+        let mut board = Board::new()?;
+        board.set_variant(variant);
+        board.from_fen(variant.start_position())?;
+        Ok(board)
+    }
+
+    /// Changes the rule set, emptying the pockets when the new variant has
+    /// no drops
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+
+        if !variant.has_drops() {
+            self.pockets = [Pocket::default(); 2];
+        }
+
+        self.refresh_checks();
+    }
+
+    /// Returns the pieces the given color holds in hand
+    pub fn pocket(&self, color: Color) -> &Pocket {
+        &self.pockets[color as usize]
+    }
+
     // a function that returns who's turn it is
     pub fn turn(&self) -> Color {
         self.turn
     }
 
-    pub fn move_piece(&mut self, data: &str) -> Result<(), Error> {
+    /// Returns the pieces captured on this board, in the order they were
+    /// taken
+    pub fn captured(&self) -> &[Piece] {
+        &self.captured
+    }
+
+    /// Returns the number of moves since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> usize {
+        self.halfmove_clock
+    }
+
+    /// Returns the number of the current full move, starting at 1
+    pub fn fullmove_number(&self) -> usize {
+        self.fullmove_number
+    }
+
+    /// Returns the most recently played move, if any
+    pub fn last_move(&self) -> Option<Move> {
+        self.last_move
+    }
+
+    /// Returns the squares the last move left and reached, the same square
+    /// twice for a drop
+    pub fn last_move_squares(&self) -> Option<[Square; 2]> {
+        self.last_move
+            .map(|m| [(m.from_file, m.from_rank), (m.to_file, m.to_rank)])
+    }
+
+    /// Returns the square of the side to move's king when it is in check
+    pub fn checked_king(&self) -> Option<Square> {
+        self.king_square(self.turn).filter(|_| self.is_check())
+    }
+
+    /// Returns the square the king of the given color is standing on
+    pub fn king_square(&self, color: Color) -> Option<(usize, usize)> {
+        for file in 0..8 {
+            for rank in 0..8 {
+                if let Some(piece) = self.get_piece(file, rank) {
+                    if piece.class == Class::King && piece.color == color {
+                        return Some((file, rank));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns true if the king of the given color is attacked. Always false
+    /// in variants where the king is an ordinary piece.
+    pub fn is_in_check(&self, color: Color) -> bool {
+        if !self.variant.has_royal_king() {
+            return false;
+        }
+
+        match self.king_square(color) {
+            Some((file, rank)) => self.is_attacked(file, rank, color.opponent()),
+            None => false,
+        }
+    }
+
+    /// Returns true if the side to move is in check
+    pub fn is_check(&self) -> bool {
+        self.checkers != 0
+    }
+
+    /// Returns the squares of the pieces giving check to the side to move
+    pub fn checkers(&self) -> Vec<Square> {
+        squares(self.checkers)
+    }
+
+    /// Returns the squares of the pieces of the side to move that are pinned
+    /// to their king
+    pub fn pinned(&self) -> Vec<Square> {
+        squares(self.pinned)
+    }
+
+    pub fn is_pinned(&self, file: usize, rank: usize) -> bool {
+        self.pinned & bit(file, rank) != 0
+    }
+
+    /// Finds the checkers and pins of the side to move again, after the
+    /// position changed
+    fn refresh_checks(&mut self) {
+        self.checkers = 0;
+        self.pinned = 0;
+        self.evasions = 0;
+
+        if !self.variant.has_royal_king() {
+            return;
+        }
+
+        let Some((file, rank)) = self.king_square(self.turn) else {
+            return;
+        };
+
+        let them = self.turn.opponent();
+        let (file, rank) = (file as i32, rank as i32);
+
+        let enemy = |f: i32, r: i32, class: Class| {
+            (0..8).contains(&f)
+                && (0..8).contains(&r)
+                && self
+                    .get_piece(f as usize, r as usize)
+                    .is_some_and(|p| p.color == them && p.class == class)
+        };
+
+        let pawn_rank = match self.turn {
+            Color::White => rank + 1,
+            Color::Black => rank - 1,
+        };
+
+        let mut jumps = Vec::new();
+        for f in [file - 1, file + 1] {
+            if enemy(f, pawn_rank, Class::Pawn) {
+                jumps.push((f, pawn_rank));
+            }
+        }
+        for (df, dr) in KNIGHT_OFFSETS {
+            if enemy(file + df, rank + dr, Class::Knight) {
+                jumps.push((file + df, rank + dr));
+            }
+        }
+
+        for (f, r) in jumps {
+            self.checkers |= bit(f as usize, r as usize);
+            self.evasions |= bit(f as usize, r as usize);
+        }
+
+        for direction in Direction::ALL {
+            let (df, dr) = direction.offset();
+            let diagonal = direction.is_diagonal();
+            let mut line = 0;
+            let mut own = None;
+            let (mut f, mut r) = (file + df, rank + dr);
+
+            while (0..8).contains(&f) && (0..8).contains(&r) {
+                let (fu, ru) = (f as usize, r as usize);
+                line |= bit(fu, ru);
+
+                if let Some(p) = self.get_piece(fu, ru) {
+                    if p.color == self.turn {
+                        if own.is_some() {
+                            break;
+                        }
+                        own = Some(bit(fu, ru));
+                    } else {
+                        let slides = match p.class {
+                            Class::Queen => true,
+                            Class::Rook => !diagonal,
+                            Class::Bishop => diagonal,
+                            _ => false,
+                        };
+
+                        match (slides, own) {
+                            (true, None) => {
+                                self.checkers |= bit(fu, ru);
+                                self.evasions |= line;
+                            }
+                            (true, Some(pinned)) => self.pinned |= pinned,
+                            _ => {}
+                        }
+
+                        break;
+                    }
+                }
+
+                f += df;
+                r += dr;
+            }
+        }
+    }
+
+    /// Returns the squares of the pieces of the given color attacking a
+    /// square, nearest first along each line
+    pub fn attackers(&self, square: Square, by: Color) -> Vec<Square> {
+        let is = |at: Square, classes: &[Class]| {
+            self.get_piece(at.0, at.1)
+                .is_some_and(|p| p.color == by && classes.contains(&p.class))
+        };
+
+        let mut attackers = Vec::new();
+
+        // pawns attack diagonally forward, so look one rank behind the square
+        let behind = match by {
+            Color::White => [Direction::SouthWest, Direction::SouthEast],
+            Color::Black => [Direction::NorthWest, Direction::NorthEast],
+        };
+        for direction in behind {
+            if let Some(at) = square.step(direction).filter(|at| is(*at, &[Class::Pawn])) {
+                attackers.push(at);
+            }
+        }
+
+        for (df, dr) in KNIGHT_OFFSETS {
+            let (f, r) = (square.0 as i32 + df, square.1 as i32 + dr);
+            if (0..8).contains(&f)
+                && (0..8).contains(&r)
+                && is((f as usize, r as usize), &[Class::Knight])
+            {
+                attackers.push((f as usize, r as usize));
+            }
+        }
+
+        for direction in Direction::ALL {
+            let sliders = match direction.is_diagonal() {
+                true => [Class::Bishop, Class::Queen],
+                false => [Class::Rook, Class::Queen],
+            };
+
+            let first = ray(square, direction)
+                .into_iter()
+                .find(|at| self.get_piece(at.0, at.1).is_some());
+
+            if let Some(at) = first {
+                let adjacent = square.chebyshev_distance(at) == 1;
+                if is(at, &sliders) || (adjacent && is(at, &[Class::King])) {
+                    attackers.push(at);
+                }
+            }
+        }
+
+        attackers
+    }
+
+    /// Returns true if any piece of the given color attacks the square
+    pub fn is_attacked(&self, file: usize, rank: usize, by: Color) -> bool {
+        let file = file as i32;
+        let rank = rank as i32;
+
+        let piece_at = |f: i32, r: i32| -> Option<Piece> {
+            if !(0..8).contains(&f) || !(0..8).contains(&r) {
+                return None;
+            }
+            self.get_piece(f as usize, r as usize)
+                .filter(|p| p.color == by)
+        };
+
+        // pawns attack diagonally forward, so look one rank behind the square
+        let pawn_rank = match by {
+            Color::White => rank - 1,
+            Color::Black => rank + 1,
+        };
+
+        for f in [file - 1, file + 1] {
+            if let Some(p) = piece_at(f, pawn_rank) {
+                if p.class == Class::Pawn {
+                    return true;
+                }
+            }
+        }
+
+        for (df, dr) in KNIGHT_OFFSETS {
+            if let Some(p) = piece_at(file + df, rank + dr) {
+                if p.class == Class::Knight {
+                    return true;
+                }
+            }
+        }
+
+        for direction in Direction::ALL {
+            let (df, dr) = direction.offset();
+            let diagonal = direction.is_diagonal();
+            let mut f = file + df;
+            let mut r = rank + dr;
+            let mut distance = 1;
+
+            while (0..8).contains(&f) && (0..8).contains(&r) {
+                if let Some(p) = self.get_piece(f as usize, r as usize) {
+                    if p.color == by {
+                        let attacks = match p.class {
+                            Class::King => distance == 1,
+                            Class::Queen => true,
+                            Class::Rook => !diagonal,
+                            Class::Bishop => diagonal,
+                            _ => false,
+                        };
+
+                        if attacks {
+                            return true;
+                        }
+                    }
+
+                    // any piece blocks the line
+                    break;
+                }
+
+                f += df;
+                r += dr;
+                distance += 1;
+            }
+        }
+
+        false
+    }
+
+    pub fn move_piece(&mut self, data: &str) -> Result<MoveOutcome, Error> {
+        // playing a move only checks the piece's own rules, so the king's
+        // safety is checked here
+        let m = Move::parse(self, data)?;
+        m.validate(self)?;
+        if !self.is_legal(&m) {
+            let reason = self.why_illegal(&m).unwrap_or(IllegalReason::KingInCheck);
+            return Err(MoveError::KingInCheck(reason).into());
+        }
+
+        // a position that was broken to begin with, e.g. one set up without
+        // kings, is left alone
+        #[cfg(debug_assertions)]
+        let consistent = self.inconsistency().is_none();
+
+        let mut outcome = self.play(&m.to_string())?;
+
+        #[cfg(debug_assertions)]
+        if consistent {
+            self.assert_consistent();
+        }
+
+        outcome.check = self.is_in_check(self.turn);
+        outcome.status = self.status();
+        outcome.result = self.variant.result(outcome.status, self.turn);
+
+        Ok(outcome)
+    }
+
+    /// Plays a move, leaving out what it takes a look at the new position to
+    /// find out: whether it gives check or ends the game
+    fn play(&mut self, data: &str) -> Result<MoveOutcome, Error> {
         let data = data.trim();
 
         let m: Move = data.try_into()?;
 
         // validate move against board status
-        m.validate(&self)?;
+        m.validate(self)?;
 
         self.halfmove_clock += 1;
 
+        let mut outcome = MoveOutcome {
+            m,
+            color: self.turn,
+            piece: Class::Pawn,
+            capture: None,
+            en_passant: false,
+            castled: false,
+            promotion: None,
+            check: false,
+            status: Status::Ongoing,
+            result: GameResult::Ongoing,
+        };
+
+        if let Some(class) = m.drop {
+            self.pockets[self.turn as usize].take(class);
+            self.set_piece(Piece::new(class, self.turn), m.to_file, m.to_rank);
+            self.en_passant = None;
+
+            self.moves.push(data.to_string());
+            self.last_move = Some(m);
+            self.switch_turn();
+
+            outcome.piece = class;
+            return Ok(outcome);
+        }
+
         let mut piece = self.get_piece(m.from_file, m.from_rank).unwrap();
+        outcome.piece = piece.class;
 
         // check if the destination is an en passnt capture
-        if self.is_en_passant(m.to_file, m.to_rank) {
+        if piece.class == Class::Pawn && self.is_en_passant(m.to_file, m.to_rank) {
             let rank = match piece.color {
                 Color::White => m.to_rank - 1,
                 Color::Black => m.to_rank + 1,
             };
 
+            let captured = self.get_piece(m.to_file, rank).unwrap();
+
             self.halfmove_clock = 0;
-            self.captured.push(self.get_piece(m.to_file, rank).unwrap());
+            self.capture(captured);
             self.clear_piece(m.to_file, rank);
+
+            outcome.capture = Some(captured);
+            outcome.en_passant = true;
         }
 
         let target = self.get_piece(m.to_file, m.to_rank);
 
         if let Some(capture) = target {
             self.halfmove_clock = 0;
-            self.captured.push(capture);
+            self.capture(capture);
+            outcome.capture = Some(capture);
         }
 
-        // set en passant if pawn moves two spaces
+        // set en passant if pawn moves two spaces, any other move clears it
+        self.en_passant = None;
         if piece.class == Class::Pawn {
             self.halfmove_clock = 0;
-            if m.distance() == 2 {
+            if m.from_file == m.to_file && m.distance() == 2 {
                 let rank = if piece.color == Color::White {
                     m.to_rank - 1
                 } else {
@@ -248,13 +823,13 @@ impl Board {
                 };
 
                 self.en_passant = Some((m.to_file, rank));
-            } else {
-                self.en_passant = None;
             }
         }
 
         // check if the move is a castle
-        if piece.class == Class::King {
+        if piece.class == Class::King && m.from_file.abs_diff(m.to_file) == 2 {
+            outcome.castled = true;
+
             if m.to_file == 6 {
                 let rook = self.get_piece(7, m.to_rank).unwrap();
                 self.set_piece(rook, 5, m.to_rank);
@@ -266,13 +841,73 @@ impl Board {
             }
         }
 
+        // moving a king or rook off its starting square, or capturing a rook
+        // there, gives up the matching castling rights
+        self.revoke_castling(m.from_file, m.from_rank);
+        self.revoke_castling(m.to_file, m.to_rank);
+
+        if let Some(class) = m.promotion {
+            piece.class = class;
+            piece.promoted = true;
+            outcome.promotion = Some(class);
+        }
+
         piece.moves += 1;
         self.set_piece(piece, m.to_file, m.to_rank);
         self.clear_piece(m.from_file, m.from_rank);
 
         self.moves.push(data.to_string());
+        self.last_move = Some(m);
+        self.switch_turn();
+
+        Ok(outcome)
+    }
+
+    /// Plays a sequence of coordinate moves, skipping empty entries. On
+    /// failure returns the index of the offending move along with the
+    /// reason; the moves before it stay on the board.
+    pub fn apply_moves<'a, I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        moves: I,
+    ) -> Result<(), (usize, Error)> {
+        for (i, m) in moves.into_iter().enumerate() {
+            let m = m.trim();
+
+            if !m.is_empty() {
+                self.move_piece(m).map_err(|e| (i, e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Passes the turn to the opponent without moving, as engines do for
+    /// null move pruning. Not allowed while in check, since the king would
+    /// be left attacked.
+    pub fn make_null_move(&mut self) -> Result<(), Error> {
+        if self.is_in_check(self.turn) {
+            return Err(MoveError::NullMoveInCheck.into());
+        }
+
+        self.halfmove_clock += 1;
+        self.en_passant = None;
+        self.last_move = None;
+        self.switch_turn();
+
+        Ok(())
+    }
 
-        // switch turn
+    /// Gives the move to the given side. Any en passant square goes, as it
+    /// only belongs to the side that was to move.
+    pub fn set_turn(&mut self, color: Color) {
+        if self.turn != color {
+            self.en_passant = None;
+            self.turn = color;
+            self.refresh_checks();
+        }
+    }
+
+    fn switch_turn(&mut self) {
         self.turn = match self.turn {
             Color::White => Color::Black,
             Color::Black => {
@@ -281,45 +916,51 @@ impl Board {
             }
         };
 
-        Ok(())
+        self.refresh_checks();
+    }
+
+    /// Records a captured piece, handing it to the capturing side in
+    /// variants with drops
+    fn capture(&mut self, piece: Piece) {
+        if let Some(class) = self.variant.on_capture(&piece) {
+            self.pockets[self.turn as usize].add(class);
+        }
+
+        self.captured.push(piece);
     }
 
     pub fn from_fen(&mut self, data: &str) -> Result<(), Error> {
+        let variant = self.variant;
+
         *self = Board::new()?;
-        let mut file = 0;
-        let mut rank = 7;
+        self.variant = variant;
 
-        // split by spaces
-        let parts: Vec<&str> = data.split(" ").collect();
-        let mut parts = parts.iter();
+        let mut parts = data.split_whitespace();
 
-        let moves = match parts.next() {
-            Some(moves) => moves,
-            None => return Err(Error::InvalidFen("missing moves".to_string())),
+        let placement = match parts.next() {
+            Some(placement) => placement,
+            None => return Err(FenError::MissingField("piece placement").into()),
         };
 
         self.turn = match parts.next() {
-            Some(start) => match start {
-                &"w" => Color::White,
-                &"b" => Color::Black,
-                _ => return Err(Error::InvalidFen("invalid start".to_string())),
+            Some(turn) => match turn {
+                "w" => Color::White,
+                "b" => Color::Black,
+                _ => return Err(FenError::InvalidField("side to move").into()),
             },
-            None => return Err(Error::InvalidFen("missing start".to_string())),
+            None => return Err(FenError::MissingField("side to move").into()),
         };
 
-        match parts.next() {
-            Some(castling) => {
-                self.white_can_castle_kingside = castling.contains("K");
-                self.white_can_castle_queenside = castling.contains("Q");
-                self.black_can_castle_kingside = castling.contains("k");
-                self.black_can_castle_queenside = castling.contains("q");
-            }
-            None => return Err(Error::InvalidFen("missing castling".to_string())),
+        // castling rights are applied once the pieces are placed, since
+        // Shredder-FEN and X-FEN name rooks by file relative to the king
+        let castling = match parts.next() {
+            Some(castling) => castling,
+            None => return Err(FenError::MissingField("castling").into()),
         };
 
         match parts.next() {
             Some(en_passant) => {
-                if en_passant != &"-" {
+                if en_passant != "-" {
                     let file = match en_passant.chars().nth(0) {
                         Some(file) => match file {
                             'a' => 0,
@@ -330,143 +971,1216 @@ impl Board {
                             'f' => 5,
                             'g' => 6,
                             'h' => 7,
-                            _ => {
-                                return Err(Error::InvalidFen(
-                                    "invalid en passant file".to_string(),
-                                ))
-                            }
+                            _ => return Err(FenError::InvalidField("en passant file").into()),
                         },
-                        None => {
-                            return Err(Error::InvalidFen("missing en passant file".to_string()))
-                        }
+                        None => return Err(FenError::MissingField("en passant file").into()),
                     };
 
                     let rank = match en_passant.chars().nth(1) {
                         Some(rank) => match rank {
                             '3' => 2,
                             '6' => 5,
-                            _ => {
-                                return Err(Error::InvalidFen(
-                                    "invalid en passant rank".to_string(),
-                                ))
-                            }
+                            _ => return Err(FenError::InvalidField("en passant rank").into()),
                         },
-                        None => {
-                            return Err(Error::InvalidFen("missing en passant rank".to_string()))
-                        }
+                        None => return Err(FenError::MissingField("en passant rank").into()),
                     };
 
                     self.en_passant = Some((file, rank))
                 }
             }
-            None => return Err(Error::InvalidFen("missing en passant".to_string())),
+            None => return Err(FenError::MissingField("en passant").into()),
         };
 
         self.halfmove_clock = match parts.next() {
-            Some(halfmove) => {
-                let halfmove = match halfmove.parse::<usize>() {
-                    Ok(halfmove) => halfmove,
-                    Err(_) => return Err(Error::InvalidFen("invalid halfmove clock".to_string())),
-                };
-                // if halfmove > 100 {
-                //     return Err(Error::InvalidFen("invalid halfmove clock".to_string()));
-                // }
-                halfmove
-            }
-            None => return Err(Error::InvalidFen("missing halfmove".to_string())),
+            Some(halfmove) => match halfmove.parse::<usize>() {
+                Ok(halfmove) => halfmove,
+                Err(_) => return Err(FenError::InvalidField("halfmove clock").into()),
+            },
+            None => return Err(FenError::MissingField("halfmove").into()),
         };
 
+        // moves are numbered from 1
         self.fullmove_number = match parts.next() {
-            Some(fullmove) => {
-                let fullmove = match fullmove.parse::<usize>() {
-                    Ok(fullmove) => fullmove,
-                    Err(_) => return Err(Error::InvalidFen("invalid fullmove number".to_string())),
-                };
-                // if fullmove > 100 {
-                //     return Err(Error::InvalidFen("invalid fullmove number".to_string()));
-                // }
-                fullmove
-            }
-            None => return Err(Error::InvalidFen("missing fullmove".to_string())),
+            Some(fullmove) => match fullmove.parse::<usize>() {
+                Ok(fullmove) if fullmove > 0 => fullmove,
+                _ => return Err(FenError::InvalidField("fullmove number").into()),
+            },
+            None => return Err(FenError::MissingField("fullmove").into()),
+        };
+
+        if let Some(field) = parts.next() {
+            return Err(FenError::TrailingField(field.to_string()).into());
+        }
+
+        // the pocket follows the pieces either in brackets or as a ninth rank
+        let (placement, pocket) = match placement.split_once('[') {
+            Some((placement, pocket)) => match pocket.strip_suffix(']') {
+                Some(pocket) => (placement, Some(pocket)),
+                None => return Err(FenError::UnterminatedPocket.into()),
+            },
+            None if placement.split('/').count() == 9 => match placement.rsplit_once('/') {
+                Some((placement, pocket)) => (placement, Some(pocket)),
+                None => (placement, None),
+            },
+            None => (placement, None),
         };
 
+        if let Some(pocket) = pocket {
+            self.set_pockets(pocket)?;
+        }
+
         // split by slashes
-        let rows: Vec<&str> = moves.split("/").collect();
+        let rows: Vec<&str> = placement.split('/').collect();
+
+        if rows.len() != 8 {
+            return Err(FenError::RankCount(rows.len()).into());
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0;
 
-        for row in rows {
             for c in row.chars() {
-                if c.is_ascii_digit() {
-                    let count = c.to_string().parse::<usize>().unwrap();
-                    file += count;
-                    continue;
-                }
+                if let Some(count) = c.to_digit(10) {
+                    if count == 0 || count > 8 {
+                        return Err(FenError::EmptyCount { count: c, rank }.into());
+                    }
+
+                    file += count as usize;
+                } else if c == '~' {
+                    // the piece before is a promoted pawn
+                    let piece = file
+                        .checked_sub(1)
+                        .filter(|f| *f < 8)
+                        .and_then(|f| self.get_piece(f, rank).map(|p| (f, p)));
+                    match piece {
+                        Some((f, mut piece)) => {
+                            piece.promoted = true;
+                            self.set_piece(piece, f, rank);
+                        }
+                        None => return Err(FenError::InvalidPiece(c).into()),
+                    }
+                } else {
+                    let piece = match c {
+                        'P' => Piece::new(Class::Pawn, Color::White),
+                        'N' => Piece::new(Class::Knight, Color::White),
+                        'B' => Piece::new(Class::Bishop, Color::White),
+                        'R' => Piece::new(Class::Rook, Color::White),
+                        'Q' => Piece::new(Class::Queen, Color::White),
+                        'K' => Piece::new(Class::King, Color::White),
+                        'p' => Piece::new(Class::Pawn, Color::Black),
+                        'n' => Piece::new(Class::Knight, Color::Black),
+                        'b' => Piece::new(Class::Bishop, Color::Black),
+                        'r' => Piece::new(Class::Rook, Color::Black),
+                        'q' => Piece::new(Class::Queen, Color::Black),
+                        'k' => Piece::new(Class::King, Color::Black),
+                        _ => return Err(FenError::InvalidPiece(c).into()),
+                    };
 
-                let piece = match c {
-                    'P' => Piece::new(Class::Pawn, Color::White),
-                    'N' => Piece::new(Class::Knight, Color::White),
-                    'B' => Piece::new(Class::Bishop, Color::White),
-                    'R' => Piece::new(Class::Rook, Color::White),
-                    'Q' => Piece::new(Class::Queen, Color::White),
-                    'K' => Piece::new(Class::King, Color::White),
-                    'p' => Piece::new(Class::Pawn, Color::Black),
-                    'n' => Piece::new(Class::Knight, Color::Black),
-                    'b' => Piece::new(Class::Bishop, Color::Black),
-                    'r' => Piece::new(Class::Rook, Color::Black),
-                    'q' => Piece::new(Class::Queen, Color::Black),
-                    'k' => Piece::new(Class::King, Color::Black),
-                    _ => return Err(Error::InvalidFen("invalid piece".to_string())),
-                };
+                    if file < 8 {
+                        self.set_piece(piece, file, rank);
+                    }
 
-                self.set_piece(piece, file, rank);
+                    file += 1;
+                }
 
-                file += 1;
+                if file > 8 {
+                    return Err(FenError::RankTooLong { rank }.into());
+                }
+            }
+
+            if file != 8 {
+                return Err(FenError::RankLength { rank, files: file }.into());
+            }
+        }
+
+        self.set_castling(castling)?;
+        self.refresh_checks();
+
+        self.validate_position()?;
+
+        let fen = self.to_fen();
+        if fen != self.variant.start_position() {
+            self.start = Some(fen);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the position could occur in a legal game, returning a
+    /// description of the first problem found
+    pub fn validate_position(&self) -> Result<(), Error> {
+        for color in [Color::White, Color::Black] {
+            let mut kings = 0;
+            let mut pawns = 0;
+            let mut pieces = 0;
+
+            for file in 0..8 {
+                for rank in 0..8 {
+                    let piece = match self.get_piece(file, rank) {
+                        Some(piece) if piece.color == color => piece,
+                        _ => continue,
+                    };
+
+                    pieces += 1;
+
+                    match piece.class {
+                        Class::King => kings += 1,
+                        Class::Pawn => {
+                            pawns += 1;
+
+                            if rank == 0 || rank == 7 {
+                                return Err(FenError::PawnOnBackRank { color, rank }.into());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if !self.variant.has_royal_king() {
+                continue;
+            }
+
+            if kings == 0 {
+                return Err(FenError::MissingKing(color).into());
+            }
+
+            if kings > 1 {
+                return Err(FenError::TooManyKings {
+                    color,
+                    count: kings,
+                }
+                .into());
+            }
+
+            // captured pieces change sides in variants with drops, so a side
+            // can hold more than it started with
+            if self.variant.has_drops() {
+                continue;
+            }
+
+            if pawns > 8 {
+                return Err(FenError::TooManyPawns {
+                    color,
+                    count: pawns,
+                }
+                .into());
+            }
+
+            if pieces > 16 {
+                return Err(FenError::TooManyPieces {
+                    color,
+                    count: pieces,
+                }
+                .into());
+            }
+        }
+
+        if self.is_in_check(self.turn.opponent()) {
+            return Err(FenError::OpponentInCheck(self.turn.opponent()).into());
+        }
+
+        if let Some((file, rank)) = self.en_passant {
+            // the pawn that just double pushed stands in front of the square
+            let (expected_rank, pawn_rank, start_rank) = match self.turn {
+                Color::White => (5, 4, 6),
+                Color::Black => (2, 3, 1),
+            };
+
+            if rank != expected_rank {
+                return Err(FenError::EnPassantWrongSide.into());
+            }
+
+            let opponent = self.turn.opponent();
+
+            if !self
+                .get_piece(file, pawn_rank)
+                .is_some_and(|p| p.class == Class::Pawn && p.color == opponent)
+            {
+                return Err(FenError::EnPassantWithoutPawn.into());
+            }
+
+            if self.get_piece(file, rank).is_some() || self.get_piece(file, start_rank).is_some() {
+                return Err(FenError::EnPassantOccupied.into());
+            }
+        }
+
+        let rights = [
+            (self.white_can_castle_kingside, Color::White, 0, 7),
+            (self.white_can_castle_queenside, Color::White, 0, 0),
+            (self.black_can_castle_kingside, Color::Black, 7, 7),
+            (self.black_can_castle_queenside, Color::Black, 7, 0),
+        ];
+
+        for (allowed, color, rank, rook_file) in rights {
+            if !allowed {
+                continue;
+            }
+
+            let is = |file: usize, class: Class| {
+                self.get_piece(file, rank)
+                    .is_some_and(|p| p.class == class && p.color == color)
+            };
+
+            if !is(4, Class::King) || !is(rook_file, Class::Rook) {
+                return Err(FenError::CastlingWithoutPieces(color).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Panics when the board's state has been corrupted: a royal king gone
+    /// missing, castling rights without the king and rook in place, an en
+    /// passant square the side to move can't take on, cached material that
+    /// disagrees with the pieces or move clocks that can't be right. Debug
+    /// builds check this after every move.
+    pub fn assert_consistent(&self) {
+        if let Some(problem) = self.inconsistency() {
+            panic!("inconsistent board {}: {}", self.to_fen(), problem);
+        }
+    }
+
+    /// Describes the first broken invariant of `assert_consistent`
+    fn inconsistency(&self) -> Option<String> {
+        for color in [Color::White, Color::Black] {
+            let mut count = [0u8; 6];
+            for file in 0..8 {
+                for rank in 0..8 {
+                    if let Some(p) = self.get_piece(file, rank).filter(|p| p.color == color) {
+                        count[p.class as usize] += 1;
+                    }
+                }
+            }
+
+            let kings = count[Class::King as usize];
+            if self.variant.has_royal_king() && kings != 1 {
+                return Some(format!("{} has {} kings", color, kings));
+            }
+
+            let material = self.material(color);
+            for class in [
+                Class::Pawn,
+                Class::Knight,
+                Class::Bishop,
+                Class::Rook,
+                Class::Queen,
+                Class::King,
+            ] {
+                if material.get(class) != count[class as usize] {
+                    return Some(format!("{} {:?} count is off", color, class));
+                }
+            }
+        }
+
+        let rights = [
+            (self.white_can_castle_kingside, Color::White, 0, 7),
+            (self.white_can_castle_queenside, Color::White, 0, 0),
+            (self.black_can_castle_kingside, Color::Black, 7, 7),
+            (self.black_can_castle_queenside, Color::Black, 7, 0),
+        ];
+
+        for (allowed, color, rank, rook_file) in rights {
+            let is = |file: usize, class: Class| {
+                self.get_piece(file, rank)
+                    .is_some_and(|p| p.class == class && p.color == color)
+            };
+
+            if allowed && (!is(4, Class::King) || !is(rook_file, Class::Rook)) {
+                return Some(format!("{} may castle without king and rook", color));
+            }
+        }
+
+        if let Some((file, rank)) = self.en_passant {
+            let (expected_rank, pawn_rank) = match self.turn {
+                Color::White => (5, 4),
+                Color::Black => (2, 3),
+            };
+            let opponent = self.turn.opponent();
+
+            if rank != expected_rank
+                || self.get_piece(file, rank).is_some()
+                || !self
+                    .get_piece(file, pawn_rank)
+                    .is_some_and(|p| p.class == Class::Pawn && p.color == opponent)
+            {
+                return Some(format!(
+                    "en passant square {} is wrong",
+                    (file, rank).name()
+                ));
+            }
+        }
+
+        // the halfmove clock can't count more plies than have been played
+        let plies = 2 * self.fullmove_number.saturating_sub(1) + self.turn as usize;
+        if self.fullmove_number == 0 || self.halfmove_clock > plies {
+            return Some(format!(
+                "halfmove clock {} at move {}",
+                self.halfmove_clock, self.fullmove_number
+            ));
+        }
+
+        if self.is_check() != self.is_in_check(self.turn) {
+            return Some("cached check disagrees with the board".to_string());
+        }
+
+        None
+    }
+
+    /// Fills the pockets from FEN piece letters, uppercase for white
+    fn set_pockets(&mut self, pocket: &str) -> Result<(), Error> {
+        if !self.variant.has_drops() {
+            return Err(FenError::PocketNotAllowed(self.variant).into());
+        }
+
+        for c in pocket.chars() {
+            let class = match c.to_ascii_lowercase() {
+                'p' => Class::Pawn,
+                'n' => Class::Knight,
+                'b' => Class::Bishop,
+                'r' => Class::Rook,
+                'q' => Class::Queen,
+                _ => return Err(FenError::InvalidPocketPiece(c).into()),
+            };
+
+            let color = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            self.pockets[color as usize].add(class);
+        }
+
+        Ok(())
+    }
+
+    /// Applies a FEN castling field, accepting standard `KQkq` as well as
+    /// Shredder-FEN and X-FEN rook file letters (`HAha`). The board only
+    /// castles with the king on the e-file and the rooks in the corners, so
+    /// a Chess960 castling field is rejected
+    fn set_castling(&mut self, castling: &str) -> Result<(), Error> {
+        self.white_can_castle_kingside = false;
+        self.white_can_castle_queenside = false;
+        self.black_can_castle_kingside = false;
+        self.black_can_castle_queenside = false;
+
+        if castling == "-" || !self.variant.has_castling() {
+            return Ok(());
+        }
+
+        for c in castling.chars() {
+            let (color, kingside) = match c {
+                'K' => (Color::White, true),
+                'Q' => (Color::White, false),
+                'k' => (Color::Black, true),
+                'q' => (Color::Black, false),
+                'A'..='H' | 'a'..='h' => {
+                    let color = if c.is_ascii_uppercase() {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+
+                    let rook_file = (c.to_ascii_lowercase() as u8 - b'a') as usize;
+
+                    let king_file = match self.king_square(color) {
+                        Some((file, _)) => file,
+                        None => return Err(FenError::CastlingWithoutKing.into()),
+                    };
+
+                    if king_file != 4 || (rook_file != 0 && rook_file != 7) {
+                        return Err(FenError::Chess960Castling(c).into());
+                    }
+
+                    (color, rook_file == 7)
+                }
+                _ => return Err(FenError::InvalidField("castling").into()),
+            };
+
+            match (color, kingside) {
+                (Color::White, true) => self.white_can_castle_kingside = true,
+                (Color::White, false) => self.white_can_castle_queenside = true,
+                (Color::Black, true) => self.black_can_castle_kingside = true,
+                (Color::Black, false) => self.black_can_castle_queenside = true,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the position as Shredder-FEN, naming castling rooks by file
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen(true)
+    }
+}
+
+impl Board {
+    /// Draws the board with FEN letters and `.` for empty squares, white at
+    /// the bottom. The squares of the last move are put in brackets and a
+    /// king in check in parentheses.
+    pub fn to_ascii(&self) -> String {
+        self.render(|piece| piece.to_fen(), '.')
+    }
+
+    /// Draws the board with chess piece symbols, white at the bottom, marked
+    /// like `to_ascii`
+    pub fn to_unicode(&self) -> String {
+        self.render(|piece| piece.to_string(), '·')
+    }
+
+    fn render(&self, symbol: impl Fn(&Piece) -> String, empty: char) -> String {
+        let last_move = self.last_move_squares();
+        let checked = self.checked_king();
+        let mark = |square: Square| match square {
+            _ if checked == Some(square) => Some(['(', ')']),
+            _ if last_move.is_some_and(|squares| squares.contains(&square)) => Some(['[', ']']),
+            _ => None,
+        };
+
+        let mut out = String::new();
+
+        for rank in (0..8).rev() {
+            out.push_str(&(rank + 1).to_string());
+
+            for file in 0..8usize {
+                // the gap before a square holds the marks of both neighbours
+                let left = file.checked_sub(1).and_then(|left| mark((left, rank)));
+                out.push(match (left, mark((file, rank))) {
+                    (Some(_), Some(_)) => '|',
+                    (Some([_, close]), None) => close,
+                    (None, Some([open, _])) => open,
+                    (None, None) => ' ',
+                });
+
+                match self.get_piece(file, rank) {
+                    Some(piece) => out.push_str(&symbol(&piece)),
+                    None => out.push(empty),
+                }
+            }
+
+            if let Some([_, close]) = mark((7, rank)) {
+                out.push(close);
+            }
+            out.push('\n');
+        }
+
+        out.push_str("  a b c d e f g h");
+
+        out
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_ascii())
+    }
+}
+
+impl FromStr for Board {
+    type Err = Error;
+
+    fn from_str(fen: &str) -> Result<Board, Error> {
+        Board::try_from_fen(fen)
+    }
+}
+
+impl ToFen for Board {
+    fn to_fen(&self) -> String {
+        self.fen(false)
+    }
+}
+
+impl Board {
+    fn fen(&self, shredder: bool) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                match self.get_piece(file, rank) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push_str(&piece.to_fen());
+                        if piece.promoted && self.variant.has_drops() {
+                            fen.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
             }
 
-            file = 0;
             if rank > 0 {
-                rank -= 1;
+                fen.push('/');
+            }
+        }
+
+        if self.variant.has_drops() {
+            fen.push('[');
+            fen.push_str(&self.pocket(Color::White).fen(Color::White));
+            fen.push_str(&self.pocket(Color::Black).fen(Color::Black));
+            fen.push(']');
+        }
+
+        fen.push(' ');
+        fen.push(match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        // the board only castles with the corner rooks, so in Shredder-FEN
+        // kingside is always the h-file and queenside the a-file
+        let symbols = if shredder { "HAha" } else { "KQkq" };
+        let rights = [
+            self.white_can_castle_kingside,
+            self.white_can_castle_queenside,
+            self.black_can_castle_kingside,
+            self.black_can_castle_queenside,
+        ];
+
+        let mut castling = String::new();
+        for (symbol, allowed) in symbols.chars().zip(rights) {
+            if allowed {
+                castling.push(symbol);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        fen.push(' ');
+        fen.push_str(&castling);
+
+        fen.push(' ');
+        match self.en_passant {
+            Some((file, rank)) => {
+                fen.push((file as u8 + b'a') as char);
+                fen.push((rank as u8 + b'1') as char);
+            }
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+
+        fen
+    }
+}
+
+impl Board {
+    /// Returns every legal move for the side to move
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                if self
+                    .get_piece(file, rank)
+                    .is_some_and(|p| p.color == self.turn)
+                {
+                    moves.extend(self.legal_moves_from(file, rank));
+                }
+            }
+        }
+
+        moves.extend(self.legal_drops());
+
+        moves
+    }
+
+    /// Returns the legal drops from the pocket of the side to move
+    pub fn legal_drops(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for class in self.pocket(self.turn).classes() {
+            for file in 0..8 {
+                for rank in 0..8 {
+                    let m = Move::new_drop(class, file, rank);
+
+                    if self.is_legal(&m) {
+                        moves.push(m);
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Returns the legal moves of the piece on the given square, with one
+    /// move for every piece a pawn can promote to
+    pub fn legal_moves_from(&self, file: usize, rank: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for to_file in 0..8 {
+            for to_rank in 0..8 {
+                let m = Move::new(file, rank, to_file, to_rank);
+                moves.extend(self.promotions_of(m).filter(|m| self.is_legal(m)));
+            }
+        }
+
+        moves
+    }
+
+    /// Whether the move takes a pawn to the last rank, where it has to
+    /// promote
+    pub fn is_promotion(&self, m: &Move) -> bool {
+        if m.is_drop() {
+            return false;
+        }
+
+        let last_rank = |color: Color| match color {
+            Color::White => 7,
+            Color::Black => 0,
+        };
+
+        self.get_piece(m.from_file, m.from_rank)
+            .is_some_and(|p| p.class == Class::Pawn && m.to_rank == last_rank(p.color))
+    }
+
+    /// Lists the move once per promotion piece when it is a promotion, and
+    /// as it is otherwise
+    fn promotions_of(&self, m: Move) -> impl Iterator<Item = Move> {
+        let classes: &[Class] = match self.is_promotion(&m) {
+            true => self.variant.promotions(),
+            false => &[],
+        };
+
+        let plain = classes.is_empty().then_some(m);
+        plain
+            .into_iter()
+            .chain(classes.iter().map(move |class| m.with_promotion(*class)))
+    }
+
+    /// Returns the legal moves that capture a piece, including en passant
+    pub fn captures(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                if !self
+                    .get_piece(file, rank)
+                    .is_some_and(|p| p.color == self.turn)
+                {
+                    continue;
+                }
+
+                for to_file in 0..8 {
+                    for to_rank in 0..8 {
+                        let m = Move::new(file, rank, to_file, to_rank);
+
+                        if self.is_capture(&m) {
+                            moves.extend(self.promotions_of(m).filter(|m| self.is_legal(m)));
+                        }
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Returns true if the move captures a piece, including en passant
+    pub fn is_capture(&self, m: &Move) -> bool {
+        if m.is_drop() {
+            return false;
+        }
+
+        self.get_piece(m.to_file, m.to_rank).is_some()
+            || (self
+                .get_piece(m.from_file, m.from_rank)
+                .is_some_and(|p| p.class == Class::Pawn)
+                && self.is_en_passant(m.to_file, m.to_rank))
+    }
+
+    /// Returns true if the side to move has a capture that follows the piece
+    /// rules, without checking whether it leaves the king in check
+    pub fn has_capture(&self) -> bool {
+        for file in 0..8 {
+            for rank in 0..8 {
+                if !self
+                    .get_piece(file, rank)
+                    .is_some_and(|p| p.color == self.turn)
+                {
+                    continue;
+                }
+
+                for to_file in 0..8 {
+                    for to_rank in 0..8 {
+                        let m = Move::new(file, rank, to_file, to_rank);
+
+                        if self.is_capture(&m)
+                            && self
+                                .promotions_of(m)
+                                .any(|m| m.validate_movement(self).is_ok())
+                        {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns the legal moves that give check
+    pub fn checking_moves(&self) -> Vec<Move> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|m| self.with_move(m).is_ok_and(|board| board.is_check()))
+            .collect()
+    }
+
+    /// Returns whether the side to move is in check, mated or stalemated
+    pub fn status(&self) -> Status {
+        let has_moves = !self.legal_moves().is_empty();
+
+        match (self.is_check(), has_moves) {
+            (true, true) => Status::Check,
+            (true, false) => Status::Checkmate,
+            (false, true) => Status::Ongoing,
+            (false, false) => Status::Stalemate,
+        }
+    }
+
+    /// Returns the result decided by the position itself: checkmate,
+    /// stalemate or an end condition of the variant
+    pub fn result(&self) -> GameResult {
+        self.variant.result(self.status(), self.turn)
+    }
+
+    /// Whether neither side has the material left to mate: the kings with
+    /// at most one minor piece between them, or bishops that all stand on
+    /// squares of one color. Only standard chess is judged, since drops
+    /// bring pieces back and antichess has no mate.
+    pub fn is_insufficient_material(&self) -> bool {
+        if self.variant != Variant::Standard {
+            return false;
+        }
+
+        let mut minors = 0;
+        let mut knights = 0;
+        let mut bishop_squares = [false; 2];
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                match self.get_piece(file, rank).map(|p| p.class) {
+                    None | Some(Class::King) => {}
+                    Some(Class::Knight) => {
+                        minors += 1;
+                        knights += 1;
+                    }
+                    Some(Class::Bishop) => {
+                        minors += 1;
+                        bishop_squares[(file + rank) % 2] = true;
+                    }
+                    Some(_) => return false,
+                }
+            }
+        }
+
+        minors <= 1 || (knights == 0 && !(bishop_squares[0] && bishop_squares[1]))
+    }
+
+    /// Returns true if the move follows the piece rules and does not leave
+    /// the mover's king in check
+    pub fn is_legal(&self, m: &Move) -> bool {
+        if m.validate(self).is_err() {
+            return false;
+        }
+
+        let target = bit(m.to_file, m.to_rank);
+
+        if let Some(class) = m.drop {
+            // a drop can't uncover the king, only block a check
+            match self.checkers.count_ones() {
+                0 => return true,
+                1 if self.evasions & target == 0 => return false,
+                2.. => return false,
+                _ => {}
+            }
+
+            let mut board = Board {
+                captured: Vec::new(),
+                moves: Vec::new(),
+                start: None,
+                ..*self
+            };
+
+            board.set_piece(Piece::new(class, self.turn), m.to_file, m.to_rank);
+
+            return !board.is_in_check(self.turn);
+        }
+
+        let piece = self.get_piece(m.from_file, m.from_rank).unwrap();
+
+        // with the checks and pins known most moves need no trial run; en
+        // passant can uncover the king in ways pins don't show
+        let en_passant = piece.class == Class::Pawn && self.is_en_passant(m.to_file, m.to_rank);
+        if piece.class != Class::King && !en_passant {
+            let free = !self.is_pinned(m.from_file, m.from_rank);
+
+            match self.checkers.count_ones() {
+                0 if free => return true,
+                1 if self.evasions & target == 0 => return false,
+                1 if free => return true,
+                2.. => return false,
+                _ => {}
+            }
+        }
+
+        // castling is not allowed out of or through check
+        if piece.class == Class::King && m.from_file.abs_diff(m.to_file) == 2 {
+            let passing = (m.from_file + m.to_file) / 2;
+
+            if self.is_in_check(piece.color)
+                || self.is_attacked(passing, m.from_rank, piece.color.opponent())
+            {
+                return false;
+            }
+        }
+
+        // play the move on a copy of the pieces and see if the king is safe
+        let mut board = Board {
+            captured: Vec::new(),
+            moves: Vec::new(),
+            start: None,
+            ..*self
+        };
+
+        if en_passant {
+            board.clear_piece(m.to_file, m.from_rank);
+        }
+
+        if piece.class == Class::King && m.from_file.abs_diff(m.to_file) == 2 {
+            let (rook_from, rook_to) = if m.to_file == 6 { (7, 5) } else { (0, 3) };
+
+            if let Some(rook) = board.get_piece(rook_from, m.from_rank) {
+                board.set_piece(rook, rook_to, m.from_rank);
+                board.clear_piece(rook_from, m.from_rank);
+            }
+        }
+
+        board.set_piece(piece, m.to_file, m.to_rank);
+        board.clear_piece(m.from_file, m.from_rank);
+
+        !board.is_in_check(piece.color)
+    }
+
+    /// Explains why a move is not legal, or returns `None` if it is
+    pub fn why_illegal(&self, m: &Move) -> Option<IllegalReason> {
+        if self.is_legal(m) {
+            return None;
+        }
+
+        let to = (m.to_file, m.to_rank);
+
+        if let Some(class) = m.drop {
+            return Some(if !self.variant.has_drops() {
+                IllegalReason::DropsNotAllowed
+            } else if self.pocket(self.turn).count(class) == 0 {
+                IllegalReason::NotInHand(class)
+            } else if self.get_piece(m.to_file, m.to_rank).is_some() {
+                IllegalReason::Occupied { at: to }
+            } else if class == Class::Pawn && (m.to_rank == 0 || m.to_rank == 7) {
+                IllegalReason::PawnOnBackRank
+            } else {
+                IllegalReason::KingInCheck
+            });
+        }
+
+        let piece = match self.get_piece(m.from_file, m.from_rank) {
+            Some(piece) => piece,
+            None => return Some(IllegalReason::NoPiece),
+        };
+
+        if piece.color != self.turn {
+            return Some(IllegalReason::WrongTurn);
+        }
+
+        if (m.from_file, m.from_rank) == to {
+            return Some(IllegalReason::NoMovement);
+        }
+
+        match m.validate_movement(self) {
+            Ok(_) => {}
+            Err(Error::InvalidMove(MoveError::PromotionRequired)) => {
+                return Some(IllegalReason::PromotionRequired)
             }
+            Err(Error::InvalidMove(MoveError::InvalidPromotion(class))) => {
+                return Some(IllegalReason::InvalidPromotion(class))
+            }
+            Err(_) => {
+                let own = self
+                    .get_piece(m.to_file, m.to_rank)
+                    .is_some_and(|p| p.color == piece.color);
+
+                return Some(match self.blocker(m, piece.class) {
+                    Some(at) => IllegalReason::Blocked { at },
+                    None if own => IllegalReason::OwnPiece { at: to },
+                    None => IllegalReason::InvalidPath(piece.class),
+                });
+            }
+        }
+
+        if m.validate(self).is_err() {
+            return Some(IllegalReason::CaptureRequired);
+        }
+
+        if piece.class == Class::King {
+            if m.from_file.abs_diff(m.to_file) == 2 {
+                let passing = (m.from_file + m.to_file) / 2;
+
+                if self.is_in_check(piece.color) {
+                    return Some(IllegalReason::CastlingOutOfCheck);
+                }
+
+                if self.is_attacked(passing, m.from_rank, piece.color.opponent()) {
+                    return Some(IllegalReason::CastlingThroughCheck);
+                }
+            }
+
+            return Some(IllegalReason::MovesIntoCheck);
         }
 
+        Some(match self.is_in_check(piece.color) {
+            true => IllegalReason::KingInCheck,
+            false => IllegalReason::Pinned,
+        })
+    }
+
+    /// Returns the first occupied square on the way of a sliding move, a
+    /// pawn push or castling
+    fn blocker(&self, m: &Move, class: Class) -> Option<Square> {
+        let df = m.to_file as i32 - m.from_file as i32;
+        let dr = m.to_rank as i32 - m.from_rank as i32;
+
+        let straight = df == 0 || dr == 0;
+        let diagonal = df.abs() == dr.abs();
+
+        // how far along the line to look; pawns can't push onto a piece
+        // either, and castling needs the squares up to the rook empty
+        let length = match class {
+            Class::Rook if straight => df.abs().max(dr.abs()) - 1,
+            Class::Bishop if diagonal => df.abs() - 1,
+            Class::Queen if straight || diagonal => df.abs().max(dr.abs()) - 1,
+            Class::Pawn if df == 0 && dr.abs() <= 2 => dr.abs(),
+            Class::King if dr == 0 && df.abs() == 2 => {
+                if df > 0 {
+                    6 - m.from_file as i32
+                } else {
+                    m.from_file as i32 - 1
+                }
+            }
+            _ => return None,
+        };
+
+        let (step_file, step_rank) = (df.signum(), dr.signum());
+
+        (1..=length)
+            .map(|i| {
+                (
+                    (m.from_file as i32 + step_file * i) as usize,
+                    (m.from_rank as i32 + step_rank * i) as usize,
+                )
+            })
+            .find(|(file, rank)| self.get_piece(*file, *rank).is_some())
+    }
+
+    /// Returns a copy of the position with the turn passed to the opponent
+    pub(crate) fn with_null_move(&self) -> Result<Board, Error> {
+        let mut board = Board {
+            captured: Vec::new(),
+            moves: Vec::new(),
+            start: None,
+            ..*self
+        };
+
+        board.make_null_move()?;
+
+        Ok(board)
+    }
+
+    /// Returns a copy of the position with the move played, leaving out the
+    /// captured pieces and move list
+    pub(crate) fn with_move(&self, m: &Move) -> Result<Board, Error> {
+        let mut board = Board {
+            captured: Vec::new(),
+            moves: Vec::new(),
+            start: None,
+            ..*self
+        };
+
+        board.play(&m.to_string())?;
+
+        Ok(board)
+    }
+
+    /// Remembers the position, to go back to with `restore` after trying
+    /// out moves. Unlike a clone it doesn't copy the move list.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: Board {
+                captured: Vec::new(),
+                moves: Vec::new(),
+                start: None,
+                ..*self
+            },
+            moves: self.moves.len(),
+            captured: self.captured.len(),
+            start: self.start.clone(),
+        }
+    }
+
+    /// Goes back to a snapshot of this board, dropping the moves played
+    /// since. Fails when the board was set up again or has fewer moves than
+    /// at the snapshot.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        if self.start != snapshot.start
+            || self.moves.len() < snapshot.moves
+            || self.captured.len() < snapshot.captured
+        {
+            return Err(Error::InvalidInput);
+        }
+
+        let mut moves = std::mem::take(&mut self.moves);
+        moves.truncate(snapshot.moves);
+        let mut captured = std::mem::take(&mut self.captured);
+        captured.truncate(snapshot.captured);
+
+        *self = Board {
+            captured,
+            moves,
+            start: self.start.take(),
+            ..snapshot.board
+        };
+
+        Ok(())
+    }
+}
+
+/// A position saved by `Board::snapshot`, with how long the move list was
+#[derive(Clone)]
+pub struct Snapshot {
+    board: Board,
+    moves: usize,
+    captured: usize,
+    start: Option<String>,
+}
+
+impl Board {
+    /// Places a piece on a square, replacing whatever was there
+    pub fn put_piece(&mut self, piece: Piece, file: usize, rank: usize) {
+        self.revoke_castling(file, rank);
+        self.en_passant = None;
+        self.set_piece(piece, file, rank);
+        self.refresh_checks();
+    }
+
+    /// Removes and returns the piece on a square
+    pub fn remove_piece(&mut self, file: usize, rank: usize) -> Option<Piece> {
+        let piece = self.get_piece(file, rank);
+
+        self.revoke_castling(file, rank);
+        self.en_passant = None;
+        self.clear_piece(file, rank);
+        self.refresh_checks();
+
+        piece
+    }
+
+    /// Places a piece given in FEN letter and square form, e.g. `Ke4` or `pd7`
+    pub fn put(&mut self, data: &str) -> Result<(), Error> {
+        let data = data.trim();
+
+        let mut chars = data.chars();
+        let letter = chars.next().ok_or(Error::InvalidInput)?;
+        let (file, rank) = parse_square(chars.as_str())?;
+
+        let color = if letter.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let class = match letter.to_ascii_lowercase() {
+            'p' => Class::Pawn,
+            'n' => Class::Knight,
+            'b' => Class::Bishop,
+            'r' => Class::Rook,
+            'q' => Class::Queen,
+            'k' => Class::King,
+            _ => return Err(Error::InvalidInput),
+        };
+
+        self.put_piece(Piece::new(class, color), file, rank);
+
         Ok(())
     }
 
-    // pub fn to_fen(&self) -> String {
-    //     // Not enough recorded data yet to implement this
-    //     let mut fen = String::new();
-    //     // for row in self.pieces.iter() {
-    //     //     let mut empty = 0;
-    //     //     for piece in row.iter() {
-    //     //         if piece.is_none() {
-    //     //             empty += 1;
-    //     //         } else {
-    //     //             if empty > 0 {
-    //     //                 fen.push_str(&empty.to_string());
-    //     //                 empty = 0;
-    //     //             }
-    //     //             fen.push_str(&piece.unwrap().to_string());
-    //     //         }
-    //     //     }
-    //     //     if empty > 0 {
-    //     //         fen.push_str(&empty.to_string());
-    //     //     }
-    //     //     fen.push('/');
-    //     // }
-    //     // fen.pop();
-    //     fen
-    // }
+    /// Removes the piece on a square given in coordinate form, e.g. `e4`
+    pub fn remove(&mut self, data: &str) -> Result<Option<Piece>, Error> {
+        let (file, rank) = parse_square(data)?;
+        Ok(self.remove_piece(file, rank))
+    }
+
+    /// Removes every piece and all castling rights, keeping the side to move
+    /// and the variant
+    pub fn clear(&mut self) -> Result<(), Error> {
+        let turn = self.turn;
+        let variant = self.variant;
+
+        *self = Board::new()?;
+        self.turn = turn;
+        self.variant = variant;
+        self.white_can_castle_kingside = false;
+        self.white_can_castle_queenside = false;
+        self.black_can_castle_kingside = false;
+        self.black_can_castle_queenside = false;
+
+        Ok(())
+    }
 }
 
 impl Board {
+    /// Sets up the start position of the board's variant
     pub fn reset(&mut self) -> Result<(), Error> {
-        *self = Board::new()?;
+        *self = Board::start(self.variant)?;
         Ok(())
     }
 
+    /// Writes the moves played to a file. A board set up from a FEN gets the
+    /// position as a `[FEN "..."]` header line, so loading continues from it,
+    /// and a variant other than standard chess a `[Variant "..."]` line.
     pub fn save(&self, filename: &str) -> Result<(), Error> {
         let file = File::create(filename)?;
         let mut writer = BufWriter::new(file);
 
+        // a board edited by hand before any move has no recorded start
+        let start = match self.moves.is_empty() {
+            true => Some(self.to_fen()).filter(|fen| fen != self.variant.start_position()),
+            false => self.start.clone(),
+        };
+
+        if self.variant != Variant::Standard {
+            writeln!(writer, "[Variant \"{}\"]", self.variant)?;
+        }
+        if let Some(fen) = start {
+            writeln!(writer, "[FEN \"{}\"]", fen)?;
+        }
+
         // write all the moves, space seperated into file
         for m in self.moves.iter() {
             writer.write_all(m.to_string().as_bytes())?;
@@ -483,20 +2197,85 @@ impl Board {
         let mut contents = String::new();
         reader.read_to_string(&mut contents)?;
 
-        let contents = contents.trim();
+        let (variant, start, moves) = split_save(&contents)?;
 
-        let moves: Vec<_> = contents.split(" ").collect();
+        self.set_variant(variant);
+        match start {
+            Some(fen) => self.from_fen(fen)?,
+            None => self.reset()?,
+        }
 
-        // reset the board
-        self.reset()?;
+        // a game's annotations are left out, the board only plays the moves
+        let moves = save_tokens(moves)?
+            .into_iter()
+            .filter_map(|token| match token {
+                SaveToken::Move(m) => Some(m),
+                _ => None,
+            });
+        self.apply_moves(moves).map_err(|(_, e)| e)
+    }
+}
 
-        for m in moves {
-            let m = m.trim();
-            if m.len() > 0 {
-                self.move_piece(m)?;
-            }
+/// A word of the moves in a save file: a move, or a glyph or comment that
+/// `Game::save` wrote after one
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum SaveToken<'a> {
+    Move(&'a str),
+    Nag(&'a str),
+    Comment(&'a str),
+}
+
+/// Splits the moves of a save file into words, keeping a `{...}` comment
+/// in one piece
+pub(crate) fn save_tokens(moves: &str) -> Result<Vec<SaveToken<'_>>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = moves.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix('{') {
+            let (text, after) = comment.split_once('}').ok_or(Error::InvalidInput)?;
+            tokens.push(SaveToken::Comment(text.trim()));
+            rest = after;
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '{')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+
+            tokens.push(match word.starts_with('$') {
+                true => SaveToken::Nag(word),
+                false => SaveToken::Move(word),
+            });
+            rest = &rest[end..];
         }
 
-        Ok(())
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Splits the contents of a save file into the variant of its optional
+/// `[Variant "..."]` header, the FEN of its optional `[FEN "..."]` header
+/// and the moves after them
+pub(crate) fn split_save(contents: &str) -> Result<(Variant, Option<&str>, &str), Error> {
+    let mut rest = contents.trim_start();
+    let mut variant = Variant::Standard;
+    let mut start = None;
+
+    while let Some(header) = rest.strip_prefix('[') {
+        let (header, moves) = header
+            .split_once("\"]")
+            .ok_or(FenError::MissingField("header"))?;
+
+        match header.split_once(" \"") {
+            Some(("Variant", name)) => variant = Variant::try_from(name)?,
+            Some(("FEN", fen)) => start = Some(fen),
+            _ => return Err(Error::InvalidInput),
+        }
+
+        rest = moves.trim_start();
     }
+
+    Ok((variant, start, rest))
 }
@@ -0,0 +1,32 @@
+//! The system clipboard, for moving positions and games between this crate
+//! and websites such as Lichess
+
+use crate::chess::Error;
+
+fn clipboard_error(e: arboard::Error) -> Error {
+    Error::Clipboard(e.to_string())
+}
+
+/// A handle on the system clipboard. On Linux copied text is only offered
+/// while the handle lives, so keep one around rather than opening one per
+/// copy.
+pub struct Clipboard {
+    inner: arboard::Clipboard,
+}
+
+impl Clipboard {
+    pub fn new() -> Result<Clipboard, Error> {
+        Ok(Clipboard {
+            inner: arboard::Clipboard::new().map_err(clipboard_error)?,
+        })
+    }
+
+    pub fn copy(&mut self, text: &str) -> Result<(), Error> {
+        self.inner.set_text(text).map_err(clipboard_error)
+    }
+
+    /// Returns the text on the clipboard
+    pub fn paste(&mut self) -> Result<String, Error> {
+        self.inner.get_text().map_err(clipboard_error)
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::chess::{Color, Error};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TimeControl {
+    /// A fixed amount of time for the whole game
+    SuddenDeath(Duration),
+    /// Time is added to the clock after every move
+    Increment { base: Duration, increment: Duration },
+    /// The first `delay` of every move is not deducted from the clock
+    Delay { base: Duration, delay: Duration },
+}
+
+impl TimeControl {
+    fn base(&self) -> Duration {
+        match self {
+            TimeControl::SuddenDeath(base) => *base,
+            TimeControl::Increment { base, .. } => *base,
+            TimeControl::Delay { base, .. } => *base,
+        }
+    }
+}
+
+impl TryFrom<&str> for TimeControl {
+    type Error = Error;
+
+    /// Parses minutes for the game, followed by `+` and the increment or
+    /// `d` and the delay in seconds, e.g. `5`, `5+3` or `15d5`
+    fn try_from(control: &str) -> Result<TimeControl, Error> {
+        let duration = |value: &str, scale: f64| {
+            let value: f64 = value.trim().parse().map_err(|_| Error::InvalidInput)?;
+            Duration::try_from_secs_f64(value * scale).map_err(|_| Error::InvalidInput)
+        };
+
+        if let Some((base, increment)) = control.split_once('+') {
+            return Ok(TimeControl::Increment {
+                base: duration(base, 60.0)?,
+                increment: duration(increment, 1.0)?,
+            });
+        }
+        if let Some((base, delay)) = control.split_once('d') {
+            return Ok(TimeControl::Delay {
+                base: duration(base, 60.0)?,
+                delay: duration(delay, 1.0)?,
+            });
+        }
+
+        Ok(TimeControl::SuddenDeath(duration(control, 60.0)?))
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = self.base().as_secs_f64() / 60.0;
+
+        match self {
+            TimeControl::SuddenDeath(_) => write!(f, "{}", minutes),
+            TimeControl::Increment { increment, .. } => {
+                write!(f, "{}+{}", minutes, increment.as_secs_f64())
+            }
+            TimeControl::Delay { delay, .. } => write!(f, "{}d{}", minutes, delay.as_secs_f64()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    white: Duration,
+    black: Duration,
+    running: Option<(Color, Instant)>,
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Clock {
+        Clock {
+            control,
+            white: control.base(),
+            black: control.base(),
+            running: None,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Starts the clock for the given color, stopping the other side
+    pub fn start(&mut self, color: Color) {
+        self.stop();
+        if self.flagged.is_none() {
+            self.running = Some((color, Instant::now()));
+        }
+    }
+
+    /// Stops the clock, deducting the time used by the side that was running
+    pub fn stop(&mut self) {
+        if let Some((color, started)) = self.running.take() {
+            self.spend(color, started.elapsed());
+        }
+    }
+
+    /// Ends the turn of the running side and starts the opponent's clock
+    pub fn press(&mut self) -> Result<(), Error> {
+        let color = match self.running {
+            Some((color, _)) => color,
+            None => return Err(Error::InvalidInput),
+        };
+
+        self.stop();
+
+        if let Some(flagged) = self.flagged {
+            return Err(Error::TimeExpired(flagged));
+        }
+
+        if let TimeControl::Increment { increment, .. } = self.control {
+            match color {
+                Color::White => self.white += increment,
+                Color::Black => self.black += increment,
+            }
+        }
+
+        self.start(color.opponent());
+
+        Ok(())
+    }
+
+    /// Returns the side whose clock is running, if any
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Returns the time the running side has spent on its current move
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.running.map(|(_, started)| started.elapsed())
+    }
+
+    /// Returns the time left for the given color, including the running move
+    pub fn remaining(&self, color: Color) -> Duration {
+        let stored = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+
+        match self.running {
+            Some((running, started)) if running == color => {
+                stored.saturating_sub(self.chargeable(started.elapsed()))
+            }
+            _ => stored,
+        }
+    }
+
+    /// Returns which side has run out of time, if any
+    pub fn flagged(&self) -> Option<Color> {
+        if self.flagged.is_some() {
+            return self.flagged;
+        }
+
+        match self.running {
+            Some((color, _)) if self.remaining(color).is_zero() => Some(color),
+            _ => None,
+        }
+    }
+
+    fn chargeable(&self, elapsed: Duration) -> Duration {
+        match self.control {
+            TimeControl::Delay { delay, .. } => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+
+    fn spend(&mut self, color: Color, elapsed: Duration) {
+        let elapsed = self.chargeable(elapsed);
+
+        let remaining = match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+
+        *remaining = remaining.saturating_sub(elapsed);
+
+        if remaining.is_zero() {
+            self.flagged = Some(color);
+        }
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let seconds = d.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Display for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "white {} - black {}",
+            format_duration(self.remaining(Color::White)),
+            format_duration(self.remaining(Color::Black))
+        )
+    }
+}
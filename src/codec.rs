@@ -0,0 +1,152 @@
+//! A compact binary form of games for large databases and for sending over
+//! the network: a short header, then every move as its index in the list of
+//! legal moves, which takes one byte and two in the rare positions with more
+//! than 256 legal moves. Annotations, glyphs and variations are left out.
+
+use crate::{
+    chess::{Board, Error},
+    game::{Game, GameResult, Termination},
+    variant::Variant,
+};
+
+/// Starts every encoded game, the last byte being the version of the format
+const MAGIC: [u8; 4] = *b"CLG\x01";
+
+/// Positions with more legal moves than this need two bytes per move
+const ONE_BYTE: usize = 256;
+
+fn push_str(bytes: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+    let len = u16::try_from(s.len()).map_err(|_| Error::InvalidInput)?;
+    bytes.extend_from_slice(&len.to_be_bytes());
+    bytes.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Encodes a game: the variant, result, termination, players, start
+/// position and tags as strings, then the moves of the main line
+pub fn encode(game: &Game) -> Result<Vec<u8>, Error> {
+    let variant = game.board().variant();
+    let start = match game.start_fen() == variant.start_position() {
+        true => "",
+        false => game.start_fen(),
+    };
+    let termination = game.termination().map(|t| t.to_string());
+
+    let mut bytes = MAGIC.to_vec();
+    push_str(&mut bytes, &variant.to_string())?;
+    push_str(&mut bytes, &game.result().to_string())?;
+    push_str(&mut bytes, termination.as_deref().unwrap_or(""))?;
+    push_str(&mut bytes, game.white())?;
+    push_str(&mut bytes, game.black())?;
+    push_str(&mut bytes, start)?;
+
+    let count = |n: usize| u16::try_from(n).map_err(|_| Error::InvalidInput);
+
+    bytes.extend_from_slice(&count(game.tags().len())?.to_be_bytes());
+    for (name, value) in game.tags() {
+        push_str(&mut bytes, name)?;
+        push_str(&mut bytes, value)?;
+    }
+
+    bytes.extend_from_slice(&count(game.history().len())?.to_be_bytes());
+    let mut board = start_board(variant, game.start_fen())?;
+    for entry in game.history() {
+        let legal = board.legal_moves();
+        let index = legal
+            .iter()
+            .position(|m| *m == entry.m)
+            .ok_or(Error::InvalidInput)?;
+
+        match legal.len() > ONE_BYTE {
+            true => bytes.extend_from_slice(&(index as u16).to_be_bytes()),
+            false => bytes.push(index as u8),
+        }
+        board = board.with_move(&entry.m)?;
+    }
+
+    Ok(bytes)
+}
+
+fn start_board(variant: Variant, fen: &str) -> Result<Board, Error> {
+    let mut board = Board::new()?;
+    board.set_variant(variant);
+    board.from_fen(fen)?;
+    Ok(board)
+}
+
+/// Reads the fields of an encoded game in order
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < n {
+            return Err(Error::InvalidInput);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<usize, Error> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    }
+
+    fn str(&mut self) -> Result<&'a str, Error> {
+        let len = self.u16()?;
+        std::str::from_utf8(self.take(len)?).map_err(|_| Error::InvalidInput)
+    }
+}
+
+/// Decodes a game written by `encode`, replaying its moves
+pub fn decode(bytes: &[u8]) -> Result<Game, Error> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(Error::InvalidInput);
+    }
+
+    let variant = Variant::try_from(reader.str()?)?;
+    let result = GameResult::try_from(reader.str()?)?;
+    let termination = match reader.str()? {
+        "" => None,
+        t => Some(Termination::try_from(t)?),
+    };
+    let white = reader.str()?;
+    let black = reader.str()?;
+    let start = match reader.str()? {
+        "" => variant.start_position(),
+        fen => fen,
+    };
+
+    let mut game = Game::from_board(start_board(variant, start)?);
+    game.set_players(white, black);
+
+    for _ in 0..reader.u16()? {
+        let name = reader.str()?;
+        game.set_tag(name, reader.str()?);
+    }
+
+    for _ in 0..reader.u16()? {
+        let legal = game.board().legal_moves();
+        let index = match legal.len() > ONE_BYTE {
+            true => reader.u16()?,
+            false => reader.take(1)?[0] as usize,
+        };
+
+        let m = legal.get(index).ok_or(Error::InvalidInput)?;
+        game.move_piece(&m.to_string())?;
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(Error::InvalidInput);
+    }
+
+    // results reached away from the board, such as a resignation
+    if !game.is_over() && result != GameResult::Ongoing {
+        game.set_result(result, termination);
+    }
+
+    Ok(game)
+}
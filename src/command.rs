@@ -0,0 +1,169 @@
+use crate::chess::Error;
+
+/// A command a text interface understands. Arguments written in brackets,
+/// e.g. `[depth]`, are optional and have to come after the required ones.
+/// A last argument ending in `...`, e.g. `<text...>`, takes any number of
+/// words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl Command {
+    fn required(&self) -> usize {
+        self.args.iter().filter(|arg| !arg.starts_with('[')).count()
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.args
+            .last()
+            .is_some_and(|arg| arg.ends_with("...>") || arg.ends_with("...]"))
+    }
+
+    /// Returns the usage line, e.g. `goto <ply>`
+    pub fn usage(&self) -> String {
+        std::iter::once(self.name)
+            .chain(self.args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+}
+
+/// A command line split into the command it names and its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation<'r, 'a> {
+    pub command: &'r Command,
+    pub args: Vec<&'a str>,
+}
+
+impl Invocation<'_, '_> {
+    pub fn name(&self) -> &'static str {
+        self.command.name
+    }
+}
+
+/// The commands of a text interface, looked up by name, alias or an
+/// unambiguous abbreviation of the name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Registry {
+    commands: Vec<Command>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    pub fn register(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        if let Some(command) = self.commands.iter().find(|c| c.is_named(name)) {
+            return Some(command);
+        }
+
+        let mut matching = self.commands.iter().filter(|c| c.name.starts_with(name));
+
+        match (matching.next(), matching.next()) {
+            (Some(command), None) if !name.is_empty() => Some(command),
+            _ => None,
+        }
+    }
+
+    /// Returns the command whose name is closest to the given one, for
+    /// pointing out typos
+    pub fn suggest(&self, name: &str) -> Option<&Command> {
+        self.commands
+            .iter()
+            .map(|c| (distance(c.name, name), c))
+            .filter(|(d, c)| *d <= c.name.len().max(2) / 2)
+            .min_by_key(|(d, _)| *d)
+            .map(|(_, c)| c)
+    }
+
+    fn unknown(&self, name: &str) -> Error {
+        Error::UnknownCommand {
+            name: name.to_string(),
+            suggestion: self.suggest(name).map(|c| c.name),
+        }
+    }
+
+    /// Looks up the command of a line of input and checks its argument count
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Invocation<'_, 'a>, Error> {
+        let mut words = input.split_whitespace();
+        let name = words.next().ok_or(Error::InvalidInput)?;
+
+        let command = self.get(name).ok_or_else(|| self.unknown(name))?;
+
+        let args: Vec<&str> = words.collect();
+
+        let too_many = args.len() > command.args.len() && !command.is_variadic();
+        if args.len() < command.required() || too_many {
+            return Err(Error::Usage(command.usage()));
+        }
+
+        Ok(Invocation { command, args })
+    }
+
+    /// Returns the help text for one command, or a list of all of them
+    pub fn help(&self, name: Option<&str>) -> Result<String, Error> {
+        match name {
+            Some(name) => {
+                let command = self.get(name).ok_or_else(|| self.unknown(name))?;
+
+                let mut help = format!("{}\n  {}", command.usage(), command.help);
+                if !command.aliases.is_empty() {
+                    help.push_str(&format!("\n  aliases: {}", command.aliases.join(", ")));
+                }
+
+                Ok(help)
+            }
+            None => {
+                let width = self
+                    .commands
+                    .iter()
+                    .map(|c| c.usage().len())
+                    .max()
+                    .unwrap_or(0);
+
+                Ok(self
+                    .commands
+                    .iter()
+                    .map(|c| format!("{:width$}  {}", c.usage(), c.help, width = width))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+        }
+    }
+}
+
+/// Counts the single character edits turning one word into the other
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    row[b.len()]
+}
@@ -0,0 +1,303 @@
+//! Defaults read from `config.toml` in the config directory: the board
+//! theme, the language, the engine's depth, where games are saved as they go, the time
+//! control and the variant of new games
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    chess::{Board, Error},
+    clock::{Clock, TimeControl},
+    game::Game,
+    locale::Locale,
+    variant::Variant,
+};
+
+/// Returns the directory settings are kept in: `chess-lib` in
+/// `$XDG_CONFIG_HOME`, `~/.config` or `%APPDATA%` on Windows
+pub fn config_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+
+    let base = match var("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(var("APPDATA")?),
+        None => PathBuf::from(var("HOME")?).join(".config"),
+    };
+
+    Some(base.join("chess-lib"))
+}
+
+/// The colors of the squares in rendered images
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Theme {
+    #[default]
+    Brown,
+    Blue,
+    Green,
+    Gray,
+}
+
+impl Theme {
+    /// Returns the light and dark square colors
+    pub fn colors(&self) -> (&'static str, &'static str) {
+        match self {
+            Theme::Brown => ("#f0d9b5", "#b58863"),
+            Theme::Blue => ("#dee3e6", "#8ca2ad"),
+            Theme::Green => ("#ffffdd", "#86a666"),
+            Theme::Gray => ("#e0e0e0", "#a0a0a0"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Theme {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Theme, Error> {
+        match name.to_lowercase().as_str() {
+            "brown" => Ok(Theme::Brown),
+            "blue" => Ok(Theme::Blue),
+            "green" => Ok(Theme::Green),
+            "gray" | "grey" => Ok(Theme::Gray),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Theme::Brown => "brown",
+            Theme::Blue => "blue",
+            Theme::Green => "green",
+            Theme::Gray => "gray",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The keys `Config::set` understands, as they are written in the file
+pub const KEYS: [&str; 6] = [
+    "theme",
+    "locale",
+    "variant",
+    "time_control",
+    "autosave",
+    "engine.depth",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub theme: Theme,
+    /// The language of messages and of the piece letters of SAN
+    pub locale: Locale,
+    pub variant: Variant,
+    /// The clock new games start with, none when they are untimed
+    pub time_control: Option<TimeControl>,
+    /// Where the current game is written after every move
+    pub autosave: Option<PathBuf>,
+    /// Plies the built-in engine searches
+    pub depth: u32,
+    /// Where the config is kept, for one opened from a file
+    path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            theme: Theme::default(),
+            locale: Locale::default(),
+            variant: Variant::default(),
+            time_control: None,
+            autosave: None,
+            depth: 2,
+            path: None,
+        }
+    }
+}
+
+/// Reads a TOML value: a quoted string, a number or a boolean. Returns the
+/// string without its quotes.
+fn parse_value(value: &str) -> Result<String, Error> {
+    // a comment may follow the value, though not inside a string
+    let value = match value.strip_prefix('"') {
+        Some(rest) => {
+            let mut out = String::new();
+            let mut chars = rest.chars();
+            loop {
+                match chars.next().ok_or(Error::InvalidInput)? {
+                    '"' => break,
+                    '\\' => match chars.next().ok_or(Error::InvalidInput)? {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        c @ ('"' | '\\') => out.push(c),
+                        _ => return Err(Error::InvalidInput),
+                    },
+                    c => out.push(c),
+                }
+            }
+
+            let rest: String = chars.collect();
+            let rest = rest.trim();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(Error::InvalidInput);
+            }
+            return Ok(out);
+        }
+        None => value.split('#').next().unwrap_or("").trim(),
+    };
+
+    match value.is_empty() {
+        true => Err(Error::InvalidInput),
+        false => Ok(value.to_string()),
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Config {
+    /// Opens `config.toml` in the config directory, or the defaults when
+    /// there is none
+    pub fn open() -> Result<Config, Error> {
+        match config_dir() {
+            Some(dir) => Config::load(&dir.join("config.toml")),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Reads a config file, or starts from the defaults to be saved there
+    /// when the file doesn't exist
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let mut config = match path.exists() {
+            true => Config::from_toml(&std::fs::read_to_string(path)?)?,
+            false => Config::default(),
+        };
+        config.path = Some(path.to_path_buf());
+
+        Ok(config)
+    }
+
+    /// Reads the keys of `KEYS` from TOML, `engine.depth` being `depth` in
+    /// an `[engine]` table. Keys that are left out keep their default.
+    pub fn from_toml(toml: &str) -> Result<Config, Error> {
+        let mut config = Config::default();
+        let mut table = String::new();
+
+        for line in toml.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name.split('#').next().unwrap_or("").trim();
+                table = name
+                    .strip_suffix(']')
+                    .ok_or(Error::InvalidInput)?
+                    .trim()
+                    .to_string();
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(Error::InvalidInput)?;
+            let key = match table.is_empty() {
+                true => key.trim().to_string(),
+                false => format!("{}.{}", table, key.trim()),
+            };
+
+            config.set(&key, &parse_value(value.trim())?)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml = format!("theme = {}\n", quote(&self.theme.to_string()));
+        toml.push_str(&format!("locale = {}\n", quote(&self.locale.to_string())));
+        toml.push_str(&format!("variant = {}\n", quote(&self.variant.to_string())));
+        if let Some(control) = self.time_control {
+            toml.push_str(&format!("time_control = {}\n", quote(&control.to_string())));
+        }
+        if let Some(path) = &self.autosave {
+            toml.push_str(&format!(
+                "autosave = {}\n",
+                quote(&path.display().to_string())
+            ));
+        }
+        toml.push_str(&format!("\n[engine]\ndepth = {}\n", self.depth));
+
+        toml
+    }
+
+    /// Writes the config back to its file. Does nothing for a config not
+    /// opened from one.
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, self.to_toml())?;
+        }
+        Ok(())
+    }
+
+    /// Returns a setting by its key, empty when it is unset
+    pub fn get(&self, key: &str) -> Result<String, Error> {
+        Ok(match key {
+            "theme" => self.theme.to_string(),
+            "locale" => self.locale.to_string(),
+            "variant" => self.variant.to_string(),
+            "time_control" => self.time_control.map_or(String::new(), |c| c.to_string()),
+            "autosave" => self
+                .autosave
+                .as_ref()
+                .map_or(String::new(), |path| path.display().to_string()),
+            "engine.depth" => self.depth.to_string(),
+            _ => return Err(Error::InvalidInput),
+        })
+    }
+
+    /// Changes a setting by its key. `off` unsets the time control and the
+    /// autosave path.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "theme" => self.theme = Theme::try_from(value)?,
+            "locale" => self.locale = Locale::try_from(value)?,
+            "variant" => self.variant = Variant::try_from(value)?,
+            "time_control" => {
+                self.time_control = match value {
+                    "off" => None,
+                    control => Some(TimeControl::try_from(control)?),
+                }
+            }
+            "autosave" => {
+                self.autosave = match value {
+                    "off" => None,
+                    path => Some(PathBuf::from(path)),
+                }
+            }
+            "engine.depth" => {
+                let depth: u32 = value.parse().map_err(|_| Error::InvalidInput)?;
+                if depth == 0 {
+                    return Err(Error::InvalidInput);
+                }
+                self.depth = depth;
+            }
+            _ => return Err(Error::InvalidInput),
+        }
+
+        Ok(())
+    }
+
+    /// Sets up a new game with the configured variant and clock
+    pub fn new_game(&self) -> Result<Game, Error> {
+        let mut game = Game::from_board(Board::start(self.variant)?);
+        if let Some(control) = self.time_control {
+            game.set_clock(Clock::new(control));
+        }
+
+        Ok(game)
+    }
+}
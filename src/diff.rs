@@ -0,0 +1,132 @@
+use crate::{
+    chess::{Board, Color, Piece, Square},
+    variant::{Pocket, Variant},
+};
+
+/// A square whose contents differ between two boards
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SquareChange {
+    pub at: Square,
+    pub before: Option<Piece>,
+    pub after: Option<Piece>,
+}
+
+/// A difference in the position besides the pieces on the board
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StateChange {
+    Turn {
+        before: Color,
+        after: Color,
+    },
+    /// A castling right that one board has and the other doesn't
+    Castling {
+        color: Color,
+        kingside: bool,
+        after: bool,
+    },
+    EnPassant {
+        before: Option<Square>,
+        after: Option<Square>,
+    },
+    Pocket {
+        color: Color,
+        before: Pocket,
+        after: Pocket,
+    },
+    Variant {
+        before: Variant,
+        after: Variant,
+    },
+}
+
+impl Board {
+    /// Lists the squares whose pieces differ on the other board, rank by
+    /// rank from a1. Pieces only count as different by class or color.
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        let same = |a: Option<Piece>, b: Option<Piece>| match (a, b) {
+            (Some(a), Some(b)) => a.class == b.class && a.color == b.color,
+            (a, b) => a.is_none() && b.is_none(),
+        };
+
+        let mut changes = Vec::new();
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let before = self.get_piece(file, rank);
+                let after = other.get_piece(file, rank);
+
+                if !same(before, after) {
+                    changes.push(SquareChange {
+                        at: (file, rank),
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Lists the differences in side to move, castling rights, en passant
+    /// square, pockets and variant. Move clocks are not compared.
+    pub fn state_diff(&self, other: &Board) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+
+        if self.turn() != other.turn() {
+            changes.push(StateChange::Turn {
+                before: self.turn(),
+                after: other.turn(),
+            });
+        }
+
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let right = |board: &Board| match kingside {
+                    true => board.can_castle_kingside(color),
+                    false => board.can_castle_queenside(color),
+                };
+
+                if right(self) != right(other) {
+                    changes.push(StateChange::Castling {
+                        color,
+                        kingside,
+                        after: right(other),
+                    });
+                }
+            }
+        }
+
+        if self.en_passant() != other.en_passant() {
+            changes.push(StateChange::EnPassant {
+                before: self.en_passant(),
+                after: other.en_passant(),
+            });
+        }
+
+        for color in [Color::White, Color::Black] {
+            if self.pocket(color) != other.pocket(color) {
+                changes.push(StateChange::Pocket {
+                    color,
+                    before: *self.pocket(color),
+                    after: *other.pocket(color),
+                });
+            }
+        }
+
+        if self.variant() != other.variant() {
+            changes.push(StateChange::Variant {
+                before: self.variant(),
+                after: other.variant(),
+            });
+        }
+
+        changes
+    }
+
+    /// Whether both boards hold the same position, ignoring move clocks and
+    /// history
+    pub fn same_position(&self, other: &Board) -> bool {
+        self.diff(other).is_empty() && self.state_diff(other).is_empty()
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt::Display;
+
+use crate::{chess::Error, mover::Move};
+
+/// An Encyclopaedia of Chess Openings code such as `B90`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EcoCode {
+    pub volume: char,
+    pub number: u8,
+}
+
+impl TryFrom<&str> for EcoCode {
+    type Error = crate::chess::Error;
+
+    fn try_from(code: &str) -> Result<EcoCode, Error> {
+        let code = code.trim();
+        let mut chars = code.chars();
+
+        let volume = match chars.next() {
+            Some(c @ 'A'..='E') => c,
+            _ => return Err(Error::InvalidInput),
+        };
+
+        let digits = chars.as_str();
+        if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::InvalidInput);
+        }
+
+        Ok(EcoCode {
+            volume,
+            number: digits.parse().unwrap(),
+        })
+    }
+}
+
+impl Display for EcoCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{:02}", self.volume, self.number)
+    }
+}
+
+/// Returns the most specific opening matching the start of the move list
+pub fn classify(moves: &[Move]) -> Option<(EcoCode, String)> {
+    let played: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+
+    let mut best: Option<(usize, &str, &str)> = None;
+
+    for (code, name, line) in OPENINGS {
+        let line: Vec<&str> = line.split(' ').collect();
+
+        if line.len() > played.len() || !line.iter().zip(played.iter()).all(|(a, b)| a == b) {
+            continue;
+        }
+
+        if best.is_none_or(|(length, _, _)| line.len() > length) {
+            best = Some((line.len(), code, name));
+        }
+    }
+
+    best.map(|(_, code, name)| (EcoCode::try_from(code).unwrap(), name.to_string()))
+}
+
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b2b4"),
+    ("A00", "Grob Opening", "g2g4"),
+    ("A01", "Nimzo-Larsen Attack", "b2b3"),
+    ("A02", "Bird's Opening", "f2f4"),
+    ("A04", "Reti Opening", "g1f3"),
+    ("A10", "English Opening", "c2c4"),
+    ("A20", "English Opening: King's English", "c2c4 e7e5"),
+    ("A40", "Queen's Pawn Game", "d2d4"),
+    ("A43", "Old Benoni Defence", "d2d4 c7c5"),
+    ("A45", "Indian Game", "d2d4 g8f6"),
+    ("A45", "Trompowsky Attack", "d2d4 g8f6 c1g5"),
+    ("A50", "Indian Game", "d2d4 g8f6 c2c4"),
+    ("A56", "Benoni Defence", "d2d4 g8f6 c2c4 c7c5"),
+    ("A57", "Benko Gambit", "d2d4 g8f6 c2c4 c7c5 d4d5 b7b5"),
+    ("A60", "Modern Benoni", "d2d4 g8f6 c2c4 c7c5 d4d5 e7e6"),
+    ("A80", "Dutch Defence", "d2d4 f7f5"),
+    ("B00", "King's Pawn Opening", "e2e4"),
+    ("B00", "Nimzowitsch Defence", "e2e4 b8c6"),
+    ("B01", "Scandinavian Defence", "e2e4 d7d5"),
+    ("B02", "Alekhine's Defence", "e2e4 g8f6"),
+    ("B06", "Modern Defence", "e2e4 g7g6"),
+    ("B07", "Pirc Defence", "e2e4 d7d6 d2d4 g8f6"),
+    ("B10", "Caro-Kann Defence", "e2e4 c7c6"),
+    (
+        "B12",
+        "Caro-Kann Defence: Advance Variation",
+        "e2e4 c7c6 d2d4 d7d5 e4e5",
+    ),
+    ("B20", "Sicilian Defence", "e2e4 c7c5"),
+    (
+        "B21",
+        "Sicilian Defence: Smith-Morra Gambit",
+        "e2e4 c7c5 d2d4 c5d4 c2c3",
+    ),
+    (
+        "B22",
+        "Sicilian Defence: Alapin Variation",
+        "e2e4 c7c5 c2c3",
+    ),
+    ("B23", "Sicilian Defence: Closed", "e2e4 c7c5 b1c3"),
+    ("B27", "Sicilian Defence", "e2e4 c7c5 g1f3"),
+    ("B30", "Sicilian Defence", "e2e4 c7c5 g1f3 b8c6"),
+    (
+        "B33",
+        "Sicilian Defence: Sveshnikov Variation",
+        "e2e4 c7c5 g1f3 b8c6 d2d4 c5d4 f3d4 g8f6 b1c3 e7e5",
+    ),
+    ("B40", "Sicilian Defence", "e2e4 c7c5 g1f3 e7e6"),
+    ("B50", "Sicilian Defence", "e2e4 c7c5 g1f3 d7d6"),
+    (
+        "B70",
+        "Sicilian Defence: Dragon Variation",
+        "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 g7g6",
+    ),
+    (
+        "B90",
+        "Sicilian Defence: Najdorf Variation",
+        "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 a7a6",
+    ),
+    ("C00", "French Defence", "e2e4 e7e6"),
+    (
+        "C01",
+        "French Defence: Exchange Variation",
+        "e2e4 e7e6 d2d4 d7d5 e4d5",
+    ),
+    (
+        "C02",
+        "French Defence: Advance Variation",
+        "e2e4 e7e6 d2d4 d7d5 e4e5",
+    ),
+    (
+        "C03",
+        "French Defence: Tarrasch Variation",
+        "e2e4 e7e6 d2d4 d7d5 b1d2",
+    ),
+    (
+        "C15",
+        "French Defence: Winawer Variation",
+        "e2e4 e7e6 d2d4 d7d5 b1c3 f8b4",
+    ),
+    ("C20", "King's Pawn Game", "e2e4 e7e5"),
+    ("C23", "Bishop's Opening", "e2e4 e7e5 f1c4"),
+    ("C25", "Vienna Game", "e2e4 e7e5 b1c3"),
+    ("C30", "King's Gambit", "e2e4 e7e5 f2f4"),
+    ("C40", "King's Knight Opening", "e2e4 e7e5 g1f3"),
+    ("C41", "Philidor Defence", "e2e4 e7e5 g1f3 d7d6"),
+    ("C42", "Petrov's Defence", "e2e4 e7e5 g1f3 g8f6"),
+    ("C44", "King's Pawn Game", "e2e4 e7e5 g1f3 b8c6"),
+    ("C45", "Scotch Game", "e2e4 e7e5 g1f3 b8c6 d2d4 e5d4 f3d4"),
+    ("C46", "Three Knights Game", "e2e4 e7e5 g1f3 b8c6 b1c3"),
+    ("C47", "Four Knights Game", "e2e4 e7e5 g1f3 b8c6 b1c3 g8f6"),
+    ("C50", "Italian Game", "e2e4 e7e5 g1f3 b8c6 f1c4"),
+    (
+        "C50",
+        "Italian Game: Giuoco Piano",
+        "e2e4 e7e5 g1f3 b8c6 f1c4 f8c5",
+    ),
+    ("C51", "Evans Gambit", "e2e4 e7e5 g1f3 b8c6 f1c4 f8c5 b2b4"),
+    (
+        "C55",
+        "Two Knights Defence",
+        "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6",
+    ),
+    ("C60", "Ruy Lopez", "e2e4 e7e5 g1f3 b8c6 f1b5"),
+    (
+        "C65",
+        "Ruy Lopez: Berlin Defence",
+        "e2e4 e7e5 g1f3 b8c6 f1b5 g8f6",
+    ),
+    (
+        "C68",
+        "Ruy Lopez: Exchange Variation",
+        "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5c6",
+    ),
+    (
+        "C70",
+        "Ruy Lopez: Morphy Defence",
+        "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4",
+    ),
+    (
+        "C84",
+        "Ruy Lopez: Closed",
+        "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7",
+    ),
+    ("D00", "Queen's Pawn Game", "d2d4 d7d5"),
+    ("D00", "Queen's Pawn Game: London System", "d2d4 d7d5 c1f4"),
+    ("D02", "Queen's Pawn Game", "d2d4 d7d5 g1f3"),
+    ("D06", "Queen's Gambit", "d2d4 d7d5 c2c4"),
+    (
+        "D07",
+        "Queen's Gambit Declined: Chigorin Defence",
+        "d2d4 d7d5 c2c4 b8c6",
+    ),
+    (
+        "D08",
+        "Queen's Gambit Declined: Albin Countergambit",
+        "d2d4 d7d5 c2c4 e7e5",
+    ),
+    ("D10", "Slav Defence", "d2d4 d7d5 c2c4 c7c6"),
+    ("D20", "Queen's Gambit Accepted", "d2d4 d7d5 c2c4 d5c4"),
+    ("D30", "Queen's Gambit Declined", "d2d4 d7d5 c2c4 e7e6"),
+    (
+        "D43",
+        "Semi-Slav Defence",
+        "d2d4 d7d5 c2c4 e7e6 b1c3 g8f6 g1f3 c7c6",
+    ),
+    ("D80", "Grunfeld Defence", "d2d4 g8f6 c2c4 g7g6 b1c3 d7d5"),
+    ("E00", "Queen's Pawn Game", "d2d4 g8f6 c2c4 e7e6"),
+    ("E01", "Catalan Opening", "d2d4 g8f6 c2c4 e7e6 g2g3"),
+    (
+        "E11",
+        "Bogo-Indian Defence",
+        "d2d4 g8f6 c2c4 e7e6 g1f3 f8b4",
+    ),
+    (
+        "E12",
+        "Queen's Indian Defence",
+        "d2d4 g8f6 c2c4 e7e6 g1f3 b7b6",
+    ),
+    (
+        "E20",
+        "Nimzo-Indian Defence",
+        "d2d4 g8f6 c2c4 e7e6 b1c3 f8b4",
+    ),
+    ("E60", "King's Indian Defence", "d2d4 g8f6 c2c4 g7g6"),
+];
@@ -0,0 +1,1189 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    eval::{self, Params},
+    game::GameResult,
+    mover::Move,
+    rng::Rng,
+    see::see,
+    tablebase,
+    trace::{Cut, SearchTree, TreeNode},
+    zobrist::{pocket_key, polyglot_key},
+};
+
+/// Score of a checkmate, reduced by the number of plies it takes
+pub const MATE: i32 = 100_000;
+
+/// Loss in centipawns from which a move counts as a blunder
+pub const BLUNDER: i32 = 200;
+
+/// How many plies of captures are searched beyond the search depth
+const QUIESCENCE_PLIES: u32 = 4;
+
+/// Deepest iteration of a timed search
+const MAX_DEPTH: u32 = 64;
+
+/// Shallowest depth at which null move pruning is tried, and how much the
+/// search after the null move is reduced by
+const NULL_MOVE_MIN_DEPTH: u32 = 3;
+const NULL_MOVE_REDUCTION: u32 = 2;
+
+/// Skill level of an engine playing at full strength
+pub const MAX_SKILL: u8 = 20;
+
+/// Number of positions the transposition table can hold
+const TABLE_SIZE: usize = 1 << 16;
+
+/// The result of a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Score in centipawns from white's point of view
+    pub score: i32,
+    /// The best continuation found, starting with the side to move
+    pub line: Vec<Move>,
+    /// The deepest iteration that was completed
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+impl Evaluation {
+    /// Returns the number of moves to a forced mate, negative when black
+    /// is the one mating
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score.abs() < MATE - 1000 {
+            return None;
+        }
+
+        let moves = (MATE - self.score.abs() + 1) / 2;
+
+        Some(moves * self.score.signum())
+    }
+
+    /// The score from the point of view of the given color
+    pub fn score_for(&self, color: Color) -> i32 {
+        match color {
+            Color::White => self.score,
+            Color::Black => -self.score,
+        }
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mate_in() {
+            Some(moves) => write!(f, "#{}", moves),
+            None => write!(f, "{:+.2}", self.score as f64 / 100.0),
+        }
+    }
+}
+
+/// How a played move changed the evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveAnalysis {
+    pub before: Evaluation,
+    pub after: Evaluation,
+    /// Centipawns lost by the mover compared to the best line
+    pub loss: i32,
+}
+
+impl MoveAnalysis {
+    /// Builds the analysis of a move by the given color from the
+    /// evaluations of the positions before and after it
+    pub fn new(color: Color, before: Evaluation, after: Evaluation) -> MoveAnalysis {
+        // mate scores would make every inaccuracy in a won position huge
+        let clamp = |score: i32| score.clamp(-10_000, 10_000);
+        let loss = clamp(before.score_for(color)) - clamp(after.score_for(color));
+
+        MoveAnalysis {
+            before,
+            after,
+            loss: loss.max(0),
+        }
+    }
+
+    pub fn is_blunder(&self) -> bool {
+        self.loss >= BLUNDER
+    }
+}
+
+/// Limits for a search, following the UCI `go` command. Without any limit
+/// the engine searches to its configured depth.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+}
+
+impl Limits {
+    pub fn depth(depth: u32) -> Limits {
+        Limits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Limits {
+        Limits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+
+    /// The time to spend on a move for the given side, if the search is
+    /// timed at all
+    pub fn budget(&self, color: Color) -> Option<Duration> {
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+
+        let (time, increment) = match color {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+
+        // assume the game lasts another 30 moves, but never bet more than
+        // half of what is left on the clock
+        time.map(|time| (time / 30 + increment.unwrap_or_default() / 2).min(time / 2))
+    }
+}
+
+/// What `Engine::analyze` looks for
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnalysisOptions {
+    /// How many candidate moves to find, like UCI's `MultiPV`
+    pub lines: usize,
+    pub limits: Limits,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> AnalysisOptions {
+        AnalysisOptions {
+            lines: 3,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// One of the candidate moves of an analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// The place of the move among the candidates, 1 for the best
+    pub rank: usize,
+    /// The search of the move, whose line starts with it
+    pub evaluation: Evaluation,
+}
+
+/// Stops a running search from another thread. The search returns the best
+/// move of the last depth it completed.
+#[derive(Debug, Clone, Default)]
+pub struct StopToken {
+    stopped: Arc<AtomicBool>,
+}
+
+impl StopToken {
+    pub fn new() -> StopToken {
+        StopToken::default()
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+/// A simple alpha-beta search over the legal moves
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Engine {
+    depth: u32,
+    threads: usize,
+    params: &'static Params,
+    skill: u8,
+    seed: Option<u64>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new(2)
+    }
+}
+
+impl Engine {
+    /// Creates an engine searching the given number of plies
+    pub fn new(depth: u32) -> Engine {
+        Engine {
+            depth: depth.max(1),
+            threads: 1,
+            params: Params::standard(),
+            skill: MAX_SKILL,
+            seed: None,
+        }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: u32) {
+        self.depth = depth.max(1);
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Sets how many threads search in parallel. The extra threads search
+    /// the same position and share what they find through the
+    /// transposition table, which speeds up the main thread.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn params(&self) -> &'static Params {
+        self.params
+    }
+
+    /// Sets the evaluation weights, e.g. to compare tuned weights against
+    /// the standard ones
+    pub fn set_params(&mut self, params: &'static Params) {
+        self.params = params;
+    }
+
+    pub fn skill(&self) -> u8 {
+        self.skill
+    }
+
+    /// Sets the playing strength from 1 to 20. Below 20 the search is kept
+    /// shallow and the engine picks among the better moves with some noise,
+    /// never losing more than a bounded amount against its best move.
+    pub fn set_skill(&mut self, skill: u8) {
+        self.skill = skill.clamp(1, MAX_SKILL);
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Seeds the noise of weaker skill levels, so the same seed picks the
+    /// same moves in the same positions. Without a seed the noise differs
+    /// from run to run.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Loads evaluation weights written by `Params::save`. They are kept for
+    /// the rest of the program, so this is meant for loading a config once.
+    pub fn load_params(&mut self, filename: &str) -> Result<(), Error> {
+        self.params = Box::leak(Box::new(Params::load(filename)?));
+        Ok(())
+    }
+
+    pub fn evaluate(&self, board: &Board) -> Evaluation {
+        self.search(board, &Limits::default(), &StopToken::new())
+    }
+
+    /// Searches with iterative deepening until the depth is reached, the
+    /// time budget runs out or the search is stopped
+    pub fn search(&self, board: &Board, limits: &Limits, stop: &StopToken) -> Evaluation {
+        // the simplest endings are played perfectly from the tables
+        if let Some(evaluation) = tablebase::evaluate(board) {
+            return evaluation;
+        }
+
+        if self.skill >= MAX_SKILL {
+            return self.search_full(board, limits, stop, &[]);
+        }
+
+        // weaker levels look fewer plies ahead
+        let depth = limits
+            .depth
+            .unwrap_or(self.depth)
+            .min(1 + self.skill as u32 / 4);
+        let limits = Limits {
+            depth: Some(depth),
+            ..*limits
+        };
+
+        let best = self.search_full(board, &limits, stop, &[]);
+        if stop.is_stopped() {
+            return best;
+        }
+
+        let mover = |score: i32| match board.turn() {
+            Color::White => score,
+            Color::Black => -score,
+        };
+
+        let weakness = (MAX_SKILL - self.skill) as i32;
+        let max_loss = weakness * 12;
+
+        let mut choice = (i32::MIN, best.clone());
+        let mut rng = self.seed.map(|seed| Rng::new(seed ^ polyglot_key(board)));
+
+        for m in board.legal_moves() {
+            let Ok(next) = board.with_move(&m) else {
+                continue;
+            };
+
+            let mut reply = match depth {
+                0 | 1 => Evaluation {
+                    score: eval::evaluate(&next, self.params),
+                    line: Vec::new(),
+                    depth: 0,
+                    nodes: 1,
+                },
+                _ => self.search_full(&next, &Limits::depth(depth - 1), stop, &[]),
+            };
+            if mover(reply.score) < mover(best.score) - max_loss {
+                continue;
+            }
+
+            let noisy = mover(reply.score) + noise(weakness * 8, rng.as_mut());
+            if noisy > choice.0 {
+                reply.line.insert(0, m);
+                reply.nodes += best.nodes;
+                reply.depth = best.depth;
+                choice = (noisy, reply);
+            }
+        }
+
+        choice.1
+    }
+
+    /// Searches at full strength, leaving the `excluded` moves out at the
+    /// root
+    fn search_full(
+        &self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+        excluded: &[Move],
+    ) -> Evaluation {
+        self.run(board, limits, stop, excluded, None).0
+    }
+
+    /// Searches like `search` at full strength on a single thread, and
+    /// records the first `plies` plies of the last iteration it completed
+    pub fn search_tree(
+        &self,
+        board: &Board,
+        limits: &Limits,
+        plies: u32,
+    ) -> (Evaluation, Option<SearchTree>) {
+        let engine = Engine {
+            threads: 1,
+            ..*self
+        };
+        let (evaluation, root) = engine.run(board, limits, &StopToken::new(), &[], Some(plies));
+
+        (evaluation, root.map(|root| SearchTree { root, plies }))
+    }
+
+    /// Runs the search threads, the main one recording the tree when
+    /// `trace` is set
+    fn run(
+        &self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+        excluded: &[Move],
+        trace: Option<u32>,
+    ) -> (Evaluation, Option<TreeNode>) {
+        let deadline = limits
+            .budget(board.turn())
+            .map(|budget| Instant::now() + budget);
+
+        let max_depth = match (limits.depth, deadline) {
+            (Some(depth), _) => depth.clamp(1, MAX_DEPTH),
+            (None, Some(_)) => MAX_DEPTH,
+            (None, None) => self.depth,
+        };
+
+        let shared = Shared {
+            deadline,
+            stop,
+            done: StopToken::new(),
+            table: Table::new(TABLE_SIZE),
+            params: self.params,
+            excluded,
+        };
+
+        std::thread::scope(|scope| {
+            let helpers: Vec<_> = (1..self.threads)
+                .map(|id| {
+                    let shared = &shared;
+                    scope.spawn(move || deepen(board, max_depth, id, shared, None).0)
+                })
+                .collect();
+
+            let (mut best, tree) = deepen(board, max_depth, 0, &shared, trace);
+
+            // the helpers only exist to fill the table for the main thread
+            shared.done.stop();
+            for helper in helpers {
+                best.nodes += helper.join().map_or(0, |e| e.nodes);
+            }
+
+            (best, tree)
+        })
+    }
+
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        self.evaluate(board).line.first().copied()
+    }
+
+    /// Finds the best few moves of a position, best first, each with its
+    /// own score and line. Every next move is found by searching again
+    /// without the ones found before, at full strength whatever the skill.
+    pub fn analyze(&self, board: &Board, options: AnalysisOptions) -> Vec<Line> {
+        let stop = StopToken::new();
+        let legal = board.legal_moves().len();
+
+        let mut lines: Vec<Line> = Vec::new();
+        let mut found = Vec::new();
+
+        while lines.len() < options.lines.min(legal) {
+            let evaluation = self.search_full(board, &options.limits, &stop, &found);
+            let Some(&m) = evaluation.line.first() else {
+                break;
+            };
+
+            found.push(m);
+            lines.push(Line {
+                rank: lines.len() + 1,
+                evaluation,
+            });
+        }
+
+        lines
+    }
+
+    /// Compares the position before and after a move to see how much the
+    /// mover lost by playing it
+    pub fn analyze_move(&self, board: &Board, m: &Move) -> Result<MoveAnalysis, Error> {
+        let before = self.evaluate(board);
+        let after = self.evaluate(&board.with_move(m)?);
+
+        Ok(MoveAnalysis::new(board.turn(), before, after))
+    }
+}
+
+/// What the text interfaces need from an engine, so the built-in search and
+/// an external UCI engine can stand in for each other. A search is stopped
+/// early through the `StopToken` it was started with.
+pub trait Backend {
+    /// Returns the name to show for the engine
+    fn name(&self) -> String;
+
+    /// Returns the limits `evaluate` and `best_move` search with
+    fn limits(&self) -> Limits;
+
+    fn analyze(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+    ) -> Result<Evaluation, Error>;
+
+    /// Sets an option by its UCI name, e.g. `Skill Level` or `Threads`
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), Error>;
+
+    /// Finds the best few moves of a position, best first, like UCI's
+    /// `MultiPV`. An engine that only searches for the best move returns
+    /// that one alone.
+    fn analyze_lines(
+        &mut self,
+        board: &Board,
+        options: AnalysisOptions,
+    ) -> Result<Vec<Line>, Error> {
+        let evaluation = self.analyze(board, &options.limits, &StopToken::new())?;
+        if evaluation.line.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![Line {
+            rank: 1,
+            evaluation,
+        }])
+    }
+
+    fn evaluate(&mut self, board: &Board) -> Result<Evaluation, Error> {
+        let limits = self.limits();
+        self.analyze(board, &limits, &StopToken::new())
+    }
+
+    fn best_move(&mut self, board: &Board) -> Result<Option<Move>, Error> {
+        Ok(self.evaluate(board)?.line.first().copied())
+    }
+
+    /// Compares the position before and after a move to see how much the
+    /// mover lost by playing it
+    fn analyze_move(&mut self, board: &Board, m: &Move) -> Result<MoveAnalysis, Error> {
+        let before = self.evaluate(board)?;
+        let after = self.evaluate(&board.with_move(m)?)?;
+
+        Ok(MoveAnalysis::new(board.turn(), before, after))
+    }
+}
+
+impl Backend for Engine {
+    fn name(&self) -> String {
+        "chess-lib".to_string()
+    }
+
+    /// Searches to the configured depth
+    fn limits(&self) -> Limits {
+        Limits::depth(self.depth)
+    }
+
+    fn analyze(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+    ) -> Result<Evaluation, Error> {
+        Ok(self.search(board, limits, stop))
+    }
+
+    fn analyze_lines(
+        &mut self,
+        board: &Board,
+        options: AnalysisOptions,
+    ) -> Result<Vec<Line>, Error> {
+        Ok(Engine::analyze(self, board, options))
+    }
+
+    /// Understands `Skill Level`, `Depth`, `Threads` and `Seed`
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        if name.eq_ignore_ascii_case("seed") {
+            self.seed = Some(value.parse().map_err(|_| Error::InvalidInput)?);
+            return Ok(());
+        }
+
+        let value: u32 = value.parse().map_err(|_| Error::InvalidInput)?;
+
+        match name.to_lowercase().as_str() {
+            "skill level" | "skill" => self.set_skill(value.min(MAX_SKILL as u32) as u8),
+            "depth" => self.set_depth(value),
+            "threads" => self.set_threads(value as usize),
+            _ => return Err(Error::InvalidInput),
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the iterations of one search thread. Helper threads search every
+/// other iteration one ply deeper so the threads don't all do the same work.
+/// With `trace` the tree of the last completed iteration is kept.
+fn deepen(
+    board: &Board,
+    max_depth: u32,
+    id: usize,
+    shared: &Shared,
+    trace: Option<u32>,
+) -> (Evaluation, Option<TreeNode>) {
+    let mut best = Evaluation {
+        score: 0,
+        line: Vec::new(),
+        depth: 0,
+        nodes: 0,
+    };
+
+    let mut search = Search {
+        shared,
+        nodes: 0,
+        max_ply: 0,
+        abortable: false,
+        aborted: false,
+        killers: vec![[None; 2]; (MAX_DEPTH + QUIESCENCE_PLIES + 1) as usize],
+        history: Box::new([[0; 64]; 64]),
+        trace: trace.map(|plies| Tracer {
+            plies: plies as i32,
+            stack: Vec::new(),
+            next_move: None,
+            tree: None,
+        }),
+    };
+    let mut tree = None;
+
+    for depth in 1..=max_depth {
+        let depth = (depth + (id % 2) as u32).min(max_depth);
+
+        search.max_ply = (depth + QUIESCENCE_PLIES) as i32;
+        // the main thread always finishes its first iteration to have a move
+        // to play
+        search.abortable = id > 0 || depth > 1;
+
+        let (score, line) = search.negamax(board, depth, -MATE - 1, MATE + 1, 0);
+
+        best.nodes = search.nodes;
+
+        if search.aborted {
+            break;
+        }
+
+        if let Some(tracer) = &mut search.trace {
+            tree = tracer.tree.take();
+        }
+
+        best.score = match board.turn() {
+            Color::White => score,
+            Color::Black => -score,
+        };
+        best.line = line;
+        best.depth = depth;
+
+        // a deeper search won't find a faster mate
+        if score.abs() >= MATE - depth as i32 {
+            break;
+        }
+    }
+
+    (best, tree)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Bound {
+    Exact,
+    /// The score is at least this, the search failed high
+    Lower,
+    /// The score is at most this, the search failed low
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TableEntry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best: Option<Move>,
+}
+
+/// Transposition table remembering the results of searched positions,
+/// shared between the search threads
+struct Table {
+    slots: Vec<Mutex<Option<TableEntry>>>,
+}
+
+impl Table {
+    fn new(size: usize) -> Table {
+        Table {
+            slots: (0..size).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Mutex<Option<TableEntry>> {
+        &self.slots[(key % self.slots.len() as u64) as usize]
+    }
+
+    fn probe(&self, key: u64) -> Option<TableEntry> {
+        let slot = self.slot(key).lock().ok()?;
+        slot.filter(|entry| entry.key == key)
+    }
+
+    /// Stores the entry unless the slot holds a deeper search of the same
+    /// position
+    fn store(&self, entry: TableEntry) {
+        if let Ok(mut slot) = self.slot(entry.key).lock() {
+            if slot.is_none_or(|old| old.key != entry.key || old.depth <= entry.depth) {
+                *slot = Some(entry);
+            }
+        }
+    }
+}
+
+/// Mate scores are stored relative to the position instead of the root, so
+/// they stay right when the position is reached through another path
+fn to_table(score: i32, ply: i32) -> i32 {
+    if score >= MATE - 1000 {
+        score + ply
+    } else if score <= -(MATE - 1000) {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn from_table(score: i32, ply: i32) -> i32 {
+    if score >= MATE - 1000 {
+        score - ply
+    } else if score <= -(MATE - 1000) {
+        score + ply
+    } else {
+        score
+    }
+}
+
+/// State shared by all threads of a search
+struct Shared<'a> {
+    deadline: Option<Instant>,
+    stop: &'a StopToken,
+    /// Set once the main thread has finished
+    done: StopToken,
+    table: Table,
+    params: &'a Params,
+    /// Moves not to search at the root
+    excluded: &'a [Move],
+}
+
+/// State of one search thread, kept between its iterations
+struct Search<'a> {
+    shared: &'a Shared<'a>,
+    nodes: u64,
+    max_ply: i32,
+    abortable: bool,
+    aborted: bool,
+    /// Quiet moves that caused a cutoff, by ply
+    killers: Vec<[Option<Move>; 2]>,
+    /// How often a quiet move from one square to another caused a cutoff,
+    /// weighted by depth
+    history: Box<[[i32; 64]; 64]>,
+    trace: Option<Tracer>,
+}
+
+/// Records the positions a search visits, down to a number of plies
+struct Tracer {
+    plies: i32,
+    /// The positions being searched, from the root down
+    stack: Vec<TreeNode>,
+    /// The move about to be searched
+    next_move: Option<Move>,
+    /// The root, once its search is done
+    tree: Option<TreeNode>,
+}
+
+impl Search<'_> {
+    fn should_abort(&mut self) -> bool {
+        if self.abortable && !self.aborted {
+            self.aborted = self.shared.stop.is_stopped()
+                || self.shared.done.is_stopped()
+                || self
+                    .shared
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+
+        self.aborted
+    }
+
+    /// Starts a node of the traced tree, if this ply is traced
+    fn enter(&mut self, depth: u32, alpha: i32, beta: i32, ply: i32) -> bool {
+        let Some(tracer) = &mut self.trace else {
+            return false;
+        };
+
+        let m = tracer.next_move.take();
+        if ply > tracer.plies {
+            return false;
+        }
+
+        tracer.stack.push(TreeNode {
+            m,
+            depth,
+            alpha,
+            beta,
+            score: 0,
+            cut: None,
+            pruned: 0,
+            children: Vec::new(),
+        });
+        true
+    }
+
+    /// Finishes the node started last and hangs it below its parent
+    fn leave(&mut self, score: i32) {
+        let Some(tracer) = &mut self.trace else {
+            return;
+        };
+        let Some(mut node) = tracer.stack.pop() else {
+            return;
+        };
+
+        node.score = score;
+        match tracer.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => tracer.tree = Some(node),
+        }
+    }
+
+    /// Notes why the node at `ply` stopped early, if it is traced
+    fn note_cut(&mut self, ply: i32, cut: Cut, pruned: usize) {
+        if let Some(tracer) = &mut self.trace {
+            if tracer.stack.len() == ply as usize + 1 {
+                let node = tracer.stack.last_mut().unwrap();
+                node.cut = Some(cut);
+                node.pruned = pruned;
+            }
+        }
+    }
+
+    /// Negamax search returning the score for the side to move
+    fn negamax(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> (i32, Vec<Move>) {
+        let traced = self.enter(depth, alpha, beta, ply);
+        let result = self.negamax_node(board, depth, alpha, beta, ply);
+
+        if traced {
+            self.leave(result.0);
+        }
+        result
+    }
+
+    fn negamax_node(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> (i32, Vec<Move>) {
+        if depth == 0 {
+            return self.quiescence(board, alpha, beta, ply);
+        }
+
+        if self.should_abort() {
+            return (0, Vec::new());
+        }
+
+        self.nodes += 1;
+
+        let key = polyglot_key(board) ^ pocket_key(board);
+
+        let entry = self.shared.table.probe(key);
+
+        // the root is always searched so there is a full line to return
+        if let Some(entry) = entry.filter(|e| ply > 0 && e.depth >= depth) {
+            let score = from_table(entry.score, ply);
+            let line = entry.best.into_iter().collect();
+
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if usable {
+                self.note_cut(ply, Cut::Table, 0);
+                return (score, line);
+            }
+        }
+
+        // give the opponent a free move: if the position still holds, a real
+        // move will too. Left out where passing could be the best option,
+        // i.e. when a capture is forced or with only pawns left.
+        if ply > 0
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && beta.abs() < MATE - 1000
+            && !board.variant().forces_captures()
+            && has_pieces(board)
+        {
+            if let Ok(next) = board.with_null_move() {
+                let reduced = depth - 1 - NULL_MOVE_REDUCTION;
+                let score = -self.negamax(&next, reduced, -beta, -beta + 1, ply + 1).0;
+
+                if self.aborted {
+                    return (0, Vec::new());
+                }
+
+                if score >= beta {
+                    self.note_cut(ply, Cut::NullMove, 0);
+                    return (beta, Vec::new());
+                }
+            }
+        }
+
+        let mut moves = board.legal_moves();
+
+        if moves.is_empty() {
+            return (terminal(board, ply), Vec::new());
+        }
+
+        if ply == 0 {
+            moves.retain(|m| !self.shared.excluded.contains(m));
+        }
+
+        let hash = entry.and_then(|e| e.best);
+        let (score, line) =
+            self.search_moves(board, moves, hash, depth, alpha, beta, ply, -MATE - 1);
+
+        if !self.aborted {
+            let bound = if score <= alpha {
+                Bound::Upper
+            } else if score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+
+            self.shared.table.store(TableEntry {
+                key,
+                depth,
+                score: to_table(score, ply),
+                bound,
+                best: line.first().copied(),
+            });
+        }
+
+        (score, line)
+    }
+
+    /// Keeps searching captures past the horizon until the position is
+    /// quiet, so a line does not end halfway through an exchange. When in
+    /// check, or when a capture is forced, every legal move is searched
+    /// instead.
+    fn quiescence(
+        &mut self,
+        board: &Board,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> (i32, Vec<Move>) {
+        if self.should_abort() {
+            return (0, Vec::new());
+        }
+
+        self.nodes += 1;
+
+        let score = match board.turn() {
+            Color::White => eval::evaluate(board, self.shared.params),
+            Color::Black => -eval::evaluate(board, self.shared.params),
+        };
+
+        // there is no standing pat when a capture is forced
+        let forced = board.variant().forces_captures() && board.has_capture();
+
+        let (moves, best) = if board.is_check() || forced {
+            let moves = board.legal_moves();
+
+            if moves.is_empty() {
+                return (terminal(board, ply), Vec::new());
+            }
+
+            (moves, -MATE - 1)
+        } else {
+            // the side to move may also just stand pat
+            if score >= beta {
+                self.note_cut(ply, Cut::StandPat, 0);
+                return (score, Vec::new());
+            }
+
+            alpha = alpha.max(score);
+
+            // captures that lose material won't raise the score
+            let captures = board
+                .captures()
+                .into_iter()
+                .filter(|m| see(board, m) >= 0)
+                .collect();
+
+            (captures, score)
+        };
+
+        if ply >= self.max_ply {
+            return (score, Vec::new());
+        }
+
+        self.search_moves(board, moves, None, 0, alpha, beta, ply, best)
+    }
+
+    /// Sorts the moves so the ones most likely to cause a cutoff come first:
+    /// the move from the transposition table, captures by most valuable
+    /// victim and least valuable attacker, killer moves, captures that lose
+    /// material, then quiet moves by their history score
+    fn order(&self, board: &Board, moves: &mut [Move], hash: Option<Move>, ply: i32) {
+        let killers = self.killers[ply as usize];
+
+        moves.sort_by_cached_key(|m| {
+            let key = if Some(*m) == hash {
+                1_000_000
+            } else if let Some(victim) = captured(board, m) {
+                let attacker = board.get_piece(m.from_file, m.from_rank).unwrap();
+
+                // taking a piece worth at least the attacker never loses
+                // material, other captures are checked move by move
+                match value(victim) >= value(attacker.class) || see(board, m) >= 0 {
+                    true => 100_000 + value(victim) * 10 - value(attacker.class) / 10,
+                    false => 75_000,
+                }
+            } else if let Some(class) = m.promotion {
+                95_000 + value(class) / 10
+            } else if Some(*m) == killers[0] {
+                90_000
+            } else if Some(*m) == killers[1] {
+                80_000
+            } else {
+                self.history[square(m.from_file, m.from_rank)][square(m.to_file, m.to_rank)]
+                    .min(70_000)
+            };
+
+            std::cmp::Reverse(key)
+        });
+    }
+
+    /// Remembers a quiet move that caused a cutoff
+    fn record_cutoff(&mut self, m: Move, depth: u32, ply: i32) {
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+
+        let from = square(m.from_file, m.from_rank);
+        let to = square(m.to_file, m.to_rank);
+        self.history[from][to] += (depth * depth) as i32;
+    }
+
+    /// Searches the moves, best candidates first, and returns the best one
+    /// found, or `best` if none beats it
+    #[allow(clippy::too_many_arguments)]
+    fn search_moves(
+        &mut self,
+        board: &Board,
+        mut moves: Vec<Move>,
+        hash: Option<Move>,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        best: i32,
+    ) -> (i32, Vec<Move>) {
+        self.order(board, &mut moves, hash, ply);
+
+        let mut best = (best, Vec::new());
+        let count = moves.len();
+
+        for (i, m) in moves.into_iter().enumerate() {
+            let next = match board.with_move(&m) {
+                Ok(next) => next,
+                Err(_) => continue,
+            };
+
+            let child = depth.saturating_sub(1);
+            if let Some(tracer) = &mut self.trace {
+                tracer.next_move = Some(m);
+            }
+            let (score, line) = self.negamax(&next, child, -beta, -alpha, ply + 1);
+            let score = -score;
+
+            if self.aborted {
+                return (0, Vec::new());
+            }
+
+            if score > best.0 {
+                let mut full = vec![m];
+                full.extend(line);
+                best = (score, full);
+            }
+
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if depth > 0 && captured(board, &m).is_none() {
+                    self.record_cutoff(m, depth, ply);
+                }
+                self.note_cut(ply, Cut::Beta, count - i - 1);
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+/// The score for the side to move of a position without legal moves
+fn terminal(board: &Board, ply: i32) -> i32 {
+    let turn = board.turn();
+
+    match board.result() {
+        result if result == GameResult::win(turn) => MATE - ply,
+        result if result == GameResult::win(turn.opponent()) => -(MATE - ply),
+        _ => 0,
+    }
+}
+
+/// Returns a pseudo random number between `-range` and `range`, for making
+/// weaker skill levels less predictable. Draws from `rng` when there is
+/// one, else from a generator seeded with the time.
+fn noise(range: i32, rng: Option<&mut Rng>) -> i32 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+
+    if range <= 0 {
+        return 0;
+    }
+    if let Some(rng) = rng {
+        return rng.below(2 * range as usize + 1) as i32 - range;
+    }
+
+    let mut x = STATE.load(Ordering::Relaxed);
+    if x == 0 {
+        x = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0x9e37_79b9_7f4a_7c15, |d| d.as_nanos() as u64)
+            | 1;
+    }
+
+    // xorshift64
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    STATE.store(x, Ordering::Relaxed);
+
+    (x % (2 * range as u64 + 1)) as i32 - range
+}
+
+/// Whether the side to move has a piece besides pawns and the king
+fn has_pieces(board: &Board) -> bool {
+    board.material(board.turn()).pieces() > 0
+}
+
+fn square(file: usize, rank: usize) -> usize {
+    rank * 8 + file
+}
+
+/// The class of the piece the move captures, if any
+fn captured(board: &Board, m: &Move) -> Option<Class> {
+    match board.get_piece(m.to_file, m.to_rank) {
+        Some(piece) => Some(piece.class),
+        None => board
+            .get_piece(m.from_file, m.from_rank)
+            .filter(|p| p.class == Class::Pawn && board.is_en_passant(m.to_file, m.to_rank))
+            .map(|_| Class::Pawn),
+    }
+}
+
+fn value(class: Class) -> i32 {
+    match class {
+        Class::Pawn => 100,
+        Class::Knight => 320,
+        Class::Bishop => 330,
+        Class::Rook => 500,
+        Class::Queen => 900,
+        Class::King => 0,
+    }
+}
+
+/// Scores the position in centipawns from white's point of view without
+/// searching, using the standard evaluation weights
+pub fn evaluate_static(board: &Board) -> i32 {
+    eval::evaluate(board, Params::standard())
+}
@@ -0,0 +1,342 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use crate::{
+    chess::{Board, Error, FenError},
+    engine::{Backend, Limits, StopToken},
+    fen::ToFen,
+    mover::Move,
+    pgn,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// A single EPD record: the first four FEN fields followed by operations
+#[derive(Debug, Clone, PartialEq)]
+pub struct Epd {
+    pub position: String,
+    pub operations: Vec<Operation>,
+}
+
+impl Epd {
+    /// Creates a record for the given position without any operations
+    pub fn from_board(board: &Board) -> Epd {
+        let fen = board.to_fen();
+        let position: Vec<&str> = fen.split(' ').take(4).collect();
+
+        Epd {
+            position: position.join(" "),
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Epd, Error> {
+        let line = line.trim();
+
+        // the position is made up of the first four whitespace separated fields
+        let mut fields = 0;
+        let mut in_field = false;
+        let mut end = line.len();
+
+        for (i, c) in line.char_indices() {
+            if c.is_whitespace() {
+                if in_field {
+                    in_field = false;
+                    if fields == 4 {
+                        end = i;
+                        break;
+                    }
+                }
+            } else if !in_field {
+                in_field = true;
+                fields += 1;
+            }
+        }
+
+        if fields < 4 {
+            return Err(FenError::EpdFields.into());
+        }
+
+        let position = line[..end].split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let epd = Epd {
+            position,
+            operations: parse_operations(&line[end..])?,
+        };
+
+        // make sure the position itself is valid
+        epd.board()?;
+
+        Ok(epd)
+    }
+
+    /// Builds a board from the record, using the `hmvc` and `fmvn` opcodes
+    /// for the move counters when they are present
+    pub fn board(&self) -> Result<Board, Error> {
+        let halfmove = self.operand("hmvc").unwrap_or("0");
+        let fullmove = self.operand("fmvn").unwrap_or("1");
+
+        let board = Board::try_from_fen(&format!("{} {} {}", self.position, halfmove, fullmove))?;
+
+        Ok(board)
+    }
+
+    pub fn get(&self, opcode: &str) -> Option<&Operation> {
+        self.operations.iter().find(|o| o.opcode == opcode)
+    }
+
+    /// Adds an operation, replacing any existing one with the same opcode
+    pub fn set(&mut self, opcode: &str, operands: &[&str]) {
+        let operation = Operation {
+            opcode: opcode.to_string(),
+            operands: operands.iter().map(|o| o.to_string()).collect(),
+        };
+
+        match self.operations.iter_mut().find(|o| o.opcode == opcode) {
+            Some(existing) => *existing = operation,
+            None => self.operations.push(operation),
+        }
+    }
+
+    fn operand(&self, opcode: &str) -> Option<&str> {
+        self.get(opcode)
+            .and_then(|o| o.operands.first())
+            .map(|o| o.as_str())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operand("id")
+    }
+
+    /// The moves listed by the `bm` opcode, in SAN as written in the record
+    pub fn best_moves(&self) -> Vec<&str> {
+        self.operands("bm")
+    }
+
+    /// The moves listed by the `am` opcode, in SAN as written in the record
+    pub fn avoid_moves(&self) -> Vec<&str> {
+        self.operands("am")
+    }
+
+    /// Reads the moves of an opcode such as `bm` or `am`, each played from
+    /// the record's position and written in SAN or coordinate notation
+    pub fn moves(&self, opcode: &str) -> Result<Vec<Move>, Error> {
+        let board = self.board()?;
+        self.operands(opcode)
+            .into_iter()
+            .map(|m| parse_move(&board, m))
+            .collect()
+    }
+
+    fn operands(&self, opcode: &str) -> Vec<&str> {
+        match self.get(opcode) {
+            Some(o) => o.operands.iter().map(|o| o.as_str()).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn parse_operations(data: &str) -> Result<Vec<Operation>, Error> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in data.chars() {
+        if quoted {
+            if c == '"' {
+                quoted = false;
+                tokens.push(std::mem::take(&mut token));
+            } else {
+                token.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            ';' => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+
+                if tokens.is_empty() {
+                    return Err(FenError::EmptyEpdOperation.into());
+                }
+
+                let opcode = tokens.remove(0);
+                operations.push(Operation {
+                    opcode,
+                    operands: std::mem::take(&mut tokens),
+                });
+            }
+            c if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(FenError::UnterminatedEpdString.into());
+    }
+
+    if !token.is_empty() || !tokens.is_empty() {
+        return Err(FenError::UnterminatedEpdOperation.into());
+    }
+
+    Ok(operations)
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.position)?;
+
+        for operation in self.operations.iter() {
+            write!(f, " {}", operation.opcode)?;
+
+            for operand in operation.operands.iter() {
+                // string operands need quotes, as does anything with spaces
+                let is_string = operation.opcode == "id"
+                    || (operation.opcode.len() == 2 && operation.opcode.starts_with('c'));
+
+                if is_string || operand.contains(char::is_whitespace) || operand.contains(';') {
+                    write!(f, " \"{}\"", operand)?;
+                } else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+
+            write!(f, ";")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a move of a record in coordinate notation or SAN
+pub(crate) fn parse_move(board: &Board, m: &str) -> Result<Move, Error> {
+    Move::parse(board, m).or_else(|_| pgn::parse_san(board, m))
+}
+
+/// How the engine did on one record of a test suite
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuiteResult {
+    pub id: Option<String>,
+    /// The move the engine chose, in SAN
+    pub played: Option<String>,
+    /// Whether the move is one of the `bm` moves and none of the `am` ones
+    pub solved: bool,
+}
+
+/// The results of running an engine on a test suite. It displays on one
+/// line as the count and the records missed, with the moves played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuiteReport {
+    pub results: Vec<SuiteResult>,
+}
+
+impl SuiteReport {
+    /// Returns how many records the engine solved
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|r| r.solved).count()
+    }
+}
+
+impl Display for SuiteReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "found {} of {} best moves",
+            self.solved(),
+            self.results.len()
+        )?;
+
+        let missed: Vec<String> = self
+            .results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| !result.solved)
+            .map(|(i, result)| {
+                format!(
+                    "{} ({})",
+                    result.id.clone().unwrap_or_else(|| (i + 1).to_string()),
+                    result.played.as_deref().unwrap_or("-")
+                )
+            })
+            .collect();
+        if !missed.is_empty() {
+            write!(f, ", missed {}", missed.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Searches every record of a test suite and checks the engine's move
+/// against its `bm` and `am` opcodes
+pub fn run_suite(
+    records: &[Epd],
+    engine: &mut dyn Backend,
+    limits: &Limits,
+) -> Result<SuiteReport, Error> {
+    let mut results = Vec::new();
+
+    for record in records {
+        let board = record.board()?;
+        let best = record.moves("bm")?;
+        let avoid = record.moves("am")?;
+
+        let evaluation = engine.analyze(&board, limits, &StopToken::new())?;
+        let played = evaluation.line.first().copied();
+
+        let solved =
+            played.is_some_and(|m| (best.is_empty() || best.contains(&m)) && !avoid.contains(&m));
+
+        results.push(SuiteResult {
+            id: record.id().map(str::to_string),
+            played: played.map(|m| pgn::san(&board, &m)).transpose()?,
+            solved,
+        });
+    }
+
+    Ok(SuiteReport { results })
+}
+
+/// Reads every record from an EPD file, skipping blank lines
+pub fn load(filename: &str) -> Result<Vec<Epd>, Error> {
+    let file = File::open(filename)?;
+    let reader = BufReader::new(file);
+
+    let mut records = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            records.push(Epd::parse(&line)?);
+        }
+    }
+
+    Ok(records)
+}
+
+pub fn save(filename: &str, records: &[Epd]) -> Result<(), Error> {
+    let file = File::create(filename)?;
+    let mut writer = BufWriter::new(file);
+
+    for record in records {
+        writer.write_all(record.to_string().as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use std::fmt::Display;
+
+use crate::{
+    chess::{Class, Color, Square},
+    mover::IllegalReason,
+    variant::Variant,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidInput,
+    InvalidFen(FenError),
+    InvalidMove(MoveError),
+    /// Reading or writing a file or stream failed
+    Io(std::io::Error),
+    TimeExpired(Color),
+    GameOver,
+    Network(String),
+    /// The system clipboard could not be read or written
+    Clipboard(String),
+    /// An external engine failed or broke the protocol
+    Engine(String),
+    UnknownCommand {
+        name: String,
+        /// The closest known command, if any is close enough
+        suggestion: Option<&'static str>,
+    },
+    /// A command got the wrong number of arguments; holds its usage line
+    Usage(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<FenError> for Error {
+    fn from(e: FenError) -> Self {
+        Error::InvalidFen(e)
+    }
+}
+
+impl From<MoveError> for Error {
+    fn from(e: MoveError) -> Self {
+        Error::InvalidMove(e)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "Invalid input"),
+            Error::InvalidFen(fen) => write!(f, "Invalid FEN: {}", fen),
+            Error::InvalidMove(m) => write!(f, "Invalid move: {}", m),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::TimeExpired(color) => write!(f, "Time expired for {}", color),
+            Error::GameOver => write!(f, "The game is over"),
+            Error::Network(e) => write!(f, "Network error: {}", e),
+            Error::Clipboard(e) => write!(f, "Clipboard error: {}", e),
+            Error::Engine(e) => write!(f, "Engine error: {}", e),
+            Error::UnknownCommand {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "Unknown command '{}', did you mean '{}'?",
+                name, suggestion
+            ),
+            Error::UnknownCommand { name, .. } => write!(f, "Unknown command '{}'", name),
+            Error::Usage(usage) => write!(f, "Usage: {}", usage),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidFen(e) => Some(e),
+            Error::InvalidMove(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Why a move was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    NoPiece {
+        at: Square,
+    },
+    NotYourPiece {
+        at: Square,
+    },
+    NotYourTurn,
+    NoMovement,
+    OwnPiece {
+        at: Square,
+    },
+    PawnBackwards,
+    /// A pawn push longer than allowed, `distance` being the ranks moved and
+    /// `first_move` whether the pawn was on its start rank
+    PawnTooFar {
+        distance: usize,
+        first_move: bool,
+    },
+    PawnCaptureNotDiagonal,
+    PawnDiagonalWithoutCapture,
+    /// A pawn push onto an occupied square
+    PawnPushOccupied {
+        at: Square,
+    },
+    /// A double pawn push over a piece
+    PawnPushBlocked {
+        at: Square,
+    },
+    /// The piece does not move in that direction or over that distance
+    InvalidPath(Class),
+    Blocked {
+        piece: Class,
+        at: Square,
+    },
+    CaptureRequired,
+    /// The move would leave or put the mover's king in check
+    KingInCheck(IllegalReason),
+    NullMoveInCheck,
+    DropsNotAllowed(Variant),
+    NotInHand(Class),
+    DropOccupied {
+        at: Square,
+    },
+    PawnDropOnBackRank,
+    /// The move, in the notation it was given, can't be played
+    NotLegal(String),
+    Ambiguous(String),
+    /// The text is not a move in coordinate notation
+    Malformed(String),
+    /// A pawn reaching the last rank has to name the piece it becomes
+    PromotionRequired,
+    /// The piece can't be promoted to, or the move is no promotion at all
+    InvalidPromotion(Class),
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::NoPiece { .. } => write!(f, "No piece on square"),
+            MoveError::NotYourPiece { .. } => write!(f, "Not your piece"),
+            MoveError::NotYourTurn => write!(f, "Not your turn"),
+            MoveError::NoMovement => write!(f, "Piece must move"),
+            MoveError::OwnPiece { .. } => write!(f, "Can't capture your own piece"),
+            MoveError::PawnBackwards => write!(f, "Pawn can only move forward"),
+            MoveError::PawnTooFar {
+                distance,
+                first_move: true,
+            } => write!(
+                f,
+                "Pawn can only move one or two squares forward on the first move, attempted to move {} squares",
+                distance
+            ),
+            MoveError::PawnTooFar { .. } => write!(f, "Pawn can only move one square forward"),
+            MoveError::PawnCaptureNotDiagonal => write!(f, "Pawn can only capture diagonally"),
+            MoveError::PawnDiagonalWithoutCapture => write!(f, "Pawn can not move diagonally"),
+            MoveError::PawnPushOccupied { .. } => {
+                write!(f, "Pawn can only move forward onto an empty square")
+            }
+            MoveError::PawnPushBlocked { .. } => {
+                write!(f, "Pawn can not jump over the piece in front of it")
+            }
+            MoveError::InvalidPath(class) => match class {
+                Class::Pawn => write!(f, "Pawn can only move forward"),
+                Class::Knight => write!(f, "Knight can only move two squares forward and one square sideways, or two squares sideways and one square forward"),
+                Class::Bishop => write!(f, "Bishop can only move diagonally"),
+                Class::Rook => write!(f, "Rook can only move horizontally or vertically"),
+                Class::Queen => write!(f, "Queen can only move horizontally, vertically, or diagonally"),
+                Class::King => write!(f, "King can only move one square in any direction"),
+            },
+            MoveError::Blocked { piece, .. } => {
+                write!(f, "{:?} can not move through pieces", piece)
+            }
+            MoveError::CaptureRequired => write!(f, "A capture has to be made"),
+            MoveError::KingInCheck(reason) => write!(f, "{}", reason),
+            MoveError::NullMoveInCheck => write!(f, "Can't pass the move while in check"),
+            MoveError::DropsNotAllowed(variant) => {
+                write!(f, "Pieces can't be dropped in {} chess", variant)
+            }
+            MoveError::NotInHand(_) => write!(f, "No such piece in hand"),
+            MoveError::DropOccupied { .. } => {
+                write!(f, "Pieces can only be dropped on empty squares")
+            }
+            MoveError::PawnDropOnBackRank => {
+                write!(f, "Pawns can't be dropped on the first or last rank")
+            }
+            MoveError::NotLegal(m) => write!(f, "{} is not a legal move", m),
+            MoveError::Ambiguous(m) => write!(f, "{} is ambiguous", m),
+            MoveError::Malformed(m) => write!(f, "'{}' is not a move", m),
+            MoveError::PromotionRequired => write!(f, "Pawn has to promote on the last rank"),
+            MoveError::InvalidPromotion(class) => write!(f, "Can't promote to {:?}", class),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// What is wrong with a FEN or EPD record. Ranks are counted from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    /// A required field is absent, named as in the messages, e.g. `castling`
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    /// Text after the last field, the fullmove number
+    TrailingField(String),
+    RankCount(usize),
+    EmptyCount {
+        count: char,
+        rank: usize,
+    },
+    InvalidPiece(char),
+    RankTooLong {
+        rank: usize,
+    },
+    RankLength {
+        rank: usize,
+        files: usize,
+    },
+    UnterminatedPocket,
+    PocketNotAllowed(Variant),
+    InvalidPocketPiece(char),
+    PawnOnBackRank {
+        color: Color,
+        rank: usize,
+    },
+    MissingKing(Color),
+    TooManyKings {
+        color: Color,
+        count: usize,
+    },
+    TooManyPawns {
+        color: Color,
+        count: usize,
+    },
+    TooManyPieces {
+        color: Color,
+        count: usize,
+    },
+    /// The given color is in check while it is the other side to move
+    OpponentInCheck(Color),
+    EnPassantWrongSide,
+    EnPassantWithoutPawn,
+    EnPassantOccupied,
+    CastlingWithoutPieces(Color),
+    CastlingWithoutKing,
+    /// A Shredder-FEN or X-FEN castling right for a king or rook off its
+    /// standard square, as only Chess960 has
+    Chess960Castling(char),
+    EpdFields,
+    EmptyEpdOperation,
+    UnterminatedEpdString,
+    UnterminatedEpdOperation,
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {}", field),
+            FenError::InvalidField(field) => write!(f, "invalid {}", field),
+            FenError::TrailingField(field) => {
+                write!(f, "unexpected '{}' after the fullmove number", field)
+            }
+            FenError::RankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::EmptyCount { count, rank } => write!(
+                f,
+                "invalid empty square count '{}' on rank {}",
+                count,
+                rank + 1
+            ),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{}'", c),
+            FenError::RankTooLong { rank } => write!(f, "rank {} has more than 8 files", rank + 1),
+            FenError::RankLength { rank, files } => {
+                write!(f, "rank {} has {} files, expected 8", rank + 1, files)
+            }
+            FenError::UnterminatedPocket => write!(f, "unterminated pocket"),
+            FenError::PocketNotAllowed(variant) => write!(f, "pocket in a {} position", variant),
+            FenError::InvalidPocketPiece(c) => write!(f, "invalid pocket piece '{}'", c),
+            FenError::PawnOnBackRank { color, rank } => {
+                write!(f, "{} pawn on rank {}", color, rank + 1)
+            }
+            FenError::MissingKing(color) => write!(f, "missing {} king", color),
+            FenError::TooManyKings { color, count } => write!(f, "{} has {} kings", color, count),
+            FenError::TooManyPawns { color, count } => write!(f, "{} has {} pawns", color, count),
+            FenError::TooManyPieces { color, count } => {
+                write!(f, "{} has {} pieces", color, count)
+            }
+            FenError::OpponentInCheck(color) => write!(
+                f,
+                "{} is in check but it is {} to move",
+                color,
+                color.opponent()
+            ),
+            FenError::EnPassantWrongSide => {
+                write!(f, "en passant square does not match side to move")
+            }
+            FenError::EnPassantWithoutPawn => {
+                write!(f, "en passant square without a pawn that just moved")
+            }
+            FenError::EnPassantOccupied => write!(f, "en passant square is occupied"),
+            FenError::CastlingWithoutPieces(color) => write!(
+                f,
+                "{} castling rights without king and rook on their starting squares",
+                color
+            ),
+            FenError::CastlingWithoutKing => write!(f, "castling rights without a king"),
+            FenError::Chess960Castling(c) => write!(
+                f,
+                "castling right '{}' is for Chess960, which is not supported",
+                c
+            ),
+            FenError::EpdFields => write!(f, "EPD needs four position fields"),
+            FenError::EmptyEpdOperation => write!(f, "empty EPD operation"),
+            FenError::UnterminatedEpdString => write!(f, "unterminated EPD string"),
+            FenError::UnterminatedEpdOperation => {
+                write!(f, "EPD operation missing terminating ';'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
@@ -0,0 +1,542 @@
+//! Static evaluation. Every term is scored separately for the middlegame and
+//! the endgame, and the two are blended by how much material is left.
+
+use std::{
+    ops::{Add, AddAssign, Neg, Sub},
+    sync::OnceLock,
+};
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    variant::DROPPABLE,
+};
+
+const CLASS_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+/// A middlegame and an endgame value in centipawns
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Score {
+        Score { mg, eg }
+    }
+
+    /// Blends the two values by the game phase, 24 being the opening and 0
+    /// a bare endgame
+    pub fn taper(&self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+/// Phase of the starting position
+pub const MAX_PHASE: i32 = 24;
+
+/// The weights of the evaluation. Piece-square tables are indexed by
+/// `rank * 8 + file` from white's side and mirrored for black.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// Piece values, indexed by `Class as usize`
+    pub material: [Score; 6],
+    pub tables: [[Score; 64]; 6],
+    /// Penalty for every pawn behind another one on the same file
+    pub doubled_pawn: Score,
+    /// Penalty for a pawn without friendly pawns on the adjacent files
+    pub isolated_pawn: Score,
+    /// Bonus for a passed pawn by the rank it has reached, counted from its
+    /// own side
+    pub passed_pawn: [Score; 8],
+    /// Bonus for each pawn right in front of the king
+    pub pawn_shield: Score,
+    /// Penalty for each file next to or under the king without a friendly
+    /// pawn
+    pub open_king_file: Score,
+    /// Bonus for every square a piece can move to, indexed by
+    /// `Class as usize`
+    pub mobility: [Score; 6],
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        let mut tables = [[Score::default(); 64]; 6];
+
+        for (class, table) in tables.iter_mut().enumerate() {
+            let (mg, eg) = match class {
+                0 => (&PAWN_MG, &PAWN_EG),
+                1 => (&KNIGHT, &KNIGHT),
+                2 => (&BISHOP, &BISHOP),
+                3 => (&ROOK, &ROOK),
+                4 => (&QUEEN, &QUEEN),
+                _ => (&KING_MG, &KING_EG),
+            };
+
+            for (square, score) in table.iter_mut().enumerate() {
+                // the tables below are laid out with rank 8 on top
+                let index = (7 - square / 8) * 8 + square % 8;
+                *score = Score::new(mg[index], eg[index]);
+            }
+        }
+
+        Params {
+            material: [
+                Score::new(100, 120),
+                Score::new(320, 300),
+                Score::new(330, 320),
+                Score::new(500, 530),
+                Score::new(900, 950),
+                Score::new(0, 0),
+            ],
+            tables,
+            doubled_pawn: Score::new(-10, -20),
+            isolated_pawn: Score::new(-10, -15),
+            passed_pawn: [
+                Score::new(0, 0),
+                Score::new(5, 10),
+                Score::new(10, 20),
+                Score::new(15, 35),
+                Score::new(25, 60),
+                Score::new(40, 90),
+                Score::new(60, 130),
+                Score::new(0, 0),
+            ],
+            pawn_shield: Score::new(10, 0),
+            open_king_file: Score::new(-15, 0),
+            mobility: [
+                Score::new(0, 0),
+                Score::new(4, 4),
+                Score::new(5, 5),
+                Score::new(2, 4),
+                Score::new(1, 2),
+                Score::new(0, 0),
+            ],
+        }
+    }
+}
+
+impl Params {
+    /// The default weights, built once
+    pub fn standard() -> &'static Params {
+        static PARAMS: OnceLock<Params> = OnceLock::new();
+        PARAMS.get_or_init(Params::default)
+    }
+
+    /// Returns every weight with its name in the config format, e.g.
+    /// `material.knight` or `table.pawn.e4`
+    pub(crate) fn weights_mut(&mut self) -> Vec<(String, &mut Score)> {
+        let mut weights = Vec::new();
+
+        for (class, score) in self.material.iter_mut().enumerate() {
+            weights.push((format!("material.{}", CLASS_NAMES[class]), score));
+        }
+
+        for (class, table) in self.tables.iter_mut().enumerate() {
+            for (square, score) in table.iter_mut().enumerate() {
+                let file = (b'a' + (square % 8) as u8) as char;
+                let name = format!("table.{}.{}{}", CLASS_NAMES[class], file, square / 8 + 1);
+                weights.push((name, score));
+            }
+        }
+
+        weights.push(("doubled_pawn".to_string(), &mut self.doubled_pawn));
+        weights.push(("isolated_pawn".to_string(), &mut self.isolated_pawn));
+
+        for (rank, score) in self.passed_pawn.iter_mut().enumerate() {
+            weights.push((format!("passed_pawn.{}", rank + 1), score));
+        }
+
+        weights.push(("pawn_shield".to_string(), &mut self.pawn_shield));
+        weights.push(("open_king_file".to_string(), &mut self.open_king_file));
+
+        for (class, score) in self.mobility.iter_mut().enumerate() {
+            weights.push((format!("mobility.{}", CLASS_NAMES[class]), score));
+        }
+
+        weights
+    }
+
+    /// Writes the weights one per line as `<name> <middlegame> <endgame>`
+    pub fn to_config(&self) -> String {
+        let mut params = self.clone();
+
+        params
+            .weights_mut()
+            .into_iter()
+            .map(|(name, score)| format!("{} {} {}\n", name, score.mg, score.eg))
+            .collect()
+    }
+
+    /// Reads weights written by `to_config`. Weights that are left out keep
+    /// their default value; blank lines and `#` comments are skipped.
+    pub fn from_config(config: &str) -> Result<Params, Error> {
+        let mut params = Params::default();
+
+        {
+            let mut weights = params.weights_mut();
+
+            for line in config.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [name, mg, eg] = fields[..] else {
+                    return Err(Error::InvalidInput);
+                };
+
+                let (_, score) = weights
+                    .iter_mut()
+                    .find(|(weight, _)| weight == name)
+                    .ok_or(Error::InvalidInput)?;
+
+                **score = Score::new(
+                    mg.parse().map_err(|_| Error::InvalidInput)?,
+                    eg.parse().map_err(|_| Error::InvalidInput)?,
+                );
+            }
+        }
+
+        Ok(params)
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        std::fs::write(filename, self.to_config())?;
+        Ok(())
+    }
+
+    pub fn load(filename: &str) -> Result<Params, Error> {
+        Params::from_config(&std::fs::read_to_string(filename)?)
+    }
+}
+
+/// The evaluation split into its terms, each from white's point of view
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Terms {
+    pub material: Score,
+    pub placement: Score,
+    pub pawns: Score,
+    pub king_safety: Score,
+    pub mobility: Score,
+    pub phase: i32,
+}
+
+impl Terms {
+    pub fn sum(&self) -> Score {
+        self.material + self.placement + self.pawns + self.king_safety + self.mobility
+    }
+
+    /// The final score in centipawns from white's point of view
+    pub fn total(&self) -> i32 {
+        self.sum().taper(self.phase)
+    }
+}
+
+/// Scores the position in centipawns from white's point of view
+pub fn evaluate(board: &Board, params: &Params) -> i32 {
+    let terms = terms(board, params);
+
+    // giving material away is the goal in antichess, and the king is just
+    // another piece, so only the flipped material balance counts
+    if board.variant().wins_without_pieces() {
+        return -terms.material.taper(terms.phase);
+    }
+
+    terms.total()
+}
+
+pub fn terms(board: &Board, params: &Params) -> Terms {
+    let mut terms = Terms::default();
+
+    for color in [Color::White, Color::Black] {
+        let sign = |score: Score| match color {
+            Color::White => score,
+            Color::Black => -score,
+        };
+
+        let (material, placement) = pieces(board, params, color);
+        terms.material += sign(material);
+        terms.placement += sign(placement);
+        terms.pawns += sign(pawns(board, params, color));
+        terms.king_safety += sign(king_safety(board, params, color));
+        terms.mobility += sign(mobility(board, params, color));
+    }
+
+    terms.phase = phase(board);
+
+    terms
+}
+
+/// Returns how far the game is from the endgame by the pieces left
+pub fn phase(board: &Board) -> i32 {
+    let phase: i32 = [Color::White, Color::Black]
+        .into_iter()
+        .map(|color| {
+            let material = board.material(color);
+            (material.knights + material.bishops) as i32
+                + 2 * material.rooks as i32
+                + 4 * material.queens as i32
+        })
+        .sum();
+
+    phase.min(MAX_PHASE)
+}
+
+/// The rank as seen from the given side, 0 being its back rank
+fn relative_rank(color: Color, rank: usize) -> usize {
+    match color {
+        Color::White => rank,
+        Color::Black => 7 - rank,
+    }
+}
+
+fn pieces(board: &Board, params: &Params, color: Color) -> (Score, Score) {
+    let mut material = Score::default();
+    let mut placement = Score::default();
+
+    for file in 0..8 {
+        for rank in 0..8 {
+            let piece = match board.get_piece(file, rank) {
+                Some(piece) if piece.color == color => piece,
+                _ => continue,
+            };
+
+            let class = piece.class as usize;
+            material += params.material[class];
+            placement += params.tables[class][relative_rank(color, rank) * 8 + file];
+        }
+    }
+
+    // pieces in hand count as material in variants with drops
+    for class in DROPPABLE {
+        for _ in 0..board.pocket(color).count(class) {
+            material += params.material[class as usize];
+        }
+    }
+
+    (material, placement)
+}
+
+/// Returns the ranks holding a pawn of the given color on each file
+fn pawn_files(board: &Board, color: Color) -> [Vec<usize>; 8] {
+    let mut files: [Vec<usize>; 8] = Default::default();
+
+    for (file, ranks) in files.iter_mut().enumerate() {
+        for rank in 0..8 {
+            if board
+                .get_piece(file, rank)
+                .is_some_and(|p| p.class == Class::Pawn && p.color == color)
+            {
+                ranks.push(rank);
+            }
+        }
+    }
+
+    files
+}
+
+fn pawns(board: &Board, params: &Params, color: Color) -> Score {
+    let own = pawn_files(board, color);
+    let theirs = pawn_files(board, color.opponent());
+
+    let mut score = Score::default();
+
+    for file in 0..8 {
+        for _ in 1..own[file].len() {
+            score += params.doubled_pawn;
+        }
+
+        let neighbours = [file.wrapping_sub(1), file + 1]
+            .into_iter()
+            .filter(|&f| f < 8)
+            .collect::<Vec<usize>>();
+
+        for &rank in own[file].iter() {
+            if neighbours.iter().all(|&f| own[f].is_empty()) {
+                score += params.isolated_pawn;
+            }
+
+            // passed when no enemy pawn is ahead on this or an adjacent file
+            let ahead = |r: usize| relative_rank(color, r) > relative_rank(color, rank);
+            let blocked = neighbours
+                .iter()
+                .chain(std::iter::once(&file))
+                .any(|&f| theirs[f].iter().any(|&r| ahead(r)));
+
+            if !blocked {
+                score += params.passed_pawn[relative_rank(color, rank)];
+            }
+        }
+    }
+
+    score
+}
+
+fn mobility(board: &Board, params: &Params, color: Color) -> Score {
+    let mut score = Score::default();
+
+    for piece in board.piece_mobility(color) {
+        for _ in 0..piece.moves {
+            score += params.mobility[piece.class as usize];
+        }
+    }
+
+    score
+}
+
+fn king_safety(board: &Board, params: &Params, color: Color) -> Score {
+    let (king_file, king_rank) = match board.king_square(color) {
+        Some(square) => square,
+        None => return Score::default(),
+    };
+
+    let own = pawn_files(board, color);
+    let mut score = Score::default();
+
+    let files = king_file.saturating_sub(1)..=(king_file + 1).min(7);
+
+    for ranks in own[files].iter() {
+        let shield = ranks.iter().any(|&rank| {
+            let distance =
+                relative_rank(color, rank) as i32 - relative_rank(color, king_rank) as i32;
+            (1..=2).contains(&distance)
+        });
+
+        if shield {
+            score += params.pawn_shield;
+        }
+
+        if ranks.is_empty() {
+            score += params.open_king_file;
+        }
+    }
+
+    score
+}
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
@@ -0,0 +1,201 @@
+//! C interface to the board. Strings returned by these functions are owned by
+//! the caller and must be released with `chess_string_free`.
+
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    chess::{Board, Color, Status},
+    fen::ToFen,
+};
+
+/// Opaque handle to a board
+pub struct ChessBoard {
+    board: Board,
+}
+
+#[repr(C)]
+pub enum ChessStatus {
+    /// Returned for a null board
+    Invalid = -1,
+    Ongoing = 0,
+    Check = 1,
+    Checkmate = 2,
+    Stalemate = 3,
+}
+
+#[repr(C)]
+pub enum ChessColor {
+    /// Returned for a null board
+    Invalid = -1,
+    White = 0,
+    Black = 1,
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Creates a board with the default starting position, or null on failure
+#[no_mangle]
+pub extern "C" fn chess_board_new() -> *mut ChessBoard {
+    match Board::default_board() {
+        Ok(board) => Box::into_raw(Box::new(ChessBoard { board })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Releases a board created by `chess_board_new`
+///
+/// # Safety
+///
+/// `board` must be null or a pointer returned by `chess_board_new` that has
+/// not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_free(board: *mut ChessBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Replaces the position with the given FEN. Returns 0 on success and -1 if
+/// the FEN is invalid, in which case the board keeps its position.
+///
+/// # Safety
+///
+/// `board` must be a valid board pointer and `fen` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_load_fen(board: *mut ChessBoard, fen: *const c_char) -> c_int {
+    if board.is_null() || fen.is_null() {
+        return -1;
+    }
+
+    let fen = match CStr::from_ptr(fen).to_str() {
+        Ok(fen) => fen,
+        Err(_) => return -1,
+    };
+
+    let board = &mut (*board).board;
+    let previous = board.clone();
+
+    match board.from_fen(fen) {
+        Ok(_) => 0,
+        Err(_) => {
+            *board = previous;
+            -1
+        }
+    }
+}
+
+/// Returns the position as FEN
+///
+/// # Safety
+///
+/// `board` must be a valid board pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_fen(board: *const ChessBoard) -> *mut c_char {
+    if board.is_null() {
+        return ptr::null_mut();
+    }
+
+    into_c_string((*board).board.to_fen())
+}
+
+/// Plays a move in coordinate notation such as "e2e4". Returns 0 on success
+/// and -1 if the move is not valid.
+///
+/// # Safety
+///
+/// `board` must be a valid board pointer and `m` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_make_move(board: *mut ChessBoard, m: *const c_char) -> c_int {
+    if board.is_null() || m.is_null() {
+        return -1;
+    }
+
+    let m = match CStr::from_ptr(m).to_str() {
+        Ok(m) => m,
+        Err(_) => return -1,
+    };
+
+    match (*board).board.move_piece(m) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Returns every legal move as a space separated string
+///
+/// # Safety
+///
+/// `board` must be a valid board pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_legal_moves(board: *const ChessBoard) -> *mut c_char {
+    if board.is_null() {
+        return ptr::null_mut();
+    }
+
+    let moves: Vec<String> = (*board)
+        .board
+        .legal_moves()
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+
+    into_c_string(moves.join(" "))
+}
+
+/// Returns the side to move, or `Invalid` for a null board
+///
+/// # Safety
+///
+/// `board` must be null or a valid board pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_turn(board: *const ChessBoard) -> ChessColor {
+    if board.is_null() {
+        return ChessColor::Invalid;
+    }
+
+    match (*board).board.turn() {
+        Color::White => ChessColor::White,
+        Color::Black => ChessColor::Black,
+    }
+}
+
+/// Returns whether the side to move is in check, mated or stalemated, or
+/// `Invalid` for a null board
+///
+/// # Safety
+///
+/// `board` must be null or a valid board pointer.
+#[no_mangle]
+pub unsafe extern "C" fn chess_board_status(board: *const ChessBoard) -> ChessStatus {
+    if board.is_null() {
+        return ChessStatus::Invalid;
+    }
+
+    match (*board).board.status() {
+        Status::Ongoing => ChessStatus::Ongoing,
+        Status::Check => ChessStatus::Check,
+        Status::Checkmate => ChessStatus::Checkmate,
+        Status::Stalemate => ChessStatus::Stalemate,
+    }
+}
+
+/// Releases a string returned by one of the functions above
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chess_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
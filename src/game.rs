@@ -0,0 +1,984 @@
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    chess::{save_tokens, split_save, Board, Color, Error, SaveToken, Status},
+    clock::Clock,
+    codec,
+    eco::{self, EcoCode},
+    engine::{Backend, MoveAnalysis},
+    fen::ToFen,
+    mover::{Move, MoveOutcome},
+    net::quote,
+    pgn::{self, Nag},
+    stats::Statistics,
+    variant::Variant,
+    variation::{MoveTree, Node},
+    zobrist::{pocket_key, polyglot_key},
+};
+
+/// Why a draw can be claimed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DrawClaim {
+    /// The position has occurred three times
+    Repetition,
+    /// Fifty moves by each side without a capture or pawn move
+    FiftyMoves,
+}
+
+impl Display for DrawClaim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawClaim::Repetition => write!(f, "threefold repetition"),
+            DrawClaim::FiftyMoves => write!(f, "fifty-move rule"),
+        }
+    }
+}
+
+/// How a game ended
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    /// A player ran out of time
+    Timeout,
+    /// The players agreed to a draw
+    Agreement,
+    Stalemate,
+    /// Threefold repetition claimed, or fivefold repetition
+    Repetition,
+    /// The fifty-move rule claimed, or the seventy-five-move rule
+    FiftyMoves,
+    InsufficientMaterial,
+    /// A player left the game
+    Abandonment,
+    /// Decided without being played out, as engine matches do with lopsided
+    /// or dead even games
+    Adjudication,
+    /// Ended by a rule of the variant, such as running out of pieces or
+    /// moves in antichess
+    VariantEnd,
+}
+
+impl From<DrawClaim> for Termination {
+    fn from(claim: DrawClaim) -> Termination {
+        match claim {
+            DrawClaim::Repetition => Termination::Repetition,
+            DrawClaim::FiftyMoves => Termination::FiftyMoves,
+        }
+    }
+}
+
+impl Termination {
+    /// Returns the value of the PGN `Termination` tag, which tells only the
+    /// ends of the game outside the rules apart from a `normal` one
+    pub fn pgn_value(&self) -> &'static str {
+        match self {
+            Termination::Timeout => "time forfeit",
+            Termination::Abandonment => "abandoned",
+            Termination::Adjudication => "adjudication",
+            _ => "normal",
+        }
+    }
+
+    /// Returns the PGN tags telling how the game ended: `Termination` with
+    /// its standard value, and `TerminationDetails` with the reason when
+    /// the standard value doesn't say
+    pub fn pgn_tags(&self) -> Vec<(&'static str, String)> {
+        let mut tags = vec![("Termination", self.pgn_value().to_string())];
+        if self.pgn_value() == "normal" {
+            tags.push(("TerminationDetails", self.to_string()));
+        }
+        tags
+    }
+}
+
+impl TryFrom<&str> for Termination {
+    type Error = crate::chess::Error;
+
+    /// Parses a termination as written by `Display`, in any case
+    fn try_from(termination: &str) -> Result<Termination, Error> {
+        match termination.trim().to_lowercase().as_str() {
+            "checkmate" => Ok(Termination::Checkmate),
+            "resignation" => Ok(Termination::Resignation),
+            "time forfeit" | "timeout" => Ok(Termination::Timeout),
+            "agreement" => Ok(Termination::Agreement),
+            "stalemate" => Ok(Termination::Stalemate),
+            "repetition" => Ok(Termination::Repetition),
+            "fifty-move rule" => Ok(Termination::FiftyMoves),
+            "insufficient material" => Ok(Termination::InsufficientMaterial),
+            "abandoned" | "abandonment" => Ok(Termination::Abandonment),
+            "adjudication" => Ok(Termination::Adjudication),
+            "variant end" => Ok(Termination::VariantEnd),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Termination::Checkmate => write!(f, "checkmate"),
+            Termination::Resignation => write!(f, "resignation"),
+            Termination::Timeout => write!(f, "time forfeit"),
+            Termination::Agreement => write!(f, "agreement"),
+            Termination::Stalemate => write!(f, "stalemate"),
+            Termination::Repetition => write!(f, "repetition"),
+            Termination::FiftyMoves => write!(f, "fifty-move rule"),
+            Termination::InsufficientMaterial => write!(f, "insufficient material"),
+            Termination::Abandonment => write!(f, "abandoned"),
+            Termination::Adjudication => write!(f, "adjudication"),
+            Termination::VariantEnd => write!(f, "variant end"),
+        }
+    }
+}
+
+fn position_key(board: &Board) -> u64 {
+    polyglot_key(board) ^ pocket_key(board)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GameResult {
+    Ongoing,
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// The result for a win by the given color
+    pub fn win(color: Color) -> GameResult {
+        match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+}
+
+impl TryFrom<&str> for GameResult {
+    type Error = crate::chess::Error;
+
+    /// Parses a result as written in PGN, e.g. `1-0` or `1/2-1/2`
+    fn try_from(result: &str) -> Result<GameResult, Error> {
+        match result.trim() {
+            "*" => Ok(GameResult::Ongoing),
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::Ongoing => write!(f, "*"),
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+        }
+    }
+}
+
+/// A move as it was played in a game
+#[derive(Debug, Clone)]
+pub struct GameMove {
+    pub m: Move,
+    pub color: Color,
+    pub played_at: SystemTime,
+    /// Time spent on the move, when the game has a clock
+    pub think: Option<Duration>,
+    pub annotation: Option<String>,
+    /// Glyphs such as `!?` judging the move
+    pub nags: Vec<Nag>,
+    /// Engine analysis, when analysis mode was on
+    pub analysis: Option<MoveAnalysis>,
+}
+
+/// A named position of a game, kept as the moves leading to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub line: Vec<Move>,
+}
+
+impl Bookmark {
+    /// Returns the number of moves played up to the position
+    pub fn ply(&self) -> usize {
+        self.line.len()
+    }
+}
+
+/// A game session: the board plus everything that is not part of the rules
+pub struct Game {
+    board: Board,
+    start: String,
+    white: String,
+    black: String,
+    result: GameResult,
+    termination: Option<Termination>,
+    clock: Option<Clock>,
+    history: Vec<GameMove>,
+    /// Moves taken back with `undo`, most recent last
+    undone: Vec<GameMove>,
+    /// Every line played, including those replaced after an undo
+    tree: MoveTree,
+    /// Keys of every position so far, for spotting repetitions
+    keys: Vec<u64>,
+    draw_offer: Option<Color>,
+    engine: Option<Box<dyn Backend>>,
+    tags: Vec<(String, String)>,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Game {
+    /// Creates a game from the default starting position
+    pub fn new() -> Result<Game, Error> {
+        Ok(Game::from_board(Board::default_board()?))
+    }
+
+    /// Creates a game continuing from an existing position
+    pub fn from_board(board: Board) -> Game {
+        Game {
+            keys: vec![position_key(&board)],
+            start: board.to_fen(),
+            board,
+            white: "White".to_string(),
+            black: "Black".to_string(),
+            result: GameResult::Ongoing,
+            termination: None,
+            clock: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+            tree: MoveTree::new(),
+            draw_offer: None,
+            engine: None,
+            tags: Vec::new(),
+            bookmarks: Vec::new(),
+        }
+    }
+
+    /// Loads a game written by `Game::save` or `Board::save`, keeping every
+    /// move in the history and starting from the FEN header when there is
+    /// one, under the variant of the variant header
+    pub fn load(filename: &str) -> Result<Game, Error> {
+        let contents = std::fs::read_to_string(filename)?;
+        let (variant, start, moves) = split_save(&contents)?;
+
+        let mut board = Board::start(variant)?;
+        if let Some(fen) = start {
+            board.from_fen(fen)?;
+        }
+
+        let mut game = Game::from_board(board);
+        for token in save_tokens(moves)? {
+            match token {
+                SaveToken::Move(m) => game.move_piece(m).map(|_| ())?,
+                SaveToken::Nag(nag) => game.add_nag(Nag::try_from(nag)?)?,
+                SaveToken::Comment(text) => game.annotate(text)?,
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Writes the moves played like `Board::save`, each followed by its
+    /// glyphs as `$` tokens and its annotation in braces
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        let mut contents = String::new();
+
+        if self.board.variant() != Variant::Standard {
+            contents.push_str(&format!("[Variant \"{}\"]\n", self.board.variant()));
+        }
+        if self.start != self.board.variant().start_position() {
+            contents.push_str(&format!("[FEN \"{}\"]\n", self.start));
+        }
+
+        for entry in &self.history {
+            contents.push_str(&format!("{} ", entry.m));
+
+            for nag in &entry.nags {
+                contents.push_str(&format!("${} ", nag.0));
+            }
+            if let Some(annotation) = &entry.annotation {
+                contents.push_str(&format!("{{{}}} ", annotation.replace('}', ")")));
+            }
+        }
+
+        std::fs::write(filename, contents)?;
+        Ok(())
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the FEN of the position the game started from
+    pub fn start_fen(&self) -> &str {
+        &self.start
+    }
+
+    /// Returns every position of the game in order, from the start position
+    /// to the current one
+    pub fn positions(&self) -> Result<Vec<Board>, Error> {
+        let mut board = Board::new()?;
+        board.set_variant(self.board.variant());
+        board.from_fen(&self.start)?;
+
+        let mut positions = Vec::new();
+        for entry in &self.history {
+            let next = board.with_move(&entry.m)?;
+            positions.push(board);
+            board = next;
+        }
+        positions.push(board);
+
+        Ok(positions)
+    }
+
+    pub fn white(&self) -> &str {
+        &self.white
+    }
+
+    pub fn black(&self) -> &str {
+        &self.black
+    }
+
+    pub fn set_players(&mut self, white: &str, black: &str) {
+        self.white = white.to_string();
+        self.black = black.to_string();
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// Returns how the game ended, none while it goes on or when a result
+    /// read from a PGN file didn't say
+    pub fn termination(&self) -> Option<Termination> {
+        self.termination
+    }
+
+    /// Records a result reached away from the board, e.g. one read from a
+    /// PGN file
+    pub(crate) fn set_result(&mut self, result: GameResult, termination: Option<Termination>) {
+        self.result = result;
+        self.termination = termination;
+    }
+
+    /// Ends the game, unless it already is over
+    fn finish(&mut self, result: GameResult, termination: Termination) {
+        if !self.is_over() {
+            self.result = result;
+            self.termination = Some(termination);
+        }
+    }
+
+    /// Returns the value of a PGN tag such as `Event` or `ECO`
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Sets a PGN tag, replacing an earlier value of the same name
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result != GameResult::Ongoing
+    }
+
+    /// Sums up the game so far: captures, checks and castling per side,
+    /// the time spent with a clock, and the material balance after every
+    /// move
+    pub fn statistics(&self) -> Result<Statistics, Error> {
+        Statistics::of(self)
+    }
+
+    /// Writes the game as PGN, with the annotations as comments, every
+    /// line played beside the main line as a variation, and the start
+    /// position in a `FEN` tag when it isn't the usual one
+    pub fn to_pgn(&self) -> Result<String, Error> {
+        let variant = self.board.variant();
+        let variant_name = variant.to_string();
+
+        let mut tags: Vec<(&str, &str)> = vec![
+            ("Event", self.tag("Event").unwrap_or("?")),
+            ("Site", self.tag("Site").unwrap_or("?")),
+            ("Date", self.tag("Date").unwrap_or("????.??.??")),
+            ("Round", self.tag("Round").unwrap_or("?")),
+            ("White", &self.white),
+            ("Black", &self.black),
+        ];
+        if variant != Variant::Standard {
+            tags.push(("Variant", &variant_name));
+        }
+        if self.start != variant.start_position() {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", &self.start));
+        }
+        let opening = self.opening().map(|(code, name)| (code.to_string(), name));
+        if let Some((code, name)) = &opening {
+            tags.push(("ECO", code));
+            tags.push(("Opening", name));
+        }
+        let termination = self.termination.map(|t| t.pgn_tags()).unwrap_or_default();
+        for (name, value) in &termination {
+            tags.push((name, value));
+        }
+        for (name, value) in &self.tags {
+            if !tags.iter().any(|(n, _)| n == name) {
+                tags.push((name, value));
+            }
+        }
+
+        let mut start = Board::new()?;
+        start.set_variant(variant);
+        start.from_fen(&self.start)?;
+
+        let ply = pgn::start_ply(&start);
+        let moves = self.tree.to_pgn_moves(start)?;
+        Ok(pgn::write(&tags, &moves, ply, self.result))
+    }
+
+    /// Encodes the game in the compact binary form of `codec`
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        codec::encode(self)
+    }
+
+    /// Reads a game encoded by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, Error> {
+        codec::decode(bytes)
+    }
+
+    pub fn history(&self) -> &[GameMove] {
+        &self.history
+    }
+
+    /// Returns the moves played in SAN, with the `+` or `#` of the checks
+    /// and mates they gave
+    pub fn san_moves(&self) -> Result<Vec<String>, Error> {
+        let mut board = Board::new()?;
+        board.set_variant(self.board.variant());
+        board.from_fen(&self.start)?;
+
+        let mut moves = Vec::new();
+        for entry in &self.history {
+            moves.push(pgn::san(&board, &entry.m)?);
+            board = board.with_move(&entry.m)?;
+        }
+
+        Ok(moves)
+    }
+
+    /// Describes the whole game state as a JSON object: the position, its
+    /// legal moves, status, result, clocks in milliseconds, captured pieces
+    /// and the moves played, in coordinate notation and in SAN
+    pub fn to_json(&self) -> String {
+        let list = |items: Vec<String>| {
+            let quoted: Vec<String> = items.iter().map(|item| quote(item)).collect();
+            format!("[{}]", quoted.join(","))
+        };
+
+        let clock = match &self.clock {
+            Some(clock) => format!(
+                "{{\"white\":{},\"black\":{}}}",
+                clock.remaining(Color::White).as_millis(),
+                clock.remaining(Color::Black).as_millis()
+            ),
+            None => "null".to_string(),
+        };
+
+        let fields = [
+            ("fen", quote(&self.board.to_fen())),
+            ("variant", quote(&self.board.variant().to_string())),
+            ("turn", quote(&self.board.turn().to_string())),
+            (
+                "status",
+                quote(&format!("{:?}", self.board.status()).to_lowercase()),
+            ),
+            ("result", quote(&self.result.to_string())),
+            (
+                "termination",
+                self.termination
+                    .map_or("null".to_string(), |t| quote(&t.to_string())),
+            ),
+            (
+                "legal_moves",
+                list(
+                    self.board
+                        .legal_moves()
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                ),
+            ),
+            ("clock", clock),
+            (
+                "captured",
+                list(self.board.captured().iter().map(|p| p.to_fen()).collect()),
+            ),
+            (
+                "moves",
+                list(self.history.iter().map(|e| String::from(e.m)).collect()),
+            ),
+            ("san", list(self.san_moves().unwrap_or_default())),
+        ];
+
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("{}:{}", quote(key), value))
+            .collect();
+
+        format!("{{{}}}", fields.join(","))
+    }
+
+    /// Identifies the opening from the moves played so far, which only
+    /// standard chess from the usual start position has
+    pub fn opening(&self) -> Option<(EcoCode, String)> {
+        let variant = self.board.variant();
+        if variant != Variant::Standard || self.start != variant.start_position() {
+            return None;
+        }
+
+        let moves: Vec<Move> = self.history.iter().map(|entry| entry.m).collect();
+        eco::classify(&moves)
+    }
+
+    /// Turns analysis mode on with the given engine, or off with `None`.
+    /// While it is on every move is searched and the result kept with it.
+    pub fn set_analysis(&mut self, engine: Option<Box<dyn Backend>>) {
+        self.engine = engine;
+    }
+
+    pub fn is_analyzing(&self) -> bool {
+        self.engine.is_some()
+    }
+
+    /// Attaches a game clock and starts it for the side to move
+    pub fn set_clock(&mut self, mut clock: Clock) {
+        clock.start(self.board.turn());
+        self.clock = Some(clock);
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Ends the game if the side to move has run out of time
+    pub fn check_flag(&mut self) -> Option<Color> {
+        let flagged = self.clock.as_ref().and_then(|c| c.flagged());
+
+        if let Some(color) = flagged {
+            self.finish(GameResult::win(color.opponent()), Termination::Timeout);
+        }
+
+        flagged
+    }
+
+    pub fn move_piece(&mut self, data: &str) -> Result<MoveOutcome, Error> {
+        if let Some(color) = self.check_flag() {
+            return Err(Error::TimeExpired(color));
+        }
+
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        let color = self.board.turn();
+
+        let analysis = match self.engine.as_mut() {
+            Some(engine) => {
+                let m = Move::parse(&self.board, data)?;
+                Some(engine.analyze_move(&self.board, &m)?)
+            }
+            None => None,
+        };
+
+        let before = self.board.snapshot();
+        let outcome = self.board.move_piece(data)?;
+        let think = self.clock.as_ref().and_then(Clock::elapsed);
+
+        // a move the clock doesn't take isn't played, so the board has to
+        // agree with the history again
+        if let Some(clock) = self.clock.as_mut() {
+            if let Err(e) = clock.press() {
+                self.board.restore(&before)?;
+                self.check_flag();
+                return Err(e);
+            }
+        }
+
+        // making a move declines a pending draw offer from the opponent
+        if self.draw_offer == Some(color.opponent()) {
+            self.draw_offer = None;
+        }
+
+        self.keys.push(position_key(&self.board));
+
+        // mate, stalemate, repetitions and the variant's own end conditions
+        // finish the game
+        self.decide(outcome.result);
+
+        let m = self.board.last_move().unwrap();
+        self.history.push(GameMove {
+            m,
+            color,
+            played_at: SystemTime::now(),
+            think,
+            annotation: None,
+            nags: Vec::new(),
+            analysis,
+        });
+        self.tree.insert(&self.history);
+
+        // playing the move that was taken back keeps the rest of the line
+        // to redo, any other move leaves it as a variation and goes on
+        // along the line of the move played, if it was played before
+        match self.undone.last() {
+            Some(next) if next.m == m => {
+                self.undone.pop();
+            }
+            _ => self.undone = self.continuation(),
+        }
+
+        Ok(outcome)
+    }
+
+    /// Returns every line played in the game, see `variations`
+    pub fn tree(&self) -> &MoveTree {
+        &self.tree
+    }
+
+    fn line(&self) -> Vec<Move> {
+        self.history.iter().map(|entry| entry.m).collect()
+    }
+
+    /// Returns the main continuation of the tree after the current
+    /// position, the next move last as in `undone`
+    fn continuation(&self) -> Vec<GameMove> {
+        let mut moves: Vec<GameMove> = self
+            .tree
+            .main_line(&self.line())
+            .into_iter()
+            .cloned()
+            .collect();
+        moves.reverse();
+        moves
+    }
+
+    /// Returns the moves played from the current position at some point,
+    /// main line first. Playing one of them follows its line.
+    pub fn variations(&self) -> &[Node] {
+        self.tree.children(&self.line())
+    }
+
+    /// Makes the line to the current position the main line, so it is the
+    /// one written first to PGN
+    pub fn promote_line(&mut self) -> Result<(), Error> {
+        let line = self.line();
+        self.tree.promote(&line)
+    }
+
+    /// Returns the number of moves played up to the current position
+    pub fn ply(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns the moves that can be played again with `redo`, next first
+    pub fn undone(&self) -> impl Iterator<Item = &GameMove> {
+        self.undone.iter().rev()
+    }
+
+    /// Takes back the most recent move. It can be replayed with `redo` until
+    /// a different move is played, after which it stays in the tree as a
+    /// variation.
+    pub fn undo(&mut self) -> Result<(), Error> {
+        let entry = self.history.pop().ok_or(Error::InvalidInput)?;
+        self.undone.push(entry);
+
+        self.rebuild()
+    }
+
+    /// Plays the most recently undone move again
+    pub fn redo(&mut self) -> Result<(), Error> {
+        let entry = self.undone.pop().ok_or(Error::InvalidInput)?;
+
+        let outcome = self.board.move_piece(&entry.m.to_string())?;
+        self.keys.push(position_key(&self.board));
+        self.decide(outcome.result);
+        self.history.push(entry);
+        self.sync_clock();
+
+        Ok(())
+    }
+
+    /// Moves through the history with undo and redo until `ply` moves have
+    /// been played
+    pub fn goto(&mut self, ply: usize) -> Result<(), Error> {
+        if ply > self.history.len() + self.undone.len() {
+            return Err(Error::InvalidInput);
+        }
+
+        if self.history.len() > ply {
+            while self.history.len() > ply {
+                let entry = self.history.pop().unwrap();
+                self.undone.push(entry);
+            }
+
+            self.rebuild()?;
+        }
+
+        while self.history.len() < ply {
+            self.redo()?;
+        }
+
+        Ok(())
+    }
+
+    /// Remembers the current position under a name, replacing an earlier
+    /// bookmark of the same name
+    pub fn bookmark(&mut self, name: &str) {
+        let bookmark = Bookmark {
+            name: name.to_string(),
+            line: self.history.iter().map(|entry| entry.m).collect(),
+        };
+
+        match self.bookmarks.iter_mut().find(|b| b.name == name) {
+            Some(existing) => *existing = bookmark,
+            None => self.bookmarks.push(bookmark),
+        }
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Returns to a bookmarked position with undo and redo. Fails when the
+    /// bookmark is unknown or its line was dropped by playing a different
+    /// move after an undo.
+    pub fn goto_bookmark(&mut self, name: &str) -> Result<(), Error> {
+        let bookmark = self
+            .bookmarks
+            .iter()
+            .find(|b| b.name == name)
+            .ok_or(Error::InvalidInput)?;
+
+        // the moves that can be reached: the history, then what redo replays
+        let mut reachable = self
+            .history
+            .iter()
+            .chain(self.undone.iter().rev())
+            .map(|entry| entry.m);
+        if !bookmark.line.iter().all(|m| reachable.next() == Some(*m)) {
+            return Err(Error::InvalidInput);
+        }
+
+        self.goto(bookmark.ply())
+    }
+
+    /// Sets the board up again from the start position and the history
+    fn rebuild(&mut self) -> Result<(), Error> {
+        let mut board = Board::new()?;
+        board.set_variant(self.board.variant());
+        board.from_fen(&self.start)?;
+
+        self.keys = vec![position_key(&board)];
+        for entry in &self.history {
+            board.move_piece(&entry.m.to_string())?;
+            self.keys.push(position_key(&board));
+        }
+
+        let result = board.result();
+        self.board = board;
+        self.decide(result);
+        self.draw_offer = None;
+        self.sync_clock();
+
+        Ok(())
+    }
+
+    /// Runs the clock for the side to move after the board went back or
+    /// forward, since pressing it charges whichever side is running
+    fn sync_clock(&mut self) {
+        let turn = self.board.turn();
+        if let Some(clock) = self.clock.as_mut() {
+            if clock.running().is_some_and(|color| color != turn) {
+                clock.start(turn);
+            }
+        }
+    }
+
+    /// Returns how often the current position has occurred, counting this
+    /// time
+    pub fn repetitions(&self) -> usize {
+        let current = self.keys.last().unwrap();
+        self.keys.iter().filter(|key| *key == current).count()
+    }
+
+    /// Returns the rule a player could claim a draw under right now. Such
+    /// draws only happen when claimed, unlike fivefold repetition and the
+    /// seventy-five-move rule, which end the game by themselves.
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        if self.is_over() {
+            None
+        } else if self.repetitions() >= 3 {
+            Some(DrawClaim::Repetition)
+        } else if self.board.halfmove_clock() >= 100 {
+            Some(DrawClaim::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    pub fn can_claim_draw(&self) -> bool {
+        self.claimable_draw().is_some()
+    }
+
+    /// Ends the game in a draw by threefold repetition or the fifty-move
+    /// rule, returning the rule it was claimed under
+    pub fn claim_draw(&mut self) -> Result<DrawClaim, Error> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        let claim = self.claimable_draw().ok_or(Error::InvalidInput)?;
+
+        self.finish(GameResult::Draw, claim.into());
+        self.draw_offer = None;
+
+        Ok(claim)
+    }
+
+    /// Settles the result after a move given the board's verdict: a game
+    /// the board finished stands, otherwise fivefold repetition, the
+    /// seventy-five-move rule and insufficient material draw automatically
+    fn decide(&mut self, result: GameResult) {
+        let termination = match result {
+            GameResult::Ongoing if self.repetitions() >= 5 => Termination::Repetition,
+            GameResult::Ongoing if self.board.halfmove_clock() >= 150 => Termination::FiftyMoves,
+            GameResult::Ongoing if self.board.is_insufficient_material() => {
+                Termination::InsufficientMaterial
+            }
+            GameResult::Ongoing => {
+                self.result = result;
+                self.termination = None;
+                return;
+            }
+            // the board ends games by mate or with no moves left, which a
+            // variant played without pieces to keep decides its own way
+            _ if self.board.variant().wins_without_pieces() => Termination::VariantEnd,
+            _ => match self.board.status() {
+                Status::Checkmate => Termination::Checkmate,
+                _ => Termination::Stalemate,
+            },
+        };
+
+        self.result = match termination {
+            Termination::Checkmate | Termination::Stalemate | Termination::VariantEnd => result,
+            _ => GameResult::Draw,
+        };
+        self.termination = Some(termination);
+    }
+
+    /// Attaches a free text annotation to the most recent move
+    pub fn annotate(&mut self, text: &str) -> Result<(), Error> {
+        match self.history.last_mut() {
+            Some(entry) => {
+                entry.annotation = Some(text.to_string());
+                self.tree.insert(&self.history);
+                Ok(())
+            }
+            None => Err(Error::InvalidInput),
+        }
+    }
+
+    /// Attaches a glyph such as `!?` to the most recent move
+    pub fn add_nag(&mut self, nag: Nag) -> Result<(), Error> {
+        let entry = self.history.last_mut().ok_or(Error::InvalidInput)?;
+        if !entry.nags.contains(&nag) {
+            entry.nags.push(nag);
+        }
+
+        self.tree.insert(&self.history);
+        Ok(())
+    }
+
+    /// Ends the game with a win for the opponent of the given color
+    pub fn resign(&mut self, color: Color) -> Result<(), Error> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        self.finish(GameResult::win(color.opponent()), Termination::Resignation);
+        self.draw_offer = None;
+
+        Ok(())
+    }
+
+    /// Ends the game with a win for the opponent of a player who left it
+    pub fn abandon(&mut self, color: Color) -> Result<(), Error> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        self.finish(GameResult::win(color.opponent()), Termination::Abandonment);
+        self.draw_offer = None;
+
+        Ok(())
+    }
+
+    /// Returns the color that has an open draw offer, if any
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), Error> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        self.draw_offer = Some(color);
+
+        Ok(())
+    }
+
+    /// Accepts the opponent's draw offer, ending the game in a draw
+    pub fn accept_draw(&mut self, color: Color) -> Result<(), Error> {
+        if self.is_over() {
+            return Err(Error::GameOver);
+        }
+
+        if self.draw_offer != Some(color.opponent()) {
+            return Err(Error::InvalidInput);
+        }
+
+        self.finish(GameResult::Draw, Termination::Agreement);
+        self.draw_offer = None;
+
+        Ok(())
+    }
+
+    pub fn decline_draw(&mut self, color: Color) -> Result<(), Error> {
+        if self.draw_offer != Some(color.opponent()) {
+            return Err(Error::InvalidInput);
+        }
+
+        self.draw_offer = None;
+
+        Ok(())
+    }
+}
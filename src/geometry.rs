@@ -0,0 +1,145 @@
+//! Squares, distances and lines on the board
+
+use crate::chess::{Color, Square};
+
+/// The eight directions a line can run in, north being towards black's side
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// The change in file and rank of one step in this direction
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, -1),
+            Direction::South => (0, -1),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, 1),
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        let (file, rank) = self.offset();
+        file != 0 && rank != 0
+    }
+
+    pub fn opposite(&self) -> Direction {
+        let (file, rank) = self.offset();
+        Direction::from_offset(-file, -rank).unwrap()
+    }
+
+    fn from_offset(file: i32, rank: i32) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|d| d.offset() == (file, rank))
+    }
+
+    /// Returns the direction leading from one square to the other, if they
+    /// share a rank, file or diagonal
+    pub fn towards(from: Square, to: Square) -> Option<Direction> {
+        let file = to.0 as i32 - from.0 as i32;
+        let rank = to.1 as i32 - from.1 as i32;
+
+        if (file, rank) == (0, 0) || (file != 0 && rank != 0 && file.abs() != rank.abs()) {
+            return None;
+        }
+
+        Direction::from_offset(file.signum(), rank.signum())
+    }
+}
+
+/// Properties of a square given as file and rank, 0 to 7 each
+pub trait SquareGeometry {
+    /// The color of the square itself, a1 being dark
+    fn color(&self) -> Color;
+    /// The number of king steps to the other square
+    fn chebyshev_distance(&self, other: Square) -> usize;
+    /// The number of rook steps of one square to the other square
+    fn manhattan_distance(&self, other: Square) -> usize;
+    /// The square one step away in the direction, if it is on the board
+    fn step(&self, direction: Direction) -> Option<Square>;
+    /// The name of the square, e.g. `e4`
+    fn name(&self) -> String;
+}
+
+impl SquareGeometry for Square {
+    fn color(&self) -> Color {
+        match (self.0 + self.1) % 2 {
+            0 => Color::Black,
+            _ => Color::White,
+        }
+    }
+
+    fn chebyshev_distance(&self, other: Square) -> usize {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
+
+    fn manhattan_distance(&self, other: Square) -> usize {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
+    }
+
+    fn step(&self, direction: Direction) -> Option<Square> {
+        let (file, rank) = direction.offset();
+        let file = self.0 as i32 + file;
+        let rank = self.1 as i32 + rank;
+
+        match (0..8).contains(&file) && (0..8).contains(&rank) {
+            true => Some((file as usize, rank as usize)),
+            false => None,
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("{}{}", (b'a' + self.0 as u8) as char, self.1 + 1)
+    }
+}
+
+/// Returns the squares from a square to the edge of the board in a
+/// direction, nearest first
+pub fn ray(from: Square, direction: Direction) -> Vec<Square> {
+    let mut squares = Vec::new();
+    let mut square = from;
+
+    while let Some(next) = square.step(direction) {
+        squares.push(next);
+        square = next;
+    }
+
+    squares
+}
+
+/// Returns the squares strictly between two squares on a rank, file or
+/// diagonal, nearest to `from` first. Squares not on a common line have
+/// nothing between them.
+pub fn between(from: Square, to: Square) -> Vec<Square> {
+    match Direction::towards(from, to) {
+        Some(direction) => ray(from, direction)
+            .into_iter()
+            .take_while(|square| *square != to)
+            .collect(),
+        None => Vec::new(),
+    }
+}
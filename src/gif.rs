@@ -0,0 +1,360 @@
+//! Animated GIFs of whole games for sharing replays, drawn and encoded
+//! without an image library
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    game::Game,
+};
+
+/// The palette every frame is drawn with
+const PALETTE: [[u8; 3]; 8] = [
+    [0xf0, 0xd9, 0xb5], // light square
+    [0xb5, 0x88, 0x63], // dark square
+    [0xff, 0xff, 0xff], // white piece
+    [0x20, 0x20, 0x20], // black piece
+    [0x00, 0x00, 0x00], // outline of a white piece
+    [0xa0, 0xa0, 0xa0], // outline of a black piece
+    [0xce, 0xd2, 0x6b], // light square of the last move
+    [0xaa, 0xa2, 0x3a], // dark square of the last move
+];
+
+const LIGHT: u8 = 0;
+const DARK: u8 = 1;
+const LAST_MOVE: u8 = 6;
+
+/// Silhouettes of the pieces, 16 by 16
+const SPRITE: usize = 16;
+
+fn sprite(class: Class) -> [&'static str; SPRITE] {
+    match class {
+        Class::Pawn => [
+            "................",
+            "................",
+            "................",
+            "......####......",
+            ".....######.....",
+            ".....######.....",
+            "......####......",
+            ".....######.....",
+            "......####......",
+            "......####......",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "...##########...",
+            "................",
+            "................",
+        ],
+        Class::Knight => [
+            "................",
+            "................",
+            "......##.#......",
+            ".....#######....",
+            "....#########...",
+            "...##########...",
+            "...####.#####...",
+            ".......######...",
+            "......######....",
+            ".....#######....",
+            ".....#######....",
+            "....#########...",
+            "...###########..",
+            "...###########..",
+            "................",
+            "................",
+        ],
+        Class::Bishop => [
+            "................",
+            ".......##.......",
+            "......####......",
+            ".....######.....",
+            ".....###.##.....",
+            ".....##.###.....",
+            "......####......",
+            ".......##.......",
+            "......####......",
+            "......####......",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "...##########...",
+            "................",
+            "................",
+        ],
+        Class::Rook => [
+            "................",
+            "................",
+            "...##..##..##...",
+            "...##########...",
+            "....########....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+            "................",
+        ],
+        Class::Queen => [
+            "................",
+            ".#....#..#....#.",
+            ".##..##..##..##.",
+            ".###.######.###.",
+            "..############..",
+            "..############..",
+            "...##########...",
+            "....########....",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+            "................",
+            "................",
+        ],
+        Class::King => [
+            ".......##.......",
+            ".....######.....",
+            ".......##.......",
+            "......####......",
+            "..###.####.###..",
+            ".##############.",
+            ".##############.",
+            "..############..",
+            "...##########...",
+            "....########....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+            "................",
+        ],
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GifOptions {
+    /// Width of a square in pixels
+    pub square_size: u16,
+    /// How long every position is shown
+    pub delay: Duration,
+    /// How long the final position is shown before the replay loops
+    pub final_delay: Duration,
+    /// Draws the board from black's side
+    pub flipped: bool,
+}
+
+impl Default for GifOptions {
+    fn default() -> GifOptions {
+        GifOptions {
+            square_size: 32,
+            delay: Duration::from_secs(1),
+            final_delay: Duration::from_secs(3),
+            flipped: false,
+        }
+    }
+}
+
+/// Draws a position as palette indices, row by row from the top left
+fn frame(board: &Board, last_move: Option<[(usize, usize); 2]>, options: &GifOptions) -> Vec<u8> {
+    let size = options.square_size as usize;
+    let width = size * 8;
+    let mut pixels = vec![0; width * width];
+
+    for y in 0..width {
+        for x in 0..width {
+            let (column, row) = (x / size, y / size);
+            let square = match options.flipped {
+                false => (column, 7 - row),
+                true => (7 - column, row),
+            };
+
+            let mut color = match (square.0 + square.1) % 2 {
+                0 => DARK,
+                _ => LIGHT,
+            };
+            if last_move.is_some_and(|squares| squares.contains(&square)) {
+                color += LAST_MOVE;
+            }
+
+            if let Some(piece) = board.get_piece(square.0, square.1) {
+                let sprite = sprite(piece.class);
+                let is_body = |sx: i32, sy: i32| {
+                    (0..SPRITE as i32).contains(&sx)
+                        && (0..SPRITE as i32).contains(&sy)
+                        && sprite[sy as usize].as_bytes()[sx as usize] == b'#'
+                };
+
+                let sx = ((x % size) * SPRITE / size) as i32;
+                let sy = ((y % size) * SPRITE / size) as i32;
+
+                if is_body(sx, sy) {
+                    let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .any(|(dx, dy)| !is_body(sx + dx, sy + dy));
+
+                    color = match (piece.color, edge) {
+                        (Color::White, false) => 2,
+                        (Color::Black, false) => 3,
+                        (Color::White, true) => 4,
+                        (Color::Black, true) => 5,
+                    };
+                }
+            }
+
+            pixels[y * width + x] = color;
+        }
+    }
+
+    pixels
+}
+
+/// Packs codes of varying width into bytes, lowest bits first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compresses palette indices with the variable width LZW of GIF
+fn lzw(min_size: u32, pixels: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << min_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_size + 1;
+
+    writer.write(clear, width);
+
+    let mut prefix: Option<u16> = None;
+    for &pixel in pixels {
+        let Some(code) = prefix else {
+            prefix = Some(pixel as u16);
+            continue;
+        };
+
+        if let Some(&extended) = table.get(&(code, pixel)) {
+            prefix = Some(extended);
+            continue;
+        }
+
+        writer.write(code, width);
+
+        if next < 4096 {
+            table.insert((code, pixel), next);
+            next += 1;
+            if next > 1 << width {
+                width += 1;
+            }
+        } else {
+            // the table is full, so start over
+            writer.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_size + 1;
+        }
+
+        prefix = Some(pixel as u16);
+    }
+
+    if let Some(code) = prefix {
+        writer.write(code, width);
+    }
+    writer.write(end, width);
+
+    writer.finish()
+}
+
+fn centiseconds(duration: Duration) -> [u8; 2] {
+    ((duration.as_millis() / 10).min(u16::MAX as u128) as u16).to_le_bytes()
+}
+
+/// Encodes every position of a game as the frames of a looping GIF
+pub fn render(game: &Game, options: &GifOptions) -> Result<Vec<u8>, Error> {
+    let positions = game.positions()?;
+    let side = (options.square_size as u32 * 8).min(u16::MAX as u32) as u16;
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend(side.to_le_bytes());
+    gif.extend(side.to_le_bytes());
+    // a global palette of 8 colors, then background color and aspect ratio
+    gif.extend([0xf2, 0, 0]);
+    for color in PALETTE {
+        gif.extend(color);
+    }
+
+    // loop forever
+    gif.extend([0x21, 0xff, 0x0b]);
+    gif.extend(b"NETSCAPE2.0");
+    gif.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for (i, board) in positions.iter().enumerate() {
+        let last_move = i
+            .checked_sub(1)
+            .map(|ply| game.history()[ply].m)
+            .map(|m| [(m.from_file, m.from_rank), (m.to_file, m.to_rank)]);
+
+        let delay = match i + 1 == positions.len() {
+            true => options.final_delay,
+            false => options.delay,
+        };
+
+        gif.extend([0x21, 0xf9, 0x04, 0x00]);
+        gif.extend(centiseconds(delay));
+        gif.extend([0x00, 0x00]);
+
+        gif.push(0x2c);
+        gif.extend([0, 0, 0, 0]);
+        gif.extend(side.to_le_bytes());
+        gif.extend(side.to_le_bytes());
+        gif.push(0);
+
+        gif.push(3);
+        for block in lzw(3, &frame(board, last_move, options)).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3b);
+
+    Ok(gif)
+}
+
+/// Writes the replay of a game as a GIF file
+pub fn save(game: &Game, options: &GifOptions, filename: &str) -> Result<(), Error> {
+    std::fs::write(filename, render(game, options)?)?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Terminal graphs of the evaluation over a game, one column per position
+
+/// Scores beyond this many centipawns either way are drawn at the edge
+pub const SCALE: i32 = 500;
+
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws scores, in centipawns from white's point of view, as one line of
+/// block characters: high when white is better, low when black is
+pub fn sparkline(scores: &[i32]) -> String {
+    scores
+        .iter()
+        .map(|score| {
+            let score = (*score).clamp(-SCALE, SCALE) + SCALE;
+            LEVELS[(score * (LEVELS.len() as i32 - 1) / (2 * SCALE)) as usize]
+        })
+        .collect()
+}
+
+/// Draws scores as bars growing up from the middle row for white and down
+/// for black, `height` rows on either side
+pub fn bars(scores: &[i32], height: usize) -> Vec<String> {
+    // the number of rows a score fills on its side, rounding up so any
+    // advantage shows
+    let rows = |score: i32| {
+        let score = score.clamp(-SCALE, SCALE).unsigned_abs() as usize;
+        (score * height).div_ceil(SCALE as usize)
+    };
+
+    let mut lines = Vec::new();
+
+    for row in (1..=height).rev() {
+        lines.push(
+            scores
+                .iter()
+                .map(|s| {
+                    if *s > 0 && rows(*s) >= row {
+                        '█'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    lines.push("─".repeat(scores.len()));
+
+    for row in 1..=height {
+        lines.push(
+            scores
+                .iter()
+                .map(|s| {
+                    if *s < 0 && rows(*s) >= row {
+                        '█'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    lines
+}
+
+/// Returns the indices of the `count` largest changes between neighbouring
+/// scores with the change, in the order they happened. Index `i` is the
+/// change from score `i - 1` to score `i`.
+pub fn swings(scores: &[i32], count: usize) -> Vec<(usize, i32)> {
+    let clamp = |score: i32| score.clamp(-SCALE * 2, SCALE * 2);
+
+    let mut swings: Vec<(usize, i32)> = scores
+        .windows(2)
+        .enumerate()
+        .map(|(i, pair)| (i + 1, clamp(pair[1]) - clamp(pair[0])))
+        .filter(|(_, change)| *change != 0)
+        .collect();
+
+    swings.sort_by_key(|(_, change)| -change.abs());
+    swings.truncate(count);
+    swings.sort();
+
+    swings
+}
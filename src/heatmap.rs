@@ -0,0 +1,123 @@
+//! How many pieces of each side attack every square, for seeing who controls
+//! which part of the board
+
+use crate::{
+    chess::{Board, Color, Square},
+    markup::{Brush, Markup},
+};
+
+/// ANSI 256-color backgrounds from faint to strong, for squares one side
+/// attacks once, twice, three times or more often than the other
+const WHITE_SHADES: [u8; 4] = [153, 111, 69, 27];
+const BLACK_SHADES: [u8; 4] = [224, 217, 210, 196];
+/// For squares both sides attack equally often
+const CONTESTED: u8 = 229;
+
+/// The number of attackers of each color on every square
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Heatmap {
+    /// By color, file and rank
+    counts: [[[u8; 8]; 8]; 2],
+}
+
+impl Heatmap {
+    pub fn new(board: &Board) -> Heatmap {
+        let mut counts = [[[0; 8]; 8]; 2];
+
+        for color in [Color::White, Color::Black] {
+            for (file, column) in counts[color as usize].iter_mut().enumerate() {
+                for (rank, count) in column.iter_mut().enumerate() {
+                    *count = board.attackers((file, rank), color).len() as u8;
+                }
+            }
+        }
+
+        Heatmap { counts }
+    }
+
+    /// Returns how many pieces of a color attack the square
+    pub fn count(&self, square: Square, color: Color) -> usize {
+        self.counts[color as usize][square.0][square.1] as usize
+    }
+
+    /// Returns how many more white attackers the square has than black ones
+    pub fn balance(&self, square: Square) -> i32 {
+        self.count(square, Color::White) as i32 - self.count(square, Color::Black) as i32
+    }
+
+    /// Returns the squares a color attacks more often than its opponent
+    pub fn controlled(&self, color: Color) -> Vec<Square> {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+
+        (0..8)
+            .flat_map(|rank| (0..8).map(move |file| (file, rank)))
+            .filter(|square| self.balance(*square) * sign > 0)
+            .collect()
+    }
+
+    /// Highlights the squares in blue where white has more attackers, in red
+    /// where black has, and in yellow where both have as many
+    pub fn to_markup(&self) -> Markup {
+        let mut markup = Markup::new();
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let square = (file, rank);
+                let brush = match self.balance(square) {
+                    0 if self.count(square, Color::White) == 0 => continue,
+                    0 => Brush::Yellow,
+                    b if b > 0 => Brush::Blue,
+                    _ => Brush::Red,
+                };
+                markup.mark(square, brush);
+            }
+        }
+
+        markup
+    }
+
+    /// The ANSI background of a square, darker the more one side outnumbers
+    /// the other on it
+    fn background(&self, square: Square) -> Option<u8> {
+        let shade = |shades: &[u8; 4], by: i32| shades[(by as usize - 1).min(3)];
+
+        match self.balance(square) {
+            0 if self.count(square, Color::White) == 0 => None,
+            0 => Some(CONTESTED),
+            b if b > 0 => Some(shade(&WHITE_SHADES, b)),
+            b => Some(shade(&BLACK_SHADES, -b)),
+        }
+    }
+}
+
+/// Draws the board like `Board::to_unicode`, each square shaded by which
+/// side attacks it more often and by how many pieces
+pub fn render(board: &Board, heatmap: &Heatmap) -> String {
+    let mut out = String::new();
+
+    for rank in (0..8).rev() {
+        out.push_str(&(rank + 1).to_string());
+
+        for file in 0..8 {
+            let symbol = match board.get_piece(file, rank) {
+                Some(piece) => piece.to_string(),
+                None => '·'.to_string(),
+            };
+
+            out.push(' ');
+            match heatmap.background((file, rank)) {
+                Some(color) => out.push_str(&format!("\x1b[30;48;5;{}m{}\x1b[0m", color, symbol)),
+                None => out.push_str(&symbol),
+            }
+        }
+
+        out.push('\n');
+    }
+
+    out.push_str("  a b c d e f g h");
+
+    out
+}
@@ -1,3 +1,72 @@
+pub mod annotate;
+pub mod announce;
+pub mod arena;
+pub mod bench;
+pub mod book;
 pub mod chess;
-mod fen;
-mod mover;
+pub mod clock;
+pub mod codec;
+pub mod command;
+pub mod config;
+pub mod diff;
+pub mod eco;
+pub mod engine;
+pub mod epd;
+pub mod error;
+pub mod eval;
+pub mod fen;
+pub mod game;
+pub mod geometry;
+pub mod graph;
+pub mod heatmap;
+pub mod locale;
+pub mod manager;
+pub mod markup;
+pub mod mobility;
+pub mod mover;
+pub mod net;
+pub mod notation;
+pub mod notify;
+pub mod pgn;
+pub mod ponder;
+pub mod position;
+pub mod profile;
+pub mod puzzle;
+pub mod query;
+pub mod repertoire;
+pub mod replay;
+pub mod rng;
+pub mod screen;
+pub mod script;
+pub mod see;
+pub mod stats;
+pub mod tablebase;
+pub mod teach;
+pub mod trace;
+pub mod training;
+pub mod tune;
+pub mod uci;
+pub mod variant;
+pub mod variation;
+pub mod zobrist;
+
+#[cfg(test)]
+mod testing;
+
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "gif")]
+pub mod gif;
+
+#[cfg(feature = "lichess")]
+pub mod lichess;
+
+#[cfg(feature = "svg")]
+pub mod svg;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
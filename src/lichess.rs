@@ -0,0 +1,257 @@
+//! Online play through the Lichess Board API. Moves are exchanged in UCI
+//! notation, which matches the coordinate notation used by the board.
+
+use std::io::{BufRead, BufReader, Read};
+
+use serde_json::Value;
+
+use crate::{
+    chess::{Board, Color, Error},
+    game::Game,
+    mover::Move,
+};
+
+const API: &str = "https://lichess.org/api";
+
+fn network_error(e: impl std::fmt::Display) -> Error {
+    Error::Network(e.to_string())
+}
+
+/// The state of an online game as sent by Lichess
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameState {
+    /// Every move played since the initial position
+    pub moves: Vec<String>,
+    /// Remaining time in milliseconds
+    pub wtime: u64,
+    pub btime: u64,
+    /// `started` while the game is running, otherwise how it ended
+    pub status: String,
+    pub winner: Option<Color>,
+}
+
+/// The first event of a game stream, with everything needed to set up the
+/// game locally
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameFull {
+    pub id: String,
+    pub white: String,
+    pub black: String,
+    /// The starting position, `startpos` for the standard one
+    pub initial_fen: String,
+    pub state: GameState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    Full(GameFull),
+    State(GameState),
+    ChatLine { username: String, text: String },
+    OpponentGone(bool),
+}
+
+impl GameState {
+    fn from_json(value: &Value) -> Result<GameState, Error> {
+        let invalid = || Error::Network("invalid game state".to_string());
+
+        let winner = match value["winner"].as_str() {
+            Some("white") => Some(Color::White),
+            Some("black") => Some(Color::Black),
+            _ => None,
+        };
+
+        Ok(GameState {
+            moves: value["moves"]
+                .as_str()
+                .ok_or_else(invalid)?
+                .split_whitespace()
+                .map(|m| m.to_string())
+                .collect(),
+            wtime: value["wtime"].as_u64().unwrap_or(0),
+            btime: value["btime"].as_u64().unwrap_or(0),
+            status: value["status"].as_str().ok_or_else(invalid)?.to_string(),
+            winner,
+        })
+    }
+
+    pub fn is_over(&self) -> bool {
+        !matches!(self.status.as_str(), "created" | "started")
+    }
+}
+
+impl GameFull {
+    /// Sets up a local game with the players, the initial position and the
+    /// moves played so far
+    pub fn game(&self) -> Result<Game, Error> {
+        let board = match self.initial_fen.as_str() {
+            "startpos" => Board::default_board()?,
+            fen => fen.parse()?,
+        };
+
+        let mut game = Game::from_board(board);
+        game.set_players(&self.white, &self.black);
+        sync(&mut game, &self.state)?;
+
+        Ok(game)
+    }
+}
+
+impl GameEvent {
+    pub fn from_json(data: &str) -> Result<GameEvent, Error> {
+        let value: Value = serde_json::from_str(data).map_err(network_error)?;
+
+        let player = |player: &Value| {
+            player["name"]
+                .as_str()
+                .or(player["id"].as_str())
+                .unwrap_or("Anonymous")
+                .to_string()
+        };
+
+        let event = match value["type"].as_str() {
+            Some("gameFull") => GameEvent::Full(GameFull {
+                id: value["id"].as_str().unwrap_or_default().to_string(),
+                white: player(&value["white"]),
+                black: player(&value["black"]),
+                initial_fen: value["initialFen"]
+                    .as_str()
+                    .unwrap_or("startpos")
+                    .to_string(),
+                state: GameState::from_json(&value["state"])?,
+            }),
+            Some("gameState") => GameEvent::State(GameState::from_json(&value)?),
+            Some("chatLine") => GameEvent::ChatLine {
+                username: value["username"].as_str().unwrap_or_default().to_string(),
+                text: value["text"].as_str().unwrap_or_default().to_string(),
+            },
+            Some("opponentGone") => GameEvent::OpponentGone(value["gone"].as_bool() == Some(true)),
+            _ => return Err(Error::Network("unknown event".to_string())),
+        };
+
+        Ok(event)
+    }
+}
+
+/// Plays the moves of the state that are not yet in the local game and
+/// returns them, which are the opponent's moves for a game we take part in
+pub fn sync(game: &mut Game, state: &GameState) -> Result<Vec<Move>, Error> {
+    let played = game.history().len();
+
+    if state.moves.len() < played {
+        return Err(Error::Network(
+            "game state is behind the local game".to_string(),
+        ));
+    }
+
+    let mut moves = Vec::new();
+
+    for m in state.moves[played..].iter() {
+        game.move_piece(m)?;
+        moves.push(game.board().last_move().unwrap());
+    }
+
+    Ok(moves)
+}
+
+/// The events of a single game, read as they arrive
+pub struct GameStream {
+    reader: Box<dyn BufRead + Send>,
+}
+
+impl Iterator for GameStream {
+    type Item = Result<GameEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(network_error(e))),
+            }
+
+            // lichess sends empty lines to keep the connection alive
+            if !line.trim().is_empty() {
+                return Some(GameEvent::from_json(line.trim()));
+            }
+        }
+    }
+}
+
+/// An authenticated Lichess client
+pub struct Client {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl Client {
+    /// Creates a client using a personal API token with the `board:play` scope
+    pub fn new(token: &str) -> Client {
+        Client {
+            agent: ureq::Agent::new(),
+            token: token.trim().to_string(),
+        }
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, Error> {
+        self.agent
+            .get(&format!("{}{}", API, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(network_error)
+    }
+
+    fn post(&self, path: &str) -> Result<(), Error> {
+        self.agent
+            .post(&format!("{}{}", API, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(network_error)?;
+
+        Ok(())
+    }
+
+    /// Returns the username the token belongs to
+    pub fn username(&self) -> Result<String, Error> {
+        let mut data = String::new();
+        self.get("/account")?
+            .into_reader()
+            .read_to_string(&mut data)
+            .map_err(network_error)?;
+
+        let value: Value = serde_json::from_str(&data).map_err(network_error)?;
+
+        value["username"]
+            .as_str()
+            .map(|name| name.to_string())
+            .ok_or_else(|| Error::Network("missing username".to_string()))
+    }
+
+    /// Opens the event stream of a game
+    pub fn stream_game(&self, id: &str) -> Result<GameStream, Error> {
+        let response = self.get(&format!("/board/game/stream/{}", id))?;
+
+        Ok(GameStream {
+            reader: Box::new(BufReader::new(response.into_reader())),
+        })
+    }
+
+    pub fn make_move(&self, id: &str, m: &Move) -> Result<(), Error> {
+        self.post(&format!("/board/game/{}/move/{}", id, m))
+    }
+
+    pub fn resign(&self, id: &str) -> Result<(), Error> {
+        self.post(&format!("/board/game/{}/resign", id))
+    }
+
+    pub fn abort(&self, id: &str) -> Result<(), Error> {
+        self.post(&format!("/board/game/{}/abort", id))
+    }
+
+    /// Offers or accepts a draw, or declines one when `accept` is false
+    pub fn draw(&self, id: &str, accept: bool) -> Result<(), Error> {
+        let answer = if accept { "yes" } else { "no" };
+        self.post(&format!("/board/game/{}/draw/{}", id, answer))
+    }
+}
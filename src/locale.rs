@@ -0,0 +1,362 @@
+//! Translations of the messages shown to the player and of the piece
+//! letters of SAN, e.g. `Sf3` for a knight move in German
+
+use std::fmt::Display;
+
+use crate::{
+    chess::{Board, Class, Color, Error, MoveError},
+    mover::Move,
+    pgn,
+};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Dutch,
+    French,
+    Spanish,
+}
+
+/// The messages of the catalog. The English text of each is in `ENGLISH`,
+/// with `{}` where its arguments go.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Message {
+    InvalidInput,
+    InvalidFen,
+    InvalidMove,
+    Io,
+    TimeExpired,
+    GameOver,
+    Network,
+    Clipboard,
+    Engine,
+    UnknownCommand,
+    DidYouMean,
+    Usage,
+    NotYourTurn,
+    NoPiece,
+    NotYourPiece,
+    NotLegal,
+    Ambiguous,
+    Malformed,
+    PromotionRequired,
+    White,
+    Black,
+    YourMove,
+    PromoteTo,
+}
+
+const MESSAGES: usize = 23;
+
+const ENGLISH: [&str; MESSAGES] = [
+    "Invalid input",
+    "Invalid FEN: {}",
+    "Invalid move: {}",
+    "I/O error: {}",
+    "Time expired for {}",
+    "The game is over",
+    "Network error: {}",
+    "Clipboard error: {}",
+    "Engine error: {}",
+    "Unknown command '{}'",
+    "Unknown command '{}', did you mean '{}'?",
+    "Usage: {}",
+    "Not your turn",
+    "No piece on square",
+    "Not your piece",
+    "{} is not a legal move",
+    "{} is ambiguous",
+    "'{}' is not a move",
+    "Pawn has to promote on the last rank",
+    "white",
+    "black",
+    "your move",
+    "Promote to ({})?",
+];
+
+const GERMAN: [&str; MESSAGES] = [
+    "Ungültige Eingabe",
+    "Ungültiger FEN: {}",
+    "Ungültiger Zug: {}",
+    "E/A-Fehler: {}",
+    "Zeit abgelaufen für {}",
+    "Die Partie ist vorbei",
+    "Netzwerkfehler: {}",
+    "Fehler der Zwischenablage: {}",
+    "Engine-Fehler: {}",
+    "Unbekannter Befehl '{}'",
+    "Unbekannter Befehl '{}', meinten Sie '{}'?",
+    "Aufruf: {}",
+    "Sie sind nicht am Zug",
+    "Keine Figur auf dem Feld",
+    "Nicht Ihre Figur",
+    "{} ist kein legaler Zug",
+    "{} ist mehrdeutig",
+    "'{}' ist kein Zug",
+    "Der Bauer muss auf der letzten Reihe umgewandelt werden",
+    "Weiß",
+    "Schwarz",
+    "Sie sind am Zug",
+    "Umwandeln in ({})?",
+];
+
+const DUTCH: [&str; MESSAGES] = [
+    "Ongeldige invoer",
+    "Ongeldige FEN: {}",
+    "Ongeldige zet: {}",
+    "I/O-fout: {}",
+    "Tijd verstreken voor {}",
+    "De partij is afgelopen",
+    "Netwerkfout: {}",
+    "Klembordfout: {}",
+    "Engine-fout: {}",
+    "Onbekend commando '{}'",
+    "Onbekend commando '{}', bedoelde je '{}'?",
+    "Gebruik: {}",
+    "Je bent niet aan zet",
+    "Geen stuk op dit veld",
+    "Niet jouw stuk",
+    "{} is geen legale zet",
+    "{} is dubbelzinnig",
+    "'{}' is geen zet",
+    "De pion moet op de laatste rij promoveren",
+    "wit",
+    "zwart",
+    "jij bent aan zet",
+    "Promoveren tot ({})?",
+];
+
+const FRENCH: [&str; MESSAGES] = [
+    "Entrée invalide",
+    "FEN invalide : {}",
+    "Coup invalide : {}",
+    "Erreur d'E/S : {}",
+    "Temps écoulé pour {}",
+    "La partie est terminée",
+    "Erreur réseau : {}",
+    "Erreur du presse-papiers : {}",
+    "Erreur du moteur : {}",
+    "Commande inconnue '{}'",
+    "Commande inconnue '{}', vouliez-vous dire '{}' ?",
+    "Usage : {}",
+    "Ce n'est pas votre tour",
+    "Aucune pièce sur la case",
+    "Ce n'est pas votre pièce",
+    "{} n'est pas un coup légal",
+    "{} est ambigu",
+    "'{}' n'est pas un coup",
+    "Le pion doit être promu sur la dernière rangée",
+    "blancs",
+    "noirs",
+    "à vous de jouer",
+    "Promouvoir en ({}) ?",
+];
+
+const SPANISH: [&str; MESSAGES] = [
+    "Entrada no válida",
+    "FEN no válido: {}",
+    "Jugada no válida: {}",
+    "Error de E/S: {}",
+    "Se acabó el tiempo de {}",
+    "La partida ha terminado",
+    "Error de red: {}",
+    "Error del portapapeles: {}",
+    "Error del motor: {}",
+    "Comando desconocido '{}'",
+    "Comando desconocido '{}', ¿quiso decir '{}'?",
+    "Uso: {}",
+    "No es su turno",
+    "No hay ninguna pieza en la casilla",
+    "No es su pieza",
+    "{} no es una jugada legal",
+    "{} es ambigua",
+    "'{}' no es una jugada",
+    "El peón tiene que coronar en la última fila",
+    "blancas",
+    "negras",
+    "su turno",
+    "¿Coronar en ({})?",
+];
+
+/// The English SAN letters of the knight, bishop, rook, queen and king
+const ENGLISH_LETTERS: [char; 5] = ['N', 'B', 'R', 'Q', 'K'];
+const CLASSES: [Class; 5] = [
+    Class::Knight,
+    Class::Bishop,
+    Class::Rook,
+    Class::Queen,
+    Class::King,
+];
+
+/// Puts the arguments in place of the `{}` of a message, in order
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}").peekable();
+
+    while let Some(part) = parts.next() {
+        out.push_str(part);
+        if parts.peek().is_some() {
+            out.push_str(args.next().unwrap_or(&""));
+        }
+    }
+
+    out
+}
+
+impl Locale {
+    fn catalog(&self) -> &'static [&'static str; MESSAGES] {
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::German => &GERMAN,
+            Locale::Dutch => &DUTCH,
+            Locale::French => &FRENCH,
+            Locale::Spanish => &SPANISH,
+        }
+    }
+
+    /// Returns a message with its arguments filled in
+    pub fn text(&self, message: Message, args: &[&str]) -> String {
+        fill(self.catalog()[message as usize], args)
+    }
+
+    /// Returns the SAN letters of the knight, bishop, rook, queen and king
+    pub fn letters(&self) -> [char; 5] {
+        match self {
+            Locale::English => ENGLISH_LETTERS,
+            Locale::German => ['S', 'L', 'T', 'D', 'K'],
+            Locale::Dutch => ['P', 'L', 'T', 'D', 'K'],
+            Locale::French => ['C', 'F', 'T', 'D', 'R'],
+            Locale::Spanish => ['C', 'A', 'T', 'D', 'R'],
+        }
+    }
+
+    /// Returns the SAN letter of a piece, which pawns don't have
+    pub fn letter(&self, class: Class) -> Option<char> {
+        let i = CLASSES.iter().position(|c| *c == class)?;
+        Some(self.letters()[i])
+    }
+
+    /// Reads a piece letter of the locale in either case, or an English one
+    /// the locale doesn't use for another piece
+    pub fn piece(&self, letter: &str) -> Option<Class> {
+        let mut chars = letter.chars();
+        let c = chars.next()?.to_ascii_uppercase();
+        if chars.next().is_some() {
+            return None;
+        }
+
+        let i = self
+            .letters()
+            .iter()
+            .position(|l| *l == c)
+            .or_else(|| ENGLISH_LETTERS.iter().position(|l| *l == c))?;
+        Some(CLASSES[i])
+    }
+
+    /// Swaps every piece letter of one set for the matching one of another,
+    /// leaving every other character as it is
+    fn translate(san: &str, from: [char; 5], to: [char; 5]) -> String {
+        san.chars()
+            .map(|c| match from.iter().position(|l| *l == c) {
+                Some(i) => to[i],
+                None => c,
+            })
+            .collect()
+    }
+
+    /// Writes the move in SAN with the piece letters of the locale
+    pub fn san(&self, board: &Board, m: &Move) -> Result<String, Error> {
+        let san = pgn::san(board, m)?;
+        Ok(Locale::translate(&san, ENGLISH_LETTERS, self.letters()))
+    }
+
+    /// Parses SAN written with the piece letters of the locale. English
+    /// letters the locale doesn't use for another piece are understood too.
+    pub fn parse_san(&self, board: &Board, san: &str) -> Result<Move, Error> {
+        let english = Locale::translate(san, self.letters(), ENGLISH_LETTERS);
+
+        // errors quote the move as it was given
+        pgn::parse_san(board, &english).map_err(|e| match e {
+            Error::InvalidMove(MoveError::NotLegal(_)) => {
+                MoveError::NotLegal(san.to_string()).into()
+            }
+            Error::InvalidMove(MoveError::Ambiguous(_)) => {
+                MoveError::Ambiguous(san.to_string()).into()
+            }
+            e => e,
+        })
+    }
+
+    pub fn color(&self, color: Color) -> String {
+        match color {
+            Color::White => self.text(Message::White, &[]),
+            Color::Black => self.text(Message::Black, &[]),
+        }
+    }
+
+    /// Describes an error in the locale's language. Details the catalog has
+    /// no message for, such as what is wrong with a FEN, stay in English.
+    pub fn error(&self, e: &Error) -> String {
+        match e {
+            Error::InvalidInput => self.text(Message::InvalidInput, &[]),
+            Error::InvalidFen(fen) => self.text(Message::InvalidFen, &[&fen.to_string()]),
+            Error::InvalidMove(m) => self.text(Message::InvalidMove, &[&self.move_error(m)]),
+            Error::Io(e) => self.text(Message::Io, &[&e.to_string()]),
+            Error::TimeExpired(color) => self.text(Message::TimeExpired, &[&self.color(*color)]),
+            Error::GameOver => self.text(Message::GameOver, &[]),
+            Error::Network(e) => self.text(Message::Network, &[e]),
+            Error::Clipboard(e) => self.text(Message::Clipboard, &[e]),
+            Error::Engine(e) => self.text(Message::Engine, &[e]),
+            Error::UnknownCommand {
+                name,
+                suggestion: Some(suggestion),
+            } => self.text(Message::DidYouMean, &[name, suggestion]),
+            Error::UnknownCommand { name, .. } => self.text(Message::UnknownCommand, &[name]),
+            Error::Usage(usage) => self.text(Message::Usage, &[usage]),
+        }
+    }
+
+    fn move_error(&self, e: &MoveError) -> String {
+        match e {
+            MoveError::NotYourTurn => self.text(Message::NotYourTurn, &[]),
+            MoveError::NoPiece { .. } => self.text(Message::NoPiece, &[]),
+            MoveError::NotYourPiece { .. } => self.text(Message::NotYourPiece, &[]),
+            MoveError::NotLegal(m) => self.text(Message::NotLegal, &[m]),
+            MoveError::Ambiguous(m) => self.text(Message::Ambiguous, &[m]),
+            MoveError::Malformed(m) => self.text(Message::Malformed, &[m]),
+            MoveError::PromotionRequired => self.text(Message::PromotionRequired, &[]),
+            e => e.to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for Locale {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Locale, Error> {
+        match name.to_lowercase().as_str() {
+            "en" | "english" => Ok(Locale::English),
+            "de" | "german" | "deutsch" => Ok(Locale::German),
+            "nl" | "dutch" | "nederlands" => Ok(Locale::Dutch),
+            "fr" | "french" | "français" => Ok(Locale::French),
+            "es" | "spanish" | "español" => Ok(Locale::Spanish),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = match self {
+            Locale::English => "en",
+            Locale::German => "de",
+            Locale::Dutch => "nl",
+            Locale::French => "fr",
+            Locale::Spanish => "es",
+        };
+        f.write_str(code)
+    }
+}
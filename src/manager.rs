@@ -0,0 +1,82 @@
+//! Several named games side by side, for correspondence-style play or for
+//! analysing a few positions in one session
+
+use crate::{chess::Error, game::Game};
+
+/// Named games, one of which is current. There is always at least one.
+pub struct GameManager {
+    games: Vec<(String, Game)>,
+    current: usize,
+}
+
+impl GameManager {
+    /// Starts with a single game under the given name
+    pub fn new(name: &str, game: Game) -> GameManager {
+        GameManager {
+            games: vec![(name.to_string(), game)],
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> &Game {
+        &self.games[self.current].1
+    }
+
+    pub fn current_mut(&mut self) -> &mut Game {
+        &mut self.games[self.current].1
+    }
+
+    pub fn current_name(&self) -> &str {
+        &self.games[self.current].0
+    }
+
+    /// Returns the names of the games in the order they were opened
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.games.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Game> {
+        self.games
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, game)| game)
+    }
+
+    fn index(&self, name: &str) -> Result<usize, Error> {
+        self.games
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or(Error::InvalidInput)
+    }
+
+    /// Adds a game and makes it current. Fails when the name is taken.
+    pub fn open(&mut self, name: &str, game: Game) -> Result<(), Error> {
+        if self.get(name).is_some() {
+            return Err(Error::InvalidInput);
+        }
+
+        self.games.push((name.to_string(), game));
+        self.current = self.games.len() - 1;
+        Ok(())
+    }
+
+    pub fn switch(&mut self, name: &str) -> Result<(), Error> {
+        self.current = self.index(name)?;
+        Ok(())
+    }
+
+    /// Closes a game and returns it. Closing the current game switches to
+    /// the one opened before it; the last game can't be closed.
+    pub fn close(&mut self, name: &str) -> Result<Game, Error> {
+        let index = self.index(name)?;
+        if self.games.len() == 1 {
+            return Err(Error::InvalidInput);
+        }
+
+        if index < self.current || (index == self.current && index > 0) {
+            self.current -= 1;
+        }
+
+        Ok(self.games.remove(index).1)
+    }
+}
@@ -0,0 +1,218 @@
+//! Arrows and highlighted squares drawn over the board, for teaching and for
+//! showing engine lines
+
+use crate::{
+    chess::{Board, Error, Square},
+    geometry::{between, Direction},
+    mover::Move,
+};
+
+/// The colors markup can be drawn in, those of the lichess board
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum Brush {
+    #[default]
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl Brush {
+    /// The ANSI background color of the brush
+    fn background(&self) -> &'static str {
+        match self {
+            Brush::Green => "42",
+            Brush::Red => "41",
+            Brush::Yellow => "43",
+            Brush::Blue => "44",
+        }
+    }
+}
+
+impl TryFrom<&str> for Brush {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Brush, Error> {
+        match name.to_lowercase().as_str() {
+            "green" | "g" => Ok(Brush::Green),
+            "red" | "r" => Ok(Brush::Red),
+            "yellow" | "y" => Ok(Brush::Yellow),
+            "blue" | "b" => Ok(Brush::Blue),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub brush: Brush,
+}
+
+impl Arrow {
+    /// Returns the squares the arrow passes over, leaving out both ends. A
+    /// knight's jump passes over none.
+    pub fn path(&self) -> Vec<Square> {
+        between(self.from, self.to)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Highlight {
+    pub square: Square,
+    pub brush: Brush,
+}
+
+/// Arrows and highlights to draw over a board. Drawing the same arrow or
+/// highlight twice takes it away again, as on lichess.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Markup {
+    arrows: Vec<Arrow>,
+    highlights: Vec<Highlight>,
+}
+
+impl Markup {
+    pub fn new() -> Markup {
+        Markup::default()
+    }
+
+    pub fn arrows(&self) -> &[Arrow] {
+        &self.arrows
+    }
+
+    pub fn highlights(&self) -> &[Highlight] {
+        &self.highlights
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty() && self.highlights.is_empty()
+    }
+
+    /// Draws an arrow between two different squares, replacing one of
+    /// another color between the same squares
+    pub fn arrow(&mut self, from: Square, to: Square, brush: Brush) -> Result<(), Error> {
+        if from == to {
+            return Err(Error::InvalidInput);
+        }
+
+        let arrow = Arrow { from, to, brush };
+        let existing = self
+            .arrows
+            .iter()
+            .position(|a| a.from == from && a.to == to);
+
+        match existing {
+            Some(i) if self.arrows[i] == arrow => {
+                self.arrows.remove(i);
+            }
+            Some(i) => self.arrows[i] = arrow,
+            None => self.arrows.push(arrow),
+        }
+
+        Ok(())
+    }
+
+    /// Highlights a square, replacing a highlight of another color on it
+    pub fn mark(&mut self, square: Square, brush: Brush) {
+        let highlight = Highlight { square, brush };
+        let existing = self.highlights.iter().position(|h| h.square == square);
+
+        match existing {
+            Some(i) if self.highlights[i] == highlight => {
+                self.highlights.remove(i);
+            }
+            Some(i) => self.highlights[i] = highlight,
+            None => self.highlights.push(highlight),
+        }
+    }
+
+    /// Draws the moves of a line, e.g. an engine's best line, in green for
+    /// the side to move and blue for its opponent
+    pub fn line(&mut self, line: &[Move]) {
+        for (i, m) in line.iter().enumerate() {
+            let brush = match i % 2 {
+                0 => Brush::Green,
+                _ => Brush::Blue,
+            };
+
+            // drops have no square to start the arrow from
+            if m.drop.is_none() {
+                let from = (m.from_file, m.from_rank);
+                let to = (m.to_file, m.to_rank);
+
+                self.arrows.retain(|a| a.from != from || a.to != to);
+                self.arrows.push(Arrow { from, to, brush });
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.arrows.clear();
+        self.highlights.clear();
+    }
+
+    /// Returns the ANSI background of a square: its highlight, or else the
+    /// color of the latest arrow starting, ending or passing over it
+    pub(crate) fn background(&self, square: Square) -> Option<&'static str> {
+        if let Some(h) = self.highlights.iter().find(|h| h.square == square) {
+            return Some(h.brush.background());
+        }
+
+        self.arrows
+            .iter()
+            .rev()
+            .find(|a| a.from == square || a.to == square || a.path().contains(&square))
+            .map(|a| a.brush.background())
+    }
+
+    /// Returns the symbol for an empty square an arrow passes over or ends
+    /// on, pointing the way the arrow runs
+    pub(crate) fn symbol(&self, square: Square) -> Option<char> {
+        self.arrows
+            .iter()
+            .rev()
+            .find(|a| a.to == square || a.path().contains(&square))
+            .map(|a| match Direction::towards(a.from, a.to) {
+                Some(Direction::North) => '↑',
+                Some(Direction::NorthEast) => '↗',
+                Some(Direction::East) => '→',
+                Some(Direction::SouthEast) => '↘',
+                Some(Direction::South) => '↓',
+                Some(Direction::SouthWest) => '↙',
+                Some(Direction::West) => '←',
+                Some(Direction::NorthWest) => '↖',
+                // a knight's jump
+                None => '*',
+            })
+    }
+}
+
+/// Draws the board like `Board::to_unicode`, with the markup in color
+pub fn render(board: &Board, markup: &Markup) -> String {
+    let mut out = String::new();
+
+    for rank in (0..8).rev() {
+        out.push_str(&(rank + 1).to_string());
+
+        for file in 0..8 {
+            let square = (file, rank);
+            let symbol = match board.get_piece(file, rank) {
+                Some(piece) => piece.to_string(),
+                None => markup.symbol(square).unwrap_or('·').to_string(),
+            };
+
+            out.push(' ');
+            match markup.background(square) {
+                Some(color) => out.push_str(&format!("\x1b[{}m{}\x1b[0m", color, symbol)),
+                None => out.push_str(&symbol),
+            }
+        }
+
+        out.push('\n');
+    }
+
+    out.push_str("  a b c d e f g h");
+
+    out
+}
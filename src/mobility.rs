@@ -0,0 +1,160 @@
+//! How freely the pieces can move, for the evaluation and for showing
+//! which pieces are short of squares
+
+use crate::{
+    chess::{Board, Class, Color, Square, KNIGHT_OFFSETS},
+    geometry::{ray, Direction, SquareGeometry},
+    see::value,
+};
+
+/// How many squares a piece can move to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PieceMobility {
+    pub square: Square,
+    pub class: Class,
+    pub moves: usize,
+}
+
+impl Board {
+    /// Returns the squares the piece on a square could move to if its side
+    /// were to move, by the piece rules alone. Pins, checks and castling
+    /// are left out, which keeps it cheap enough for the evaluation.
+    pub fn destinations(&self, square: Square) -> Vec<Square> {
+        let Some(piece) = self.get_piece(square.0, square.1) else {
+            return Vec::new();
+        };
+
+        let free = |at: Square| {
+            self.get_piece(at.0, at.1)
+                .is_none_or(|other| other.color != piece.color)
+        };
+
+        let mut squares = Vec::new();
+
+        match piece.class {
+            Class::Pawn => {
+                let (forward, start, captures) = match piece.color {
+                    Color::White => (
+                        Direction::North,
+                        1,
+                        [Direction::NorthWest, Direction::NorthEast],
+                    ),
+                    Color::Black => (
+                        Direction::South,
+                        6,
+                        [Direction::SouthWest, Direction::SouthEast],
+                    ),
+                };
+                let empty = |at: &Square| self.get_piece(at.0, at.1).is_none();
+
+                let pushes = if square.1 == start { 2 } else { 1 };
+                squares.extend(
+                    ray(square, forward)
+                        .into_iter()
+                        .take(pushes)
+                        .take_while(empty),
+                );
+
+                for direction in captures {
+                    if let Some(at) = square.step(direction) {
+                        let enemy = self
+                            .get_piece(at.0, at.1)
+                            .is_some_and(|other| other.color != piece.color);
+                        if enemy || self.is_en_passant(at.0, at.1) {
+                            squares.push(at);
+                        }
+                    }
+                }
+            }
+            Class::Knight => {
+                for (df, dr) in KNIGHT_OFFSETS {
+                    let (f, r) = (square.0 as i32 + df, square.1 as i32 + dr);
+                    if (0..8).contains(&f) && (0..8).contains(&r) && free((f as usize, r as usize))
+                    {
+                        squares.push((f as usize, r as usize));
+                    }
+                }
+            }
+            Class::King => {
+                squares.extend(
+                    Direction::ALL
+                        .into_iter()
+                        .filter_map(|direction| square.step(direction))
+                        .filter(|at| free(*at)),
+                );
+            }
+            class => {
+                for direction in Direction::ALL {
+                    let slides = match direction.is_diagonal() {
+                        true => class != Class::Rook,
+                        false => class != Class::Bishop,
+                    };
+                    if !slides {
+                        continue;
+                    }
+
+                    for at in ray(square, direction) {
+                        if free(at) {
+                            squares.push(at);
+                        }
+                        if self.get_piece(at.0, at.1).is_some() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        squares
+    }
+
+    /// Returns the number of squares each piece of a color can move to, by
+    /// the same rules as `destinations`
+    pub fn piece_mobility(&self, color: Color) -> Vec<PieceMobility> {
+        let mut pieces = Vec::new();
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let Some(piece) = self.get_piece(file, rank).filter(|p| p.color == color) else {
+                    continue;
+                };
+
+                pieces.push(PieceMobility {
+                    square: (file, rank),
+                    class: piece.class,
+                    moves: self.destinations((file, rank)).len(),
+                });
+            }
+        }
+
+        pieces
+    }
+
+    /// Returns the number of moves all pieces of a color have between them
+    pub fn mobility(&self, color: Color) -> usize {
+        self.piece_mobility(color).iter().map(|p| p.moves).sum()
+    }
+
+    /// Returns the knights, bishops, rooks and queens of a color that are
+    /// attacked without a safe square to go to: every square they can reach
+    /// is attacked by the opponent, unless it holds a piece worth at least
+    /// as much
+    pub fn trapped(&self, color: Color) -> Vec<Square> {
+        let opponent = color.opponent();
+
+        self.piece_mobility(color)
+            .into_iter()
+            .filter(|p| ![Class::Pawn, Class::King].contains(&p.class))
+            .filter(|p| self.is_attacked(p.square.0, p.square.1, opponent))
+            .filter(|p| {
+                self.destinations(p.square).into_iter().all(|at| {
+                    let worth_it = self
+                        .get_piece(at.0, at.1)
+                        .is_some_and(|victim| value(victim.class) >= value(p.class));
+                    !worth_it && self.is_attacked(at.0, at.1, opponent)
+                })
+            })
+            .map(|p| p.square)
+            .collect()
+    }
+}
@@ -1,4 +1,12 @@
-use crate::chess::{Board, Class, Color, Error};
+use std::fmt::Display;
+
+use crate::{
+    chess::{Board, Class, Color, Error, MoveError, Piece, Square, Status},
+    fen::ToFen,
+    game::GameResult,
+    geometry::{between, SquareGeometry},
+    notation, pgn,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Move {
@@ -6,6 +14,11 @@ pub struct Move {
     pub from_rank: usize,
     pub to_file: usize,
     pub to_rank: usize,
+    /// The piece dropped from the pocket in variants with drops, in which
+    /// case the from square is the same as the destination
+    pub drop: Option<Class>,
+    /// The piece a pawn reaching the last rank becomes
+    pub promotion: Option<Class>,
 }
 
 impl Move {
@@ -15,9 +28,68 @@ impl Move {
             from_rank,
             to_file,
             to_rank,
+            drop: None,
+            promotion: None,
+        }
+    }
+
+    /// Creates a move dropping a piece from the pocket onto a square
+    pub fn new_drop(class: Class, file: usize, rank: usize) -> Move {
+        Move {
+            from_file: file,
+            from_rank: rank,
+            to_file: file,
+            to_rank: rank,
+            drop: Some(class),
+            promotion: None,
+        }
+    }
+
+    /// Returns the move promoting to the given piece
+    pub fn with_promotion(self, class: Class) -> Move {
+        Move {
+            promotion: Some(class),
+            ..self
         }
     }
 
+    pub fn is_drop(&self) -> bool {
+        self.drop.is_some()
+    }
+
+    pub fn to_san(&self, board: &Board) -> Result<String, Error> {
+        notation::san(board, self)
+    }
+
+    pub fn from_san(board: &Board, san: &str) -> Result<Move, Error> {
+        notation::parse_san(board, san)
+    }
+
+    pub fn to_lan(&self, board: &Board) -> Result<String, Error> {
+        notation::to_lan(board, self)
+    }
+
+    pub fn to_iccf(&self) -> Result<String, Error> {
+        notation::to_iccf(self)
+    }
+
+    pub fn from_iccf(iccf: &str) -> Result<Move, Error> {
+        notation::from_iccf(iccf)
+    }
+
+    /// Reads a move the way players type it: coordinate notation, castling
+    /// as `O-O` or `0-0-0`, long algebraic such as `e2-e4` or `Ng1xf3`, or
+    /// ICCF numeric notation such as `5254`. A `+` or `#` after the move is
+    /// ignored. A move in none of them fails as coordinate notation does.
+    pub fn parse(board: &Board, text: &str) -> Result<Move, Error> {
+        let text = text.trim().trim_end_matches(['+', '#']);
+
+        Move::try_from(text)
+            .or_else(|e| pgn::parse_castling(board, text).ok_or(e))
+            .or_else(|e| Move::from_iccf(text).map_err(|_| e))
+            .or_else(|e| notation::from_lan(board, text).map_err(|_| e))
+    }
+
     pub fn distance(&self) -> usize {
         let file_distance = self.from_file as isize - self.to_file as isize;
         let rank_distance = self.from_rank as isize - self.to_rank as isize;
@@ -26,18 +98,45 @@ impl Move {
     }
 
     pub fn validate(&self, board: &Board) -> Result<(), Error> {
+        self.validate_movement(board)?;
+
+        if board.variant().forces_captures() && !board.is_capture(self) && board.has_capture() {
+            return Err(MoveError::CaptureRequired.into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks the move against the rules of the piece alone, leaving out
+    /// rules that depend on the other moves available
+    pub(crate) fn validate_movement(&self, board: &Board) -> Result<(), Error> {
+        if let Some(class) = self.drop {
+            return self.validate_drop(board, class);
+        }
+
         let piece = board.get_piece(self.from_file, self.from_rank);
 
         // There is no piece on the square
         if piece.is_none() {
-            return Err(Error::InvalidMove("No piece on square".to_string()));
+            return Err(MoveError::NoPiece {
+                at: (self.from_file, self.from_rank),
+            }
+            .into());
         }
 
         let piece = piece.unwrap();
 
         // Not our piece
         if piece.color != board.turn() {
-            return Err(Error::InvalidMove("Not your piece".to_string()));
+            return Err(MoveError::NotYourPiece {
+                at: (self.from_file, self.from_rank),
+            }
+            .into());
+        }
+
+        // The piece has to go somewhere
+        if self.from_file == self.to_file && self.from_rank == self.to_rank {
+            return Err(MoveError::NoMovement.into());
         }
 
         // class independent validation
@@ -56,27 +155,172 @@ impl Move {
         // Trying to capture our own piece
         if let Some(t) = target {
             if t.color == board.turn() {
-                return Err(Error::InvalidMove(
-                    "Can't capture your own piece".to_string(),
-                ));
+                return Err(MoveError::OwnPiece {
+                    at: (self.to_file, self.to_rank),
+                }
+                .into());
             }
         }
 
-        Ok(())
+        match (board.is_promotion(self), self.promotion) {
+            (true, None) => Err(MoveError::PromotionRequired.into()),
+            (true, Some(class)) if !board.variant().promotions().contains(&class) => {
+                Err(MoveError::InvalidPromotion(class).into())
+            }
+            (false, Some(class)) => Err(MoveError::InvalidPromotion(class).into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What happened when a move was played, so callers don't have to compare
+/// boards to find out
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MoveOutcome {
+    pub m: Move,
+    pub color: Color,
+    /// The piece that moved or was dropped
+    pub piece: Class,
+    pub capture: Option<Piece>,
+    pub en_passant: bool,
+    pub castled: bool,
+    pub promotion: Option<Class>,
+    /// Whether the move gives check
+    pub check: bool,
+    /// The status of the side to move after the move
+    pub status: Status,
+    /// The result of the game if the move ended it, `Ongoing` otherwise
+    pub result: GameResult,
+}
+
+impl MoveOutcome {
+    pub fn is_capture(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    pub fn ends_game(&self) -> bool {
+        self.result != GameResult::Ongoing
+    }
+
+    /// The `+` or `#` the move earns by giving check or mate
+    pub fn check_suffix(&self) -> Option<char> {
+        match self.status {
+            Status::Checkmate => Some('#'),
+            Status::Check => Some('+'),
+            _ => None,
+        }
+    }
+}
+
+/// Why a move is not legal, for showing precise feedback to the player
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IllegalReason {
+    NoPiece,
+    WrongTurn,
+    NoMovement,
+    /// The destination holds a piece of the mover's own color
+    OwnPiece {
+        at: Square,
+    },
+    /// Another piece stands in the way
+    Blocked {
+        at: Square,
+    },
+    /// The piece does not move like that
+    InvalidPath(Class),
+    /// A capture is available and has to be made
+    CaptureRequired,
+    CastlingOutOfCheck,
+    CastlingThroughCheck,
+    /// The king would move onto an attacked square
+    MovesIntoCheck,
+    /// The move does not get the king out of check
+    KingInCheck,
+    /// The piece shields its king and can't leave the line of attack
+    Pinned,
+    DropsNotAllowed,
+    NotInHand(Class),
+    /// Drops need an empty square
+    Occupied {
+        at: Square,
+    },
+    PawnOnBackRank,
+    /// A pawn reaching the last rank has to promote
+    PromotionRequired,
+    InvalidPromotion(Class),
+}
+
+impl Display for IllegalReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let square = |at: Square| at.name();
+
+        match self {
+            IllegalReason::NoPiece => write!(f, "There is no piece on that square"),
+            IllegalReason::WrongTurn => write!(f, "It is not that piece's turn"),
+            IllegalReason::NoMovement => write!(f, "The piece has to move"),
+            IllegalReason::OwnPiece { at } => {
+                write!(f, "Can't capture your own piece on {}", square(*at))
+            }
+            IllegalReason::Blocked { at } => write!(f, "The way is blocked on {}", square(*at)),
+            IllegalReason::InvalidPath(class) => write!(f, "A {:?} can't move like that", class),
+            IllegalReason::CaptureRequired => write!(f, "A capture has to be made"),
+            IllegalReason::CastlingOutOfCheck => write!(f, "Can't castle out of check"),
+            IllegalReason::CastlingThroughCheck => write!(f, "Can't castle through check"),
+            IllegalReason::MovesIntoCheck => write!(f, "The king would be in check"),
+            IllegalReason::KingInCheck => write!(f, "The king is in check"),
+            IllegalReason::Pinned => write!(f, "The piece is pinned to the king"),
+            IllegalReason::DropsNotAllowed => write!(f, "Pieces can't be dropped"),
+            IllegalReason::NotInHand(class) => write!(f, "No {:?} in hand", class),
+            IllegalReason::Occupied { at } => write!(f, "{} is not empty", square(*at)),
+            IllegalReason::PawnOnBackRank => {
+                write!(f, "Pawns can't be dropped on the first or last rank")
+            }
+            IllegalReason::PromotionRequired => {
+                write!(f, "Choose a piece for the pawn to promote to")
+            }
+            IllegalReason::InvalidPromotion(class) => write!(f, "Can't promote to a {:?}", class),
+        }
     }
 }
 
 impl Move {
+    fn validate_drop(&self, board: &Board, class: Class) -> Result<(), Error> {
+        if let Some(promotion) = self.promotion {
+            return Err(MoveError::InvalidPromotion(promotion).into());
+        }
+
+        if !board.variant().has_drops() {
+            return Err(MoveError::DropsNotAllowed(board.variant()).into());
+        }
+
+        if board.pocket(board.turn()).count(class) == 0 {
+            return Err(MoveError::NotInHand(class).into());
+        }
+
+        if board.get_piece(self.to_file, self.to_rank).is_some() {
+            return Err(MoveError::DropOccupied {
+                at: (self.to_file, self.to_rank),
+            }
+            .into());
+        }
+
+        if class == Class::Pawn && (self.to_rank == 0 || self.to_rank == 7) {
+            return Err(MoveError::PawnDropOnBackRank.into());
+        }
+
+        Ok(())
+    }
+
     fn validate_pawn(&self, board: &Board) -> Result<(), Error> {
         let piece = board.get_piece(self.from_file, self.from_rank).unwrap();
 
         // Pawn can only move forward
         if piece.color == Color::White && self.to_rank < self.from_rank {
-            return Err(Error::InvalidMove("Pawn can only move forward".to_string()));
+            return Err(MoveError::PawnBackwards.into());
         }
 
         if piece.color == Color::Black && self.to_rank > self.from_rank {
-            return Err(Error::InvalidMove("Pawn can only move forward".to_string()));
+            return Err(MoveError::PawnBackwards.into());
         }
 
         let from_file: i32 = self.from_file as i32;
@@ -85,50 +329,69 @@ impl Move {
         let to_file: i32 = self.to_file as i32;
         let to_rank: i32 = self.to_rank as i32;
 
-        // Pawn can only move one square forward, unless it is the first move
-        if piece.moves == 0 {
+        // Pawn can only move one square forward, unless it is still on its
+        // start rank
+        let start_rank = match piece.color {
+            Color::White => 1,
+            Color::Black => 6,
+        };
+
+        if self.from_rank == start_rank {
             if (to_rank - from_rank).abs() > 2 || (to_rank - from_rank).abs() < 1 {
-                return Err(Error::InvalidMove(
-                    format!("Pawn can only move one or two squares forward on the first move, attempted to move {} squares", (to_rank - from_rank).abs()).to_string(),
-                ));
+                return Err(MoveError::PawnTooFar {
+                    distance: (to_rank - from_rank).unsigned_abs() as usize,
+                    first_move: true,
+                }
+                .into());
             }
         } else {
             if (to_rank - from_rank).abs() != 1 {
-                return Err(Error::InvalidMove(
-                    "Pawn can only move one square forward".to_string(),
-                ));
+                return Err(MoveError::PawnTooFar {
+                    distance: (to_rank - from_rank).unsigned_abs() as usize,
+                    first_move: false,
+                }
+                .into());
             }
         }
 
-        let target = match board.get_piece(self.to_file, self.to_rank) {
-            Some(t) => {
-                if t.color == piece.color {
-                    None
-                } else {
-                    Some(t)
+        let diagonal_step = (to_file - from_file).abs() == 1 && (to_rank - from_rank).abs() == 1;
+
+        // Pushes need every square up to the destination empty, whoever
+        // stands there
+        if to_file == from_file {
+            if (to_rank - from_rank).abs() == 2 {
+                let passed = (self.from_file, (self.from_rank + self.to_rank) / 2);
+                if board.get_piece(passed.0, passed.1).is_some() {
+                    return Err(MoveError::PawnPushBlocked { at: passed }.into());
                 }
             }
-            None => None,
-        };
+
+            if board.get_piece(self.to_file, self.to_rank).is_some() {
+                return Err(MoveError::PawnPushOccupied {
+                    at: (self.to_file, self.to_rank),
+                }
+                .into());
+            }
+
+            return Ok(());
+        }
+
+        let target = board
+            .get_piece(self.to_file, self.to_rank)
+            .filter(|t| t.color != piece.color);
 
         // Pawn can only move one square sideways if capturing target
         if target.is_some() {
-            if (to_file - from_file).abs() != 1 {
-                return Err(Error::InvalidMove(
-                    "Pawn can only capture diagonally".to_string(),
-                ));
+            if !diagonal_step {
+                return Err(MoveError::PawnCaptureNotDiagonal.into());
             }
         } else {
-            // if the destination square is board.en_passant, then we are capturing en passant
-            if board.is_en_passant(self.to_file, self.to_rank) {
+            // a single diagonal step onto board.en_passant captures en passant
+            if diagonal_step && board.is_en_passant(self.to_file, self.to_rank) {
                 return Ok(());
             }
 
-            if (to_file - from_file).abs() != 0 {
-                return Err(Error::InvalidMove(
-                    "Pawn can not move diagonally".to_string(),
-                ));
-            }
+            return Err(MoveError::PawnDiagonalWithoutCapture.into());
         }
 
         Ok(())
@@ -136,6 +399,21 @@ impl Move {
 }
 
 impl Move {
+    /// Fails on the first piece standing between the squares of the move
+    fn check_path(&self, board: &Board, class: Class) -> Result<(), Error> {
+        let blocker = between(
+            (self.from_file, self.from_rank),
+            (self.to_file, self.to_rank),
+        )
+        .into_iter()
+        .find(|(file, rank)| board.get_piece(*file, *rank).is_some());
+
+        match blocker {
+            Some(at) => Err(MoveError::Blocked { piece: class, at }.into()),
+            None => Ok(()),
+        }
+    }
+
     pub fn validate_knight(&self, _: &Board) -> Result<(), Error> {
         let from_file: i32 = self.from_file as i32;
         let from_rank: i32 = self.from_rank as i32;
@@ -152,9 +430,7 @@ impl Move {
             return Ok(());
         }
 
-        Err(Error::InvalidMove(
-            "Knight can only move two squares forward and one square sideways, or two squares sideways and one square forward".to_string(),
-        ))
+        Err(MoveError::InvalidPath(Class::Knight).into())
     }
 }
 
@@ -168,31 +444,10 @@ impl Move {
 
         // Bishop can only move diagonally
         if (to_file - from_file).abs() != (to_rank - from_rank).abs() {
-            return Err(Error::InvalidMove(
-                "Bishop can only move diagonally".to_string(),
-            ));
-        }
-
-        // Check if there are any pieces in the way
-        let file_direction = if to_file > from_file { 1 } else { -1 };
-
-        let rank_direction = if to_rank > from_rank { 1 } else { -1 };
-
-        let mut file = from_file + file_direction;
-        let mut rank = from_rank + rank_direction;
-
-        while file != to_file && rank != to_rank {
-            if let Some(_) = board.get_piece(file as usize, rank as usize) {
-                return Err(Error::InvalidMove(
-                    "Bishop can not move through pieces".to_string(),
-                ));
-            }
-
-            file += file_direction;
-            rank += rank_direction;
+            return Err(MoveError::InvalidPath(Class::Bishop).into());
         }
 
-        Ok(())
+        self.check_path(board, Class::Bishop)
     }
 }
 
@@ -206,43 +461,10 @@ impl Move {
 
         // Rook can only move horizontally or vertically
         if from_file != to_file && from_rank != to_rank {
-            return Err(Error::InvalidMove(
-                "Rook can only move horizontally or vertically".to_string(),
-            ));
+            return Err(MoveError::InvalidPath(Class::Rook).into());
         }
 
-        // Check if there are any pieces in the way
-        if from_file == to_file {
-            let direction = if to_rank > from_rank { 1 } else { -1 };
-
-            let mut rank = from_rank + direction;
-
-            while rank != to_rank {
-                if let Some(_) = board.get_piece(from_file as usize, rank as usize) {
-                    return Err(Error::InvalidMove(
-                        "Rook can not move through pieces".to_string(),
-                    ));
-                }
-
-                rank += direction;
-            }
-        } else {
-            let direction = if to_file > from_file { 1 } else { -1 };
-
-            let mut file = from_file + direction;
-
-            while file != to_file {
-                if let Some(_) = board.get_piece(file as usize, from_rank as usize) {
-                    return Err(Error::InvalidMove(
-                        "Rook can not move through pieces".to_string(),
-                    ));
-                }
-
-                file += direction;
-            }
-        }
-
-        Ok(())
+        self.check_path(board, Class::Rook)
     }
 }
 
@@ -259,61 +481,10 @@ impl Move {
             && from_rank != to_rank
             && (to_file - from_file).abs() != (to_rank - from_rank).abs()
         {
-            return Err(Error::InvalidMove(
-                "Queen can only move horizontally, vertically, or diagonally".to_string(),
-            ));
-        }
-
-        // Check if there are any pieces in the way
-        if from_file == to_file {
-            let direction = if to_rank > from_rank { 1 } else { -1 };
-
-            let mut rank = from_rank + direction;
-
-            while rank != to_rank {
-                if let Some(_) = board.get_piece(from_file as usize, rank as usize) {
-                    return Err(Error::InvalidMove(
-                        "Queen can not move through pieces".to_string(),
-                    ));
-                }
-
-                rank += direction;
-            }
-        } else if from_rank == to_rank {
-            let direction = if to_file > from_file { 1 } else { -1 };
-
-            let mut file = from_file + direction;
-
-            while file != to_file {
-                if let Some(_) = board.get_piece(file as usize, from_rank as usize) {
-                    return Err(Error::InvalidMove(
-                        "Queen can not move through pieces".to_string(),
-                    ));
-                }
-
-                file += direction;
-            }
-        } else {
-            let file_direction = if to_file > from_file { 1 } else { -1 };
-
-            let rank_direction = if to_rank > from_rank { 1 } else { -1 };
-
-            let mut file = from_file + file_direction;
-            let mut rank = from_rank + rank_direction;
-
-            while file != to_file && rank != to_rank {
-                if let Some(_) = board.get_piece(file as usize, rank as usize) {
-                    return Err(Error::InvalidMove(
-                        "Queen can not move through pieces".to_string(),
-                    ));
-                }
-
-                file += file_direction;
-                rank += rank_direction;
-            }
+            return Err(MoveError::InvalidPath(Class::Queen).into());
         }
 
-        Ok(())
+        self.check_path(board, Class::Queen)
     }
 }
 
@@ -327,142 +498,190 @@ impl Move {
         let to_file: i32 = self.to_file as i32;
         let to_rank: i32 = self.to_rank as i32;
 
-        // Check if attempting to move on kingside (white)
-        if piece.color == Color::White
-            && piece.moves == 0
-            && from_file == 4
-            && from_rank == 0
-            && to_file == 6
-            && to_rank == 0
-        {
-            if let Some(rook) = board.get_piece(7, 0) {
-                if rook.class == Class::Rook && rook.color == Color::White && rook.moves == 0 {
-                    if board.get_piece(5, 0).is_none() && board.get_piece(6, 0).is_none() {
-                        return Ok(());
-                    }
-                }
-            }
-        }
-
-        // Check if attempting to move on queenside (white)
-        if piece.color == Color::White
-            && piece.moves == 0
-            && from_file == 4
-            && from_rank == 0
-            && to_file == 2
-            && to_rank == 0
-        {
-            if let Some(rook) = board.get_piece(0, 0) {
-                if rook.class == Class::Rook && rook.color == Color::White && rook.moves == 0 {
-                    if board.get_piece(1, 0).is_none()
-                        && board.get_piece(2, 0).is_none()
-                        && board.get_piece(3, 0).is_none()
-                    {
-                        return Ok(());
-                    }
-                }
-            }
-        }
-
-        // Check if attempting to move on kingside (black)
-        if piece.color == Color::Black
-            && piece.moves == 0
-            && from_file == 4
-            && from_rank == 7
-            && to_file == 6
-            && to_rank == 7
-        {
-            if let Some(rook) = board.get_piece(7, 7) {
-                if rook.class == Class::Rook && rook.color == Color::Black && rook.moves == 0 {
-                    if board.get_piece(5, 7).is_none() && board.get_piece(6, 7).is_none() {
-                        return Ok(());
-                    }
-                }
-            }
-        }
+        // castling is not part of every variant. The castling rights say
+        // whether king and rook have moved, which a position loaded from a
+        // FEN can't tell from the pieces.
+        let home = match piece.color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
 
-        // Check if attempting to move on queenside (black)
-        if piece.color == Color::Black
-            && piece.moves == 0
+        if board.variant().has_castling()
             && from_file == 4
-            && from_rank == 7
-            && to_file == 2
-            && to_rank == 7
+            && from_rank == home
+            && to_rank == home
+            && (to_file - from_file).abs() == 2
         {
-            if let Some(rook) = board.get_piece(0, 7) {
-                if rook.class == Class::Rook && rook.color == Color::Black && rook.moves == 0 {
-                    if board.get_piece(1, 7).is_none()
-                        && board.get_piece(2, 7).is_none()
-                        && board.get_piece(3, 7).is_none()
-                    {
-                        return Ok(());
-                    }
-                }
+            let (allowed, rook_file, between) = match to_file {
+                6 => (board.can_castle_kingside(piece.color), 7, 5..=6),
+                _ => (board.can_castle_queenside(piece.color), 0, 1..=3),
+            };
+
+            let rook = board
+                .get_piece(rook_file, self.from_rank)
+                .is_some_and(|r| r.class == Class::Rook && r.color == piece.color);
+
+            if allowed
+                && rook
+                && between
+                    .into_iter()
+                    .all(|file| board.get_piece(file, self.from_rank).is_none())
+            {
+                return Ok(());
             }
         }
 
         if (to_file - from_file).abs() > 1 || (to_rank - from_rank).abs() > 1 {
-            return Err(Error::InvalidMove(
-                "King can only move one square in any direction".to_string(),
-            ));
+            return Err(MoveError::InvalidPath(Class::King).into());
         }
 
         Ok(())
     }
 }
 
+/// Parses a square in coordinate notation such as `e4` into file and rank
+pub fn parse_square(data: &str) -> Result<Square, Error> {
+    let data = data.trim().to_lowercase();
+    let mut chars = data.chars();
+
+    let (file, rank) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(file), Some(rank), None) => (file, rank),
+        _ => return Err(Error::InvalidInput),
+    };
+
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return Err(Error::InvalidInput);
+    }
+
+    Ok(((file as u8 - b'a') as usize, (rank as u8 - b'1') as usize))
+}
+
+/// Reads the letter of a piece a pawn can become, in either case. The king
+/// counts for variants that allow promoting to it.
+pub(crate) fn promotion_class(letter: &str) -> Option<Class> {
+    match letter.to_lowercase().as_str() {
+        "n" => Some(Class::Knight),
+        "b" => Some(Class::Bishop),
+        "r" => Some(Class::Rook),
+        "q" => Some(Class::Queen),
+        "k" => Some(Class::King),
+        _ => None,
+    }
+}
+
 impl TryFrom<&str> for Move {
     type Error = crate::chess::Error;
 
-    fn try_from(m: &str) -> Result<Move, Error> {
-        let m = m.to_lowercase();
+    /// Reads a move in coordinate notation such as `e2e4`, `e7e8q` or
+    /// `N@f3`
+    fn try_from(text: &str) -> Result<Move, Error> {
+        let malformed = || Error::from(MoveError::Malformed(text.to_string()));
+        let m = text.trim().to_lowercase();
 
-        if m.len() != 4 {
-            return Err(Error::InvalidInput);
+        // checking for ASCII first keeps multibyte characters from passing
+        // the length checks and splitting mid-character
+        if !m.is_ascii() {
+            return Err(malformed());
         }
 
-        let from_file = m.chars().nth(0).unwrap() as isize - 97;
-        let from_rank = m.chars().nth(1).unwrap() as isize - 49;
-        let to_file = m.chars().nth(2).unwrap() as isize - 97;
-        let to_rank = m.chars().nth(3).unwrap() as isize - 49;
+        // drops are written as the piece letter, an @ and the square
+        if let Some((piece, square)) = m.split_once('@') {
+            let class = match piece {
+                "p" => Some(Class::Pawn),
+                "k" => None,
+                piece => promotion_class(piece),
+            }
+            .ok_or_else(malformed)?;
 
-        // ensure that the move is within the bounds of the board
-        if from_file < 0
-            || from_file > 7
-            || from_rank < 0
-            || from_rank > 7
-            || to_file < 0
-            || to_file > 7
-            || to_rank < 0
-            || to_rank > 7
-        {
-            return Err(Error::InvalidInput);
+            let (file, rank) = parse_square(square).map_err(|_| malformed())?;
+
+            return Ok(Move::new_drop(class, file, rank));
+        }
+
+        if m.len() != 4 && m.len() != 5 {
+            return Err(malformed());
         }
 
-        Ok(Move {
-            from_file: from_file as usize,
-            from_rank: from_rank as usize,
-            to_file: to_file as usize,
-            to_rank: to_rank as usize,
-        })
+        let (from_file, from_rank) = parse_square(&m[0..2]).map_err(|_| malformed())?;
+        let (to_file, to_rank) = parse_square(&m[2..4]).map_err(|_| malformed())?;
+        let m = match &m[4..] {
+            "" => Move::new(from_file, from_rank, to_file, to_rank),
+            class => Move::new(from_file, from_rank, to_file, to_rank)
+                .with_promotion(promotion_class(class).ok_or_else(malformed)?),
+        };
+
+        Ok(m)
     }
 }
 
 impl From<Move> for String {
     fn from(m: Move) -> String {
+        if let Some(class) = m.drop {
+            let piece = Piece::new(class, Color::White).to_fen();
+            return format!(
+                "{}@{}{}",
+                piece,
+                (m.to_file + 97) as u8 as char,
+                m.to_rank + 1
+            );
+        }
+
         let from_file = (m.from_file + 97) as u8 as char;
         let from_rank = (m.from_rank + 49) as u8 as char;
         let to_file = (m.to_file + 97) as u8 as char;
         let to_rank = (m.to_rank + 49) as u8 as char;
 
-        format!("{}{}{}{}", from_file, from_rank, to_file, to_rank)
+        let promotion = m.promotion.map_or(String::new(), |class| {
+            Piece::new(class, Color::Black).to_fen()
+        });
+
+        format!(
+            "{}{}{}{}{}",
+            from_file, from_rank, to_file, to_rank, promotion
+        )
     }
 }
 
-impl ToString for Move {
-    fn to_string(&self) -> String {
-        let string = String::from(*self);
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from(*self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn garbage_input_is_malformed() {
+        let inputs = [
+            "", " ", "e2", "e2e", "e2e9", "i2e4", "zz", "zzzz", "e7e8x", "e7e8qq", "e2-e4",
+            "e2e4e5", "@e4", "x@e4", "k@e4", "q@", "q@e9", "é2e4", "e2é4", "0000", "e0e4",
+        ];
+
+        for input in inputs {
+            assert!(
+                matches!(
+                    Move::try_from(input),
+                    Err(Error::InvalidMove(MoveError::Malformed(_)))
+                ),
+                "{:?} should be malformed",
+                input
+            );
+        }
+    }
 
-        string
+    #[test]
+    fn coordinate_moves_are_read() {
+        assert_eq!(Move::try_from("e2e4").unwrap(), Move::new(4, 1, 4, 3));
+        assert_eq!(Move::try_from(" E2E4 ").unwrap(), Move::new(4, 1, 4, 3));
+        assert_eq!(
+            Move::try_from("e7e8q").unwrap(),
+            Move::new(4, 6, 4, 7).with_promotion(Class::Queen)
+        );
+        assert_eq!(
+            Move::try_from("N@f3").unwrap(),
+            Move::new_drop(Class::Knight, 5, 2)
+        );
     }
 }
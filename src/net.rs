@@ -0,0 +1,383 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    chess::{Color, Error, MoveError},
+    game::Game,
+    notify::Notifier,
+};
+
+/// Largest message accepted from a peer, in bytes
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+/// A message of the network protocol. On the wire every message is a 4 byte
+/// big endian length followed by a flat JSON object with a `type` field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Opens a game. The host sends the color the peer plays and the peer
+    /// answers with its own name and that color.
+    Hello {
+        name: String,
+        color: Color,
+    },
+    Move(String),
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    Error(String),
+}
+
+impl Message {
+    pub fn to_json(&self) -> String {
+        let fields: Vec<(&str, String)> = match self {
+            Message::Hello { name, color } => vec![
+                ("type", "hello".to_string()),
+                ("name", name.clone()),
+                ("color", color.to_string()),
+            ],
+            Message::Move(m) => vec![("type", "move".to_string()), ("move", m.clone())],
+            Message::Resign => vec![("type", "resign".to_string())],
+            Message::OfferDraw => vec![("type", "offer_draw".to_string())],
+            Message::AcceptDraw => vec![("type", "accept_draw".to_string())],
+            Message::DeclineDraw => vec![("type", "decline_draw".to_string())],
+            Message::Error(message) => {
+                vec![("type", "error".to_string()), ("message", message.clone())]
+            }
+        };
+
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("{}:{}", quote(key), quote(value)))
+            .collect();
+
+        format!("{{{}}}", fields.join(","))
+    }
+
+    pub fn from_json(data: &str) -> Result<Message, Error> {
+        let fields = parse_object(data)?;
+
+        let field = |key: &str| -> Result<String, Error> {
+            fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .ok_or_else(|| Error::Network(format!("missing field '{}'", key)))
+        };
+
+        let message = match field("type")?.as_str() {
+            "hello" => Message::Hello {
+                name: field("name")?,
+                color: match field("color")?.as_str() {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(Error::Network("invalid color".to_string())),
+                },
+            },
+            "move" => Message::Move(field("move")?),
+            "resign" => Message::Resign,
+            "offer_draw" => Message::OfferDraw,
+            "accept_draw" => Message::AcceptDraw,
+            "decline_draw" => Message::DeclineDraw,
+            "error" => Message::Error(field("message")?),
+            other => return Err(Error::Network(format!("unknown message '{}'", other))),
+        };
+
+        Ok(message)
+    }
+}
+
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Parses a JSON object whose values are all strings
+fn parse_object(data: &str) -> Result<Vec<(String, String)>, Error> {
+    let invalid = || Error::Network("invalid message".to_string());
+
+    let mut chars = data.trim().chars().peekable();
+    let mut fields = Vec::new();
+
+    let skip_whitespace = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    };
+
+    let parse_string = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        if chars.next() != Some('"') {
+            return None;
+        }
+
+        let mut s = String::new();
+
+        loop {
+            match chars.next()? {
+                '"' => return Some(s),
+                '\\' => match chars.next()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        s.push(char::from_u32(code)?);
+                    }
+                    _ => return None,
+                },
+                c => s.push(c),
+            }
+        }
+    };
+
+    if chars.next() != Some('{') {
+        return Err(invalid());
+    }
+
+    skip_whitespace(&mut chars);
+
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_whitespace(&mut chars);
+            let key = parse_string(&mut chars).ok_or_else(invalid)?;
+
+            skip_whitespace(&mut chars);
+            if chars.next() != Some(':') {
+                return Err(invalid());
+            }
+
+            skip_whitespace(&mut chars);
+            let value = parse_string(&mut chars).ok_or_else(invalid)?;
+
+            fields.push((key, value));
+
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(invalid()),
+            }
+        }
+    }
+
+    skip_whitespace(&mut chars);
+    if chars.next().is_some() {
+        return Err(invalid());
+    }
+
+    Ok(fields)
+}
+
+fn network_error(e: std::io::Error) -> Error {
+    Error::Network(e.to_string())
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Message) -> Result<(), Error> {
+    let data = message.to_json();
+
+    writer
+        .write_all(&(data.len() as u32).to_be_bytes())
+        .map_err(network_error)?;
+    writer.write_all(data.as_bytes()).map_err(network_error)?;
+    writer.flush().map_err(network_error)?;
+
+    Ok(())
+}
+
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Message, Error> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len).map_err(network_error)?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(Error::Network(format!(
+            "message of {} bytes is too large",
+            len
+        )));
+    }
+
+    let mut data = vec![0; len];
+    reader.read_exact(&mut data).map_err(network_error)?;
+
+    let data = String::from_utf8(data).map_err(|_| Error::Network("invalid UTF-8".to_string()))?;
+
+    Message::from_json(&data)
+}
+
+/// A game played against a peer over TCP. The host plays white.
+pub struct Connection {
+    stream: TcpStream,
+    game: Game,
+    color: Color,
+    /// Told about every move the peer makes
+    notifier: Option<Notifier>,
+}
+
+impl Connection {
+    /// Waits for a peer to connect on the given port and starts a game
+    pub fn host(port: u16, name: &str) -> Result<Connection, Error> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(network_error)?;
+        let (mut stream, _) = listener.accept().map_err(network_error)?;
+
+        write_message(
+            &mut stream,
+            &Message::Hello {
+                name: name.to_string(),
+                color: Color::Black,
+            },
+        )?;
+
+        let peer = match read_message(&mut stream)? {
+            Message::Hello { name, .. } => name,
+            _ => return Err(Error::Network("expected hello".to_string())),
+        };
+
+        let mut game = Game::new()?;
+        game.set_players(name, &peer);
+
+        Ok(Connection {
+            stream,
+            game,
+            color: Color::White,
+            notifier: None,
+        })
+    }
+
+    /// Connects to a host and starts a game with the color it assigns
+    pub fn connect(address: &str, name: &str) -> Result<Connection, Error> {
+        let mut stream = TcpStream::connect(address).map_err(network_error)?;
+
+        let (host, color) = match read_message(&mut stream)? {
+            Message::Hello { name, color } => (name, color),
+            _ => return Err(Error::Network("expected hello".to_string())),
+        };
+
+        write_message(
+            &mut stream,
+            &Message::Hello {
+                name: name.to_string(),
+                color,
+            },
+        )?;
+
+        let mut game = Game::new()?;
+        match color {
+            Color::White => game.set_players(name, &host),
+            Color::Black => game.set_players(&host, name),
+        }
+
+        Ok(Connection {
+            stream,
+            game,
+            color,
+            notifier: None,
+        })
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The color played on this side of the connection
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets what happens when the peer moves, or nothing for `None`
+    pub fn set_notifier(&mut self, notifier: Option<Notifier>) {
+        self.notifier = notifier;
+    }
+
+    pub fn is_my_turn(&self) -> bool {
+        self.game.board().turn() == self.color
+    }
+
+    pub fn send_move(&mut self, m: &str) -> Result<(), Error> {
+        if !self.is_my_turn() {
+            return Err(MoveError::NotYourTurn.into());
+        }
+
+        // the peer gets the move in coordinate notation, however it was typed
+        let outcome = self.game.move_piece(m)?;
+        let mut m = String::from(outcome.m);
+        m.extend(outcome.check_suffix());
+
+        write_message(&mut self.stream, &Message::Move(m))
+    }
+
+    pub fn resign(&mut self) -> Result<(), Error> {
+        self.game.resign(self.color)?;
+        write_message(&mut self.stream, &Message::Resign)
+    }
+
+    pub fn offer_draw(&mut self) -> Result<(), Error> {
+        self.game.offer_draw(self.color)?;
+        write_message(&mut self.stream, &Message::OfferDraw)
+    }
+
+    pub fn accept_draw(&mut self) -> Result<(), Error> {
+        self.game.accept_draw(self.color)?;
+        write_message(&mut self.stream, &Message::AcceptDraw)
+    }
+
+    pub fn decline_draw(&mut self) -> Result<(), Error> {
+        self.game.decline_draw(self.color)?;
+        write_message(&mut self.stream, &Message::DeclineDraw)
+    }
+
+    /// Waits for the next message from the peer and applies it to the game.
+    /// Messages that break the rules are answered with an error message.
+    pub fn receive(&mut self) -> Result<Message, Error> {
+        let message = read_message(&mut self.stream)?;
+        let peer = self.color.opponent();
+
+        let result = match &message {
+            Message::Move(m) => {
+                if self.is_my_turn() {
+                    Err(MoveError::NotYourTurn.into())
+                } else {
+                    self.game.move_piece(m).map(|_| ())
+                }
+            }
+            Message::Resign => self.game.resign(peer),
+            Message::OfferDraw => self.game.offer_draw(peer),
+            Message::AcceptDraw => self.game.accept_draw(peer),
+            Message::DeclineDraw => self.game.decline_draw(peer),
+            Message::Hello { .. } => Err(Error::Network("unexpected hello".to_string())),
+            Message::Error(_) => Ok(()),
+        };
+
+        if let Err(e) = result {
+            write_message(&mut self.stream, &Message::Error(e.to_string()))?;
+            return Err(e);
+        }
+
+        if let (Message::Move(m), Some(notifier)) = (&message, &mut self.notifier) {
+            notifier.notify(m)?;
+        }
+
+        Ok(message)
+    }
+}
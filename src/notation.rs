@@ -0,0 +1,208 @@
+use crate::{
+    chess::{Board, Class, Color, Error, MoveError, Piece},
+    fen::ToFen,
+    geometry::SquareGeometry,
+    mover::{parse_square, Move},
+    pgn::{check_suffix, parse_castling, split_promotion},
+};
+
+pub use crate::pgn::{parse_san, san};
+
+/// The ways a move can be written down
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Notation {
+    /// Coordinates as used by UCI engines, e.g. `g1f3`
+    Uci,
+    /// Standard Algebraic Notation, e.g. `Nf3`
+    San,
+    /// Long algebraic notation, e.g. `Ng1-f3` or `e4xd5`
+    Lan,
+    /// ICCF numeric notation used in correspondence chess, e.g. `7163`
+    Iccf,
+}
+
+impl Notation {
+    /// Reads a move written in this notation in the given position
+    pub fn parse(&self, board: &Board, text: &str) -> Result<Move, Error> {
+        match self {
+            Notation::Uci => Move::try_from(text.trim()),
+            Notation::San => parse_san(board, text),
+            Notation::Lan => from_lan(board, text),
+            Notation::Iccf => from_iccf(text),
+        }
+    }
+
+    /// Writes a move in this notation. SAN and long algebraic notation need
+    /// the move to be legal in the position.
+    pub fn format(&self, board: &Board, m: &Move) -> Result<String, Error> {
+        match self {
+            Notation::Uci => Ok(String::from(*m)),
+            Notation::San => san(board, m),
+            Notation::Lan => to_lan(board, m),
+            Notation::Iccf => to_iccf(m),
+        }
+    }
+}
+
+impl TryFrom<&str> for Notation {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Notation, Error> {
+        match name.trim().to_lowercase().as_str() {
+            "uci" | "coordinate" => Ok(Notation::Uci),
+            "san" => Ok(Notation::San),
+            "lan" | "long" => Ok(Notation::Lan),
+            "iccf" | "numeric" => Ok(Notation::Iccf),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+/// Rewrites a move from one notation into another
+pub fn convert(board: &Board, text: &str, from: Notation, to: Notation) -> Result<String, Error> {
+    let m = from.parse(board, text)?;
+    to.format(board, &m)
+}
+
+/// Writes the move in long algebraic notation: the piece letter, both
+/// squares joined by `-` or `x`, and the check suffix
+pub fn to_lan(board: &Board, m: &Move) -> Result<String, Error> {
+    if !board.is_legal(m) {
+        return Err(MoveError::NotLegal(m.to_string()).into());
+    }
+
+    let piece = match m.drop {
+        Some(_) => None,
+        None => board.get_piece(m.from_file, m.from_rank),
+    };
+
+    // drops and castling look the same as in SAN
+    let castles =
+        piece.is_some_and(|p| p.class == Class::King) && m.from_file.abs_diff(m.to_file) == 2;
+    if piece.is_none() || castles {
+        return san(board, m);
+    }
+
+    let piece = piece.unwrap();
+    let mut lan = String::new();
+
+    if piece.class != Class::Pawn {
+        lan.push_str(&Piece::new(piece.class, Color::White).to_fen());
+    }
+
+    let capture = board.get_piece(m.to_file, m.to_rank).is_some()
+        || (piece.class == Class::Pawn && board.is_en_passant(m.to_file, m.to_rank));
+
+    lan.push_str(&(m.from_file, m.from_rank).name());
+    lan.push(if capture { 'x' } else { '-' });
+    lan.push_str(&(m.to_file, m.to_rank).name());
+
+    if let Some(class) = m.promotion {
+        lan.push('=');
+        lan.push_str(&Piece::new(class, Color::White).to_fen());
+    }
+
+    if let Some(suffix) = check_suffix(board, m)? {
+        lan.push(suffix);
+    }
+
+    Ok(lan)
+}
+
+/// Reads a move in long algebraic notation, accepting the separator and
+/// piece letter as optional, e.g. `Ng1-f3`, `e2e4`, `e7-e8=Q` or `Bb5xc6+`
+pub fn from_lan(board: &Board, lan: &str) -> Result<Move, Error> {
+    let text = lan.trim().trim_end_matches(['+', '#', '!', '?']);
+    let invalid = || Error::from(MoveError::NotLegal(lan.trim().to_string()));
+
+    let (text, promotion) = split_promotion(text).ok_or_else(invalid)?;
+
+    if parse_castling(board, text).is_some() || text.contains('@') {
+        return parse_san(board, text);
+    }
+
+    let (class, squares) = match text.chars().next() {
+        Some('K') => (Some(Class::King), &text[1..]),
+        Some('Q') => (Some(Class::Queen), &text[1..]),
+        Some('R') => (Some(Class::Rook), &text[1..]),
+        Some('B') => (Some(Class::Bishop), &text[1..]),
+        Some('N') => (Some(Class::Knight), &text[1..]),
+        _ => (None, text),
+    };
+
+    let squares: String = squares
+        .chars()
+        .filter(|c| !matches!(c, '-' | 'x' | ':'))
+        .collect();
+    if squares.len() != 4 || !squares.is_ascii() {
+        return Err(invalid());
+    }
+
+    let (from_file, from_rank) = parse_square(&squares[..2]).map_err(|_| invalid())?;
+    let (to_file, to_rank) = parse_square(&squares[2..]).map_err(|_| invalid())?;
+    let m = Move {
+        promotion,
+        ..Move::new(from_file, from_rank, to_file, to_rank)
+    };
+
+    let moved = board.get_piece(from_file, from_rank).map(|p| p.class);
+    if moved.is_none() || (class.is_some() && class != moved) || !board.is_legal(&m) {
+        return Err(invalid());
+    }
+
+    Ok(m)
+}
+
+/// The ICCF digits of the promotion pieces, from 1 for the queen
+const ICCF_PROMOTIONS: [Class; 4] = [Class::Queen, Class::Rook, Class::Bishop, Class::Knight];
+
+/// Writes the move in ICCF numeric notation, files and ranks both numbered
+/// 1 to 8 and a fifth digit for the promotion piece. Castling is written as
+/// the king's move; drops can't be written.
+pub fn to_iccf(m: &Move) -> Result<String, Error> {
+    if m.is_drop() {
+        return Err(Error::InvalidInput);
+    }
+
+    let promotion = match m.promotion {
+        Some(class) => match ICCF_PROMOTIONS.iter().position(|c| *c == class) {
+            Some(i) => (i + 1).to_string(),
+            None => return Err(MoveError::InvalidPromotion(class).into()),
+        },
+        None => String::new(),
+    };
+
+    Ok(format!(
+        "{}{}{}{}{}",
+        m.from_file + 1,
+        m.from_rank + 1,
+        m.to_file + 1,
+        m.to_rank + 1,
+        promotion
+    ))
+}
+
+/// Reads a move in ICCF numeric notation
+pub fn from_iccf(iccf: &str) -> Result<Move, Error> {
+    let iccf = iccf.trim();
+
+    let digits: Vec<usize> = iccf
+        .chars()
+        .map(|c| match c {
+            '1'..='8' => Ok(c as usize - '1' as usize),
+            _ => Err(Error::InvalidInput),
+        })
+        .collect::<Result<_, _>>()?;
+
+    match digits[..] {
+        [from_file, from_rank, to_file, to_rank] => {
+            Ok(Move::new(from_file, from_rank, to_file, to_rank))
+        }
+        // a fifth digit names the promotion piece
+        [from_file, from_rank, to_file, to_rank, promotion] => {
+            let class = ICCF_PROMOTIONS.get(promotion).ok_or(Error::InvalidInput)?;
+            Ok(Move::new(from_file, from_rank, to_file, to_rank).with_promotion(*class))
+        }
+        _ => Err(Error::InvalidInput),
+    }
+}
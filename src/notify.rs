@@ -0,0 +1,58 @@
+//! Letting the player know the opponent has moved, for slow engine games
+//! and correspondence games played while looking at another window
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::chess::Error;
+
+/// What happens when the opponent moves
+pub enum Notifier {
+    /// Rings the terminal bell on standard error, which leaves the output
+    /// of the script runner alone
+    Bell,
+    /// Runs a shell command with the move in coordinate notation in the
+    /// `CHESS_MOVE` environment variable. The command runs in the
+    /// background, so a slow one doesn't hold up the game.
+    Command(String),
+    /// Calls a function with the move in coordinate notation
+    Callback(Box<dyn FnMut(&str) + Send>),
+}
+
+impl Notifier {
+    pub fn notify(&mut self, m: &str) -> Result<(), Error> {
+        match self {
+            Notifier::Bell => {
+                let mut stderr = std::io::stderr();
+                stderr.write_all(b"\x07")?;
+                stderr.flush()?;
+            }
+            Notifier::Command(command) => {
+                let mut child = shell(command)
+                    .env("CHESS_MOVE", m)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                thread::spawn(move || child.wait());
+            }
+            Notifier::Callback(callback) => callback(m),
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds a command run by the platform's shell
+pub(crate) fn shell(command: &str) -> Command {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+
+    let mut shell = Command::new(shell);
+    shell.arg(flag).arg(command);
+    shell
+}
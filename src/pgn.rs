@@ -0,0 +1,553 @@
+use std::{
+    fmt::Display,
+    io::{BufRead, Lines},
+};
+
+use crate::{
+    chess::{Board, Class, Color, Error, MoveError, Piece, Status},
+    fen::ToFen,
+    game::{Game, GameResult, Termination},
+    mover::{parse_square, promotion_class, Move},
+    variant::Variant,
+};
+
+/// Writes the move in Standard Algebraic Notation, e.g. `Nbd2`, `exd5` or
+/// `O-O+`
+pub fn san(board: &Board, m: &Move) -> Result<String, Error> {
+    if !board.is_legal(m) {
+        return Err(MoveError::NotLegal(m.to_string()).into());
+    }
+
+    let file = |file: usize| (b'a' + file as u8) as char;
+    let rank = |rank: usize| (b'1' + rank as u8) as char;
+
+    let mut san = String::new();
+
+    if let Some(class) = m.drop {
+        san.push_str(&Piece::new(class, Color::White).to_fen());
+        san.push('@');
+        san.push(file(m.to_file));
+        san.push(rank(m.to_rank));
+
+        if let Some(suffix) = check_suffix(board, m)? {
+            san.push(suffix);
+        }
+
+        return Ok(san);
+    }
+
+    let piece = board.get_piece(m.from_file, m.from_rank).unwrap();
+
+    if piece.class == Class::King && m.from_file.abs_diff(m.to_file) == 2 {
+        san.push_str(if m.to_file == 6 { "O-O" } else { "O-O-O" });
+    } else {
+        let capture = board.get_piece(m.to_file, m.to_rank).is_some()
+            || (piece.class == Class::Pawn && board.is_en_passant(m.to_file, m.to_rank));
+
+        if piece.class == Class::Pawn {
+            if capture {
+                san.push(file(m.from_file));
+            }
+        } else {
+            san.push_str(&Piece::new(piece.class, Color::White).to_fen());
+
+            // other pieces of the same kind that can reach the same square
+            let others: Vec<Move> = board
+                .legal_moves()
+                .into_iter()
+                .filter(|o| {
+                    (o.to_file, o.to_rank) == (m.to_file, m.to_rank)
+                        && (o.from_file, o.from_rank) != (m.from_file, m.from_rank)
+                        && board
+                            .get_piece(o.from_file, o.from_rank)
+                            .is_some_and(|p| p.class == piece.class)
+                })
+                .collect();
+
+            if !others.is_empty() {
+                if others.iter().all(|o| o.from_file != m.from_file) {
+                    san.push(file(m.from_file));
+                } else if others.iter().all(|o| o.from_rank != m.from_rank) {
+                    san.push(rank(m.from_rank));
+                } else {
+                    san.push(file(m.from_file));
+                    san.push(rank(m.from_rank));
+                }
+            }
+        }
+
+        if capture {
+            san.push('x');
+        }
+
+        san.push(file(m.to_file));
+        san.push(rank(m.to_rank));
+
+        if let Some(class) = m.promotion {
+            san.push('=');
+            san.push_str(&Piece::new(class, Color::White).to_fen());
+        }
+    }
+
+    if let Some(suffix) = check_suffix(board, m)? {
+        san.push(suffix);
+    }
+
+    Ok(san)
+}
+
+/// The `+` or `#` a move earns by giving check or mate
+pub(crate) fn check_suffix(board: &Board, m: &Move) -> Result<Option<char>, Error> {
+    Ok(match board.with_move(m)?.status() {
+        Status::Checkmate => Some('#'),
+        Status::Check => Some('+'),
+        _ => None,
+    })
+}
+
+/// Splits the promotion piece off the end of a move, written as `=Q` or
+/// just `Q` after the destination square. Returns `None` when the piece
+/// after an `=` is not one a pawn can become.
+pub(crate) fn split_promotion(text: &str) -> Option<(&str, Option<Class>)> {
+    if let Some((text, class)) = text.split_once('=') {
+        return Some((text, Some(promotion_class(class)?)));
+    }
+
+    let mut chars = text.chars().rev();
+    match (chars.next(), chars.next()) {
+        (Some(class @ ('N' | 'B' | 'R' | 'Q' | 'K')), Some('1'..='8')) => {
+            Some((&text[..text.len() - 1], promotion_class(&class.to_string())))
+        }
+        _ => Some((text, None)),
+    }
+}
+
+/// A Numeric Annotation Glyph, e.g. `$1` for a good move. The first six
+/// have the symbols `!`, `?`, `!!`, `??`, `!?` and `?!`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Nag(pub u8);
+
+const NAG_SYMBOLS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+impl Nag {
+    pub fn symbol(&self) -> Option<&'static str> {
+        NAG_SYMBOLS.get((self.0 as usize).wrapping_sub(1)).copied()
+    }
+}
+
+impl TryFrom<&str> for Nag {
+    type Error = Error;
+
+    /// Reads a glyph as a symbol such as `!?` or in numeric form such as
+    /// `$5`
+    fn try_from(glyph: &str) -> Result<Nag, Error> {
+        if let Some(number) = glyph.strip_prefix('$') {
+            return number.parse().map(Nag).map_err(|_| Error::InvalidInput);
+        }
+
+        NAG_SYMBOLS
+            .iter()
+            .position(|symbol| *symbol == glyph)
+            .map(|i| Nag(i as u8 + 1))
+            .ok_or(Error::InvalidInput)
+    }
+}
+
+impl Display for Nag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.symbol() {
+            Some(symbol) => write!(f, "{}", symbol),
+            None => write!(f, "${}", self.0),
+        }
+    }
+}
+
+/// A move as written in a PGN file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    /// The first glyph with a symbol goes straight after the move, the
+    /// others follow it as `$` tokens
+    pub nags: Vec<Nag>,
+    pub comment: Option<String>,
+    /// Lines played instead of this move, each starting with its own first
+    /// move
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+/// Returns the ply a position is at, counted from white's first move, so
+/// the moves of a game set up from a FEN carry on with its move numbers
+pub fn start_ply(board: &Board) -> usize {
+    (board.fullmove_number().max(1) - 1) * 2 + usize::from(board.turn() == Color::Black)
+}
+
+/// Formats a game as PGN. The moves start at `ply`, see `start_ply`; the
+/// `Result` tag is always written from `result`.
+pub fn write(tags: &[(&str, &str)], moves: &[PgnMove], ply: usize, result: GameResult) -> String {
+    let mut pgn = String::new();
+
+    for (name, value) in tags.iter().filter(|(name, _)| *name != "Result") {
+        pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+    }
+    pgn.push_str(&format!("[Result \"{}\"]\n\n", result));
+
+    let mut tokens = Vec::new();
+    movetext(moves, ply, &mut tokens);
+    tokens.push(result.to_string());
+
+    // export format keeps lines under 80 characters
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() + 1 > 79 {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+
+    pgn.push_str(&line);
+    pgn.push('\n');
+
+    pgn
+}
+
+/// Adds the tokens of a line starting at `ply`, with its variations in
+/// parentheses after the moves they replace
+fn movetext(moves: &[PgnMove], ply: usize, tokens: &mut Vec<String>) {
+    for (i, m) in moves.iter().enumerate() {
+        let ply = ply + i;
+
+        // black's move is numbered again when a variation comes before it
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        } else if i == 0 || !moves[i - 1].variations.is_empty() {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+
+        let mut san = m.san.clone();
+        let mut numeric = Vec::new();
+        for nag in &m.nags {
+            match nag.symbol() {
+                Some(symbol) if san == m.san => san.push_str(symbol),
+                _ => numeric.push(format!("${}", nag.0)),
+            }
+        }
+        tokens.push(san);
+        tokens.extend(numeric);
+
+        if let Some(comment) = &m.comment {
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+        }
+
+        for variation in &m.variations {
+            let start = tokens.len();
+            movetext(variation, ply, tokens);
+            tokens[start].insert(0, '(');
+            tokens.last_mut().unwrap().push(')');
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reads castling written as `O-O` or `O-O-O`, with zeros or lowercase
+/// letters as well, as the king's move for the side to move. Whether the
+/// king may castle is left to the caller.
+pub(crate) fn parse_castling(board: &Board, text: &str) -> Option<Move> {
+    let text = text.trim().trim_end_matches(['+', '#', '!', '?']);
+    let text = text.to_uppercase().replace('0', "O");
+
+    let (file, rank) = board.king_square(board.turn())?;
+    let to_file = match text.as_str() {
+        "O-O" => file + 2,
+        "O-O-O" => file.checked_sub(2)?,
+        _ => return None,
+    };
+
+    match to_file < 8 {
+        true => Some(Move::new(file, rank, to_file, rank)),
+        false => None,
+    }
+}
+
+/// Reads a move in Standard Algebraic Notation, e.g. `Nbd2`, `exd5`, `O-O`,
+/// `e8=Q` or `N@f3`, ignoring check marks and `!`/`?` suffixes. The `=` of a
+/// promotion may be left out.
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, Error> {
+    let invalid = || Error::from(MoveError::NotLegal(san.to_string()));
+
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+    let (text, promotion) = split_promotion(text).ok_or_else(invalid)?;
+
+    if text.contains('@') {
+        let m = Move::try_from(text)?;
+        return match board.is_legal(&m) {
+            true => Ok(m),
+            false => Err(invalid()),
+        };
+    }
+
+    if let Some(m) = parse_castling(board, text) {
+        return match board.is_legal(&m) {
+            true => Ok(m),
+            false => Err(invalid()),
+        };
+    }
+
+    let (class, rest) = match text.chars().next() {
+        Some('N') => (Class::Knight, &text[1..]),
+        Some('B') => (Class::Bishop, &text[1..]),
+        Some('R') => (Class::Rook, &text[1..]),
+        Some('Q') => (Class::Queen, &text[1..]),
+        Some('K') => (Class::King, &text[1..]),
+        _ => (Class::Pawn, text),
+    };
+
+    let rest = rest.replace('x', "");
+    if rest.len() < 2 || !rest.is_ascii() {
+        return Err(invalid());
+    }
+
+    let (from, to) = rest.split_at(rest.len() - 2);
+    let (to_file, to_rank) = parse_square(to).map_err(|_| invalid())?;
+
+    // whatever is left is a file, a rank or both telling the pieces apart
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in from.chars() {
+        match c {
+            'a'..='h' => from_file = Some((c as u8 - b'a') as usize),
+            '1'..='8' => from_rank = Some((c as u8 - b'1') as usize),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let candidates: Vec<Move> = board
+        .legal_moves()
+        .into_iter()
+        .filter(|m| {
+            !m.is_drop()
+                && (m.to_file, m.to_rank) == (to_file, to_rank)
+                && from_file.is_none_or(|f| f == m.from_file)
+                && from_rank.is_none_or(|r| r == m.from_rank)
+                && board
+                    .get_piece(m.from_file, m.from_rank)
+                    .is_some_and(|p| p.class == class)
+        })
+        .collect();
+
+    if promotion.is_none() && candidates.iter().any(|m| m.promotion.is_some()) {
+        return Err(MoveError::PromotionRequired.into());
+    }
+
+    let candidates: Vec<Move> = candidates
+        .into_iter()
+        .filter(|m| m.promotion == promotion)
+        .collect();
+
+    match candidates.as_slice() {
+        [m] => Ok(*m),
+        [] => Err(invalid()),
+        _ => Err(MoveError::Ambiguous(san.to_string()).into()),
+    }
+}
+
+/// A PGN tag pair, name then value
+type Tag = (String, String);
+
+/// Reads games one at a time from PGN text, so large databases can be
+/// filtered without loading them into memory. Every game is replayed on a
+/// board, starting from its `FEN` tag when there is one.
+pub struct PgnReader<R: BufRead> {
+    lines: Lines<R>,
+    /// A tag line already read that belongs to the next game
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            pending: None,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, Error>> {
+        match self.pending.take() {
+            Some(line) => Some(Ok(line)),
+            None => self.lines.next().map(|line| line.map_err(Error::from)),
+        }
+    }
+
+    /// Collects the tag pairs and the movetext of the next game
+    fn read_game(&mut self) -> Option<Result<(Vec<Tag>, String), Error>> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+
+        while let Some(line) = self.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let line = line.trim();
+
+            if line.starts_with('[') {
+                // a tag after the movetext starts the next game
+                if !movetext.is_empty() {
+                    self.pending = Some(line.to_string());
+                    break;
+                }
+
+                match parse_tag(line) {
+                    Some(tag) => tags.push(tag),
+                    None => return Some(Err(Error::InvalidInput)),
+                }
+            } else if line.is_empty() {
+                if !movetext.is_empty() {
+                    break;
+                }
+            } else if !line.starts_with('%') {
+                // the line ends stay, as they end `;` comments
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        if tags.is_empty() && movetext.is_empty() {
+            return None;
+        }
+
+        Some(Ok((tags, movetext)))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, Error>;
+
+    fn next(&mut self) -> Option<Result<Game, Error>> {
+        let (tags, movetext) = match self.read_game()? {
+            Ok(game) => game,
+            Err(e) => return Some(Err(e)),
+        };
+
+        Some(replay(tags, &movetext))
+    }
+}
+
+/// Parses a tag pair such as `[Event "Casual game"]`
+fn parse_tag(line: &str) -> Option<Tag> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+/// Plays the movetext of a game on a fresh board
+fn replay(tags: Vec<Tag>, movetext: &str) -> Result<Game, Error> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut board = Board::new()?;
+    if let Some(variant) = tag("Variant") {
+        board.set_variant(Variant::try_from(variant)?);
+    }
+    board.from_fen(tag("FEN").unwrap_or(board.variant().start_position()))?;
+
+    let mut game = Game::from_board(board);
+    game.set_players(
+        tag("White").unwrap_or("White"),
+        tag("Black").unwrap_or("Black"),
+    );
+
+    let mut result = tag("Result").and_then(|r| GameResult::try_from(r).ok());
+
+    let mut chars = movetext.chars().peekable();
+    let mut depth = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                if depth == 0 && !game.history().is_empty() {
+                    game.annotate(comment.trim().replace('\n', " ").as_str())?;
+                }
+            }
+            // a semicolon outside braces comments out the rest of the line
+            ';' => chars.by_ref().take_while(|c| *c != '\n').for_each(drop),
+            // variations are skipped, only the main line is played
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}()".contains(*c)) {
+                    token.push(c);
+                }
+
+                if depth > 0 {
+                    continue;
+                }
+
+                // a glyph on its own, e.g. `$1`, goes with the move before
+                if let Ok(nag) = Nag::try_from(token.as_str()) {
+                    if !game.history().is_empty() {
+                        game.add_nag(nag)?;
+                    }
+                    continue;
+                }
+
+                if let Ok(r) = GameResult::try_from(token.as_str()) {
+                    result = Some(r);
+                    continue;
+                }
+
+                // move numbers may be glued to the move, as in `1.e4`
+                let san = match token.rfind('.') {
+                    Some(i) => &token[i + 1..],
+                    None => &token,
+                };
+                if san.is_empty() {
+                    continue;
+                }
+
+                let m = parse_san(game.board(), san)?;
+                game.move_piece(&m.to_string())?;
+
+                let glyph = san.trim_end_matches(['!', '?']);
+                if let Ok(nag) = Nag::try_from(&san[glyph.len()..]) {
+                    game.add_nag(nag)?;
+                }
+            }
+        }
+    }
+
+    if let Some(result) = result.filter(|r| *r != GameResult::Ongoing) {
+        // `normal` doesn't say which rule ended the game, the details do
+        let termination = tag("TerminationDetails")
+            .or(tag("Termination"))
+            .and_then(|t| Termination::try_from(t).ok())
+            .or(game.termination());
+        game.set_result(result, termination);
+    }
+
+    for (name, value) in &tags {
+        game.set_tag(name, value);
+    }
+
+    Ok(game)
+}
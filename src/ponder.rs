@@ -0,0 +1,78 @@
+//! Pondering: the engine searches the reply it expects while the opponent
+//! is still thinking, so it can answer right away when it guessed right
+
+use std::thread::{self, JoinHandle};
+
+use crate::{
+    chess::{Board, Error},
+    engine::{Engine, Evaluation, Limits, StopToken},
+    fen::ToFen,
+    mover::Move,
+};
+
+/// A search of the position after the expected reply, running on a thread
+/// of its own. It is stopped when dropped.
+pub struct Ponder {
+    expected: Move,
+    /// The position the search is for, to tell a ponder hit
+    position: String,
+    stop: StopToken,
+    handle: Option<JoinHandle<Evaluation>>,
+}
+
+impl Ponder {
+    /// Starts searching the position after `expected` is played on `board`
+    pub fn start(
+        engine: Engine,
+        board: &Board,
+        expected: Move,
+        limits: Limits,
+    ) -> Result<Ponder, Error> {
+        let next = board.with_move(&expected)?;
+        let position = next.to_fen();
+        let stop = StopToken::new();
+
+        let token = stop.clone();
+        let handle = thread::spawn(move || engine.search(&next, &limits, &token));
+
+        Ok(Ponder {
+            expected,
+            position,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the move the search expects to be played
+    pub fn expected(&self) -> Move {
+        self.expected
+    }
+
+    /// Whether the search is for this position
+    pub fn is_hit(&self, board: &Board) -> bool {
+        board.to_fen() == self.position
+    }
+
+    /// Waits for the search to finish within its limits and returns it
+    pub fn finish(mut self) -> Option<Evaluation> {
+        self.handle.take()?.join().ok()
+    }
+
+    /// Stops the search, for when the opponent played something else
+    pub fn cancel(mut self) {
+        self.halt();
+    }
+
+    fn halt(&mut self) {
+        self.stop.stop();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
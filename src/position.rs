@@ -0,0 +1,55 @@
+//! Positions as keys: what the rules of repetition compare, without the
+//! clocks, the move history or how often each piece moved
+
+use crate::{
+    chess::{Board, Class, Color},
+    variant::{Pocket, Variant},
+};
+
+/// The part of a board that makes two positions the same: the pieces, the
+/// side to move, the castling rights, an en passant capture that can be
+/// played, and the pockets. Two positions are equal exactly when they count
+/// as a repetition, so they can be used as keys of a `HashMap`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Indexed by file, then rank
+    pub pieces: [[Option<(Class, Color)>; 8]; 8],
+    pub turn: Color,
+    /// White kingside, white queenside, black kingside and black queenside
+    pub castling: [bool; 4],
+    pub en_passant: Option<(usize, usize)>,
+    pub variant: Variant,
+    pub pockets: [Pocket; 2],
+}
+
+impl From<&Board> for Position {
+    fn from(board: &Board) -> Position {
+        let mut pieces = [[None; 8]; 8];
+        for (file, column) in pieces.iter_mut().enumerate() {
+            for (rank, square) in column.iter_mut().enumerate() {
+                *square = board.get_piece(file, rank).map(|p| (p.class, p.color));
+            }
+        }
+
+        Position {
+            pieces,
+            turn: board.turn(),
+            castling: [
+                board.can_castle_kingside(Color::White),
+                board.can_castle_queenside(Color::White),
+                board.can_castle_kingside(Color::Black),
+                board.can_castle_queenside(Color::Black),
+            ],
+            en_passant: board.capturable_en_passant(),
+            variant: board.variant(),
+            pockets: [*board.pocket(Color::White), *board.pocket(Color::Black)],
+        }
+    }
+}
+
+impl Board {
+    /// Returns the position on the board, for comparing and hashing
+    pub fn position(&self) -> Position {
+        Position::from(self)
+    }
+}
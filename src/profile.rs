@@ -0,0 +1,222 @@
+//! The player's profile: name, preferred color, and a rating that follows
+//! the rated games against the engine, kept in the config directory along
+//! with the games themselves
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    chess::{Color, Error},
+    config::config_dir,
+    engine::MAX_SKILL,
+    game::{Game, GameResult},
+};
+
+/// The rating of a new profile
+pub const INITIAL_RATING: i32 = 1200;
+
+/// How far a single game can move the rating
+const K_FACTOR: f64 = 32.0;
+
+/// A rough rating for the built-in engine at a skill level, from 800 at the
+/// weakest to 2400 at full strength
+pub fn engine_rating(skill: u8) -> i32 {
+    let skill = skill.clamp(1, MAX_SKILL) as i32;
+    800 + (skill - 1) * 1600 / (MAX_SKILL as i32 - 1)
+}
+
+/// Returns what a player is expected to score against an opponent, from 0
+/// to 1
+fn expected_score(rating: i32, opponent: i32) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) as f64 / 400.0))
+}
+
+/// A rated game in the profile's history
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatedGame {
+    /// The color the player had
+    pub color: Color,
+    pub result: GameResult,
+    pub opponent_rating: i32,
+    /// The player's rating after the game
+    pub rating: i32,
+    /// Where the game was saved
+    pub file: PathBuf,
+}
+
+impl RatedGame {
+    /// Whether the player won, lost or drew, as 1, 0 or ½
+    pub fn score(&self) -> f64 {
+        match self.result {
+            GameResult::Draw => 0.5,
+            result if result == GameResult::win(self.color) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// The color the player likes to play, for rated games that don't say
+    pub color: Option<Color>,
+    pub rating: i32,
+    pub games: Vec<RatedGame>,
+    /// Where the profile is kept, for one opened from a file
+    path: Option<PathBuf>,
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile {
+            name: "Player".to_string(),
+            color: None,
+            rating: INITIAL_RATING,
+            games: Vec::new(),
+            path: None,
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Result<Color, Error> {
+    match name {
+        "white" => Ok(Color::White),
+        "black" => Ok(Color::Black),
+        _ => Err(Error::InvalidInput),
+    }
+}
+
+impl Profile {
+    /// Opens the profile in the config directory, or a new one when there
+    /// is none yet
+    pub fn open() -> Result<Profile, Error> {
+        let dir = config_dir().ok_or(Error::InvalidInput)?;
+        Profile::load(&dir.join("profile"))
+    }
+
+    /// Reads a profile from a file, or starts a new one to be saved there
+    /// when the file doesn't exist
+    pub fn load(path: &Path) -> Result<Profile, Error> {
+        let mut profile = match path.exists() {
+            true => Profile::from_config(&std::fs::read_to_string(path)?)?,
+            false => Profile::default(),
+        };
+        profile.path = Some(path.to_path_buf());
+
+        Ok(profile)
+    }
+
+    /// Reads a profile written by `to_config`
+    pub fn from_config(config: &str) -> Result<Profile, Error> {
+        let mut profile = Profile::default();
+        let number = |value: &str| value.parse::<i32>().map_err(|_| Error::InvalidInput);
+
+        for line in config.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "name" => profile.name = value.to_string(),
+                "color" => profile.color = Some(parse_color(value)?),
+                "rating" => profile.rating = number(value)?,
+                "game" => {
+                    let fields: Vec<&str> = value.splitn(5, ' ').collect();
+                    let [color, result, opponent_rating, rating, file] = fields[..] else {
+                        return Err(Error::InvalidInput);
+                    };
+
+                    profile.games.push(RatedGame {
+                        color: parse_color(color)?,
+                        result: GameResult::try_from(result)?,
+                        opponent_rating: number(opponent_rating)?,
+                        rating: number(rating)?,
+                        file: PathBuf::from(file),
+                    });
+                }
+                _ => return Err(Error::InvalidInput),
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Writes the profile one setting per line, then a line per rated game
+    pub fn to_config(&self) -> String {
+        let mut config = format!("name {}\n", self.name);
+        if let Some(color) = self.color {
+            config.push_str(&format!("color {}\n", color));
+        }
+        config.push_str(&format!("rating {}\n", self.rating));
+
+        for game in &self.games {
+            config.push_str(&format!(
+                "game {} {} {} {} {}\n",
+                game.color,
+                game.result,
+                game.opponent_rating,
+                game.rating,
+                game.file.display()
+            ));
+        }
+
+        config
+    }
+
+    /// Writes the profile back to its file. Does nothing for a profile not
+    /// opened from one.
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, self.to_config())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of games won, drawn and lost
+    pub fn record(&self) -> (usize, usize, usize) {
+        let count = |score: f64| self.games.iter().filter(|g| g.score() == score).count();
+        (count(1.0), count(0.5), count(0.0))
+    }
+
+    /// Rates a finished game the player played as `color`, saves it next to
+    /// the profile and adds it to the history. Returns the rating change.
+    pub fn rate(&mut self, game: &Game, color: Color, opponent_rating: i32) -> Result<i32, Error> {
+        if !game.is_over() {
+            return Err(Error::InvalidInput);
+        }
+
+        let n = self.games.len() + 1;
+        let file = match &self.path {
+            Some(path) => path.with_file_name("games").join(format!("{}.pgn", n)),
+            None => PathBuf::from(format!("{}.pgn", n)),
+        };
+
+        let mut rated = RatedGame {
+            color,
+            result: game.result(),
+            opponent_rating,
+            rating: self.rating,
+            file,
+        };
+
+        let change = K_FACTOR * (rated.score() - expected_score(self.rating, opponent_rating));
+        let change = change.round() as i32;
+        rated.rating += change;
+        self.rating = rated.rating;
+
+        if self.path.is_some() {
+            if let Some(dir) = rated.file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&rated.file, game.to_pgn()?)?;
+        }
+
+        self.games.push(rated);
+        self.save()?;
+
+        Ok(change)
+    }
+}
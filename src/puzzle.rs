@@ -0,0 +1,293 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::{
+    chess::{Board, Error, MoveError},
+    engine::Engine,
+    epd::{parse_move, Epd},
+    fen::ToFen,
+    mover::Move,
+};
+
+/// A position with the line that solves it. The solution alternates between
+/// the solver's moves and the opponent's replies, starting with the solver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub id: Option<String>,
+    pub fen: String,
+    pub solution: Vec<Move>,
+    /// Other first moves that solve the puzzle, such as the further moves
+    /// of an EPD `bm`
+    pub alternates: Vec<Move>,
+}
+
+impl Puzzle {
+    /// Parses the simple puzzle format: a FEN, a `;` and the solution as
+    /// space separated moves in SAN or coordinate notation, e.g.
+    /// `<fen>; d1h5 g6h5`
+    pub fn parse(line: &str) -> Result<Puzzle, Error> {
+        let (fen, moves) = match line.split_once(';') {
+            Some(parts) => parts,
+            None => return Err(Error::InvalidInput),
+        };
+        let fen = fen.trim().to_string();
+
+        let puzzle = Puzzle {
+            id: None,
+            solution: parse_line(&Board::try_from_fen(&fen)?, moves.split_whitespace())?,
+            fen,
+            alternates: Vec::new(),
+        };
+
+        puzzle.validate()?;
+
+        Ok(puzzle)
+    }
+
+    /// Builds a puzzle from an EPD record, taking the solution from the `pv`
+    /// opcode or, failing that, the first move of `bm`. Any other `bm` moves
+    /// solve it as well. Moves may be in SAN or coordinate notation.
+    pub fn from_epd(epd: &Epd) -> Result<Puzzle, Error> {
+        let board = epd.board()?;
+        let mut best = epd.moves("bm")?;
+
+        let solution = match epd.get("pv") {
+            Some(pv) => parse_line(&board, pv.operands.iter().map(|m| m.as_str()))?,
+            None if best.is_empty() => Vec::new(),
+            None => vec![best.remove(0)],
+        };
+        best.retain(|m| solution.first() != Some(m));
+
+        let puzzle = Puzzle {
+            id: epd.id().map(|id| id.to_string()),
+            fen: board.to_fen(),
+            solution,
+            alternates: best,
+        };
+
+        puzzle.validate()?;
+
+        Ok(puzzle)
+    }
+
+    pub fn board(&self) -> Result<Board, Error> {
+        let board = Board::try_from_fen(&self.fen)?;
+        Ok(board)
+    }
+
+    /// Makes sure every move of the solution can be played
+    fn validate(&self) -> Result<(), Error> {
+        if self.solution.is_empty() {
+            return Err(Error::InvalidInput);
+        }
+
+        let mut board = self.board()?;
+        for m in self.solution.iter() {
+            board.move_piece(&m.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a line of moves, each from the position the ones before lead to
+fn parse_line<'a>(board: &Board, moves: impl Iterator<Item = &'a str>) -> Result<Vec<Move>, Error> {
+    let mut board = board.clone();
+    let mut line = Vec::new();
+
+    for m in moves {
+        let m = parse_move(&board, m)?;
+        board.move_piece(&m.to_string())?;
+        line.push(m);
+    }
+
+    Ok(line)
+}
+
+/// Reads puzzles from a file, one per line, in either the simple format or
+/// as EPD records
+pub fn load(filename: &str) -> Result<Vec<Puzzle>, Error> {
+    let file = File::open(filename)?;
+    let reader = BufReader::new(file);
+
+    let mut puzzles = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let puzzle = match Puzzle::parse(line) {
+            Ok(puzzle) => puzzle,
+            Err(_) => Puzzle::from_epd(&Epd::parse(line)?)?,
+        };
+
+        puzzles.push(puzzle);
+    }
+
+    Ok(puzzles)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Attempt {
+    /// The move was right and the opponent replied with the given move
+    Correct(Move),
+    /// The move was right and completes the solution
+    Solved,
+    /// The move was not the solution, the puzzle is failed
+    Failed,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Score {
+    pub solved: usize,
+    pub failed: usize,
+}
+
+/// Tracks a solver working through a single puzzle
+pub struct Session {
+    puzzle: Puzzle,
+    board: Board,
+    /// The line being followed: the solution, or where the solver found
+    /// another way, the engine's best play after it
+    line: Vec<Move>,
+    ply: usize,
+    finished: bool,
+    /// The engine checking moves besides the solution, and the centipawns
+    /// they may score below it
+    verifier: Option<(Engine, i32)>,
+}
+
+impl Session {
+    pub fn new(puzzle: Puzzle) -> Result<Session, Error> {
+        let board = puzzle.board()?;
+
+        Ok(Session {
+            line: puzzle.solution.clone(),
+            puzzle,
+            board,
+            ply: 0,
+            finished: false,
+            verifier: None,
+        })
+    }
+
+    /// Lets the engine accept moves other than the solution's that it
+    /// scores at most `margin` centipawns below it
+    pub fn verify_with(&mut self, engine: Engine, margin: i32) {
+        self.verifier = Some((engine, margin));
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Checks the solver's move against the solution, playing it and the
+    /// opponent's reply when it is correct
+    pub fn try_move(&mut self, data: &str) -> Result<Attempt, Error> {
+        if self.finished {
+            return Err(Error::GameOver);
+        }
+
+        let data = data.trim();
+        let m = parse_move(&self.board, data)?;
+
+        // reject illegal input without counting it as a wrong answer
+        if !self.board.is_legal(&m) {
+            m.validate(&self.board)?;
+            return Err(MoveError::NotLegal(data.to_string()).into());
+        }
+
+        if m != self.line[self.ply] && !self.accept_alternate(m)? {
+            self.finished = true;
+            return Ok(Attempt::Failed);
+        }
+
+        self.board.move_piece(&m.to_string())?;
+        self.ply += 1;
+
+        let reply = match self.line.get(self.ply) {
+            Some(reply) => *reply,
+            None => {
+                self.finished = true;
+                return Ok(Attempt::Solved);
+            }
+        };
+
+        self.board.move_piece(&reply.to_string())?;
+        self.ply += 1;
+
+        if self.ply == self.line.len() {
+            self.finished = true;
+            return Ok(Attempt::Solved);
+        }
+
+        Ok(Attempt::Correct(reply))
+    }
+
+    /// Checks a move besides the one of the line: a listed alternate of the
+    /// first move, or one the engine scores within the margin of the line's
+    /// move. Once accepted the line goes on with the engine's best play
+    /// after it, or ends with the move when there is no engine.
+    fn accept_alternate(&mut self, m: Move) -> Result<bool, Error> {
+        let listed = self.ply == 0 && self.puzzle.alternates.contains(&m);
+
+        let continuation = match self.verifier {
+            Some((engine, margin)) => {
+                let color = self.board.turn();
+                // mates found in more moves still solve the puzzle
+                let clamp = |score: i32| score.clamp(-10_000, 10_000);
+
+                let expected = engine.evaluate(&self.board.with_move(&self.line[self.ply])?);
+                let after = engine.evaluate(&self.board.with_move(&m)?);
+                if !listed
+                    && clamp(after.score_for(color)) < clamp(expected.score_for(color)) - margin
+                {
+                    return Ok(false);
+                }
+
+                after.line
+            }
+            None if listed => Vec::new(),
+            None => return Ok(false),
+        };
+
+        let remaining = self.line.len() - self.ply;
+        self.line.truncate(self.ply);
+        self.line.push(m);
+        self.line
+            .extend(continuation.into_iter().take(remaining - 1));
+
+        Ok(true)
+    }
+}
+
+impl Score {
+    pub fn record(&mut self, attempt: Attempt) {
+        match attempt {
+            Attempt::Solved => self.solved += 1,
+            Attempt::Failed => self.failed += 1,
+            Attempt::Correct(_) => {}
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} solved, {} failed", self.solved, self.failed)
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::{
+    chess::Error,
+    eco::EcoCode,
+    fen::ToFen,
+    game::{Game, GameResult},
+    pgn::PgnReader,
+};
+
+/// Criteria for picking games out of a PGN database. Every criterion that is
+/// set has to match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// Part of the name of either player, ignoring case
+    pub player: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub eco: Option<EcoCode>,
+    pub result: Option<GameResult>,
+    /// A position in FEN the game has to pass through. The move clocks are
+    /// not compared.
+    pub fen: Option<String>,
+}
+
+impl Query {
+    /// Builds a query from command line style arguments, e.g.
+    /// `--player Carlsen --eco B90 --result 1-0`
+    pub fn from_args(args: &[&str]) -> Result<Query, Error> {
+        let mut query = Query::default();
+        let mut args = args.iter();
+
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or(Error::InvalidInput)?.to_string();
+
+            match *flag {
+                "--player" => query.player = Some(value),
+                "--white" => query.white = Some(value),
+                "--black" => query.black = Some(value),
+                "--eco" => query.eco = Some(EcoCode::try_from(value.as_str())?),
+                "--result" => query.result = Some(GameResult::try_from(value.as_str())?),
+                "--fen" => query.fen = Some(value),
+                _ => return Err(Error::InvalidInput),
+            }
+        }
+
+        Ok(query)
+    }
+
+    pub fn matches(&self, game: &Game) -> bool {
+        let named = |name: &str, part: &Option<String>| {
+            part.as_ref()
+                .is_none_or(|part| name.to_lowercase().contains(&part.to_lowercase()))
+        };
+
+        if !named(game.white(), &self.white) || !named(game.black(), &self.black) {
+            return false;
+        }
+
+        if self.player.is_some()
+            && !named(game.white(), &self.player)
+            && !named(game.black(), &self.player)
+        {
+            return false;
+        }
+
+        if self.result.is_some_and(|result| result != game.result()) {
+            return false;
+        }
+
+        if let Some(eco) = self.eco {
+            // the ECO tag wins over classifying the moves ourselves
+            let code = match game.tag("ECO") {
+                Some(tag) => EcoCode::try_from(tag).ok(),
+                None => game.opening().map(|(code, _)| code),
+            };
+
+            if code != Some(eco) {
+                return false;
+            }
+        }
+
+        if let Some(fen) = &self.fen {
+            return reaches(game, fen);
+        }
+
+        true
+    }
+}
+
+/// The part of a FEN that identifies a position, leaving out the clocks
+fn position(fen: &str) -> Vec<&str> {
+    fen.split_whitespace().take(4).collect()
+}
+
+fn reaches(game: &Game, fen: &str) -> bool {
+    let wanted = position(fen);
+
+    game.positions().is_ok_and(|positions| {
+        positions
+            .iter()
+            .any(|board| position(&board.to_fen()) == wanted)
+    })
+}
+
+/// Lazily yields the games in the PGN text that match the query. Games that
+/// fail to parse are passed on as errors.
+pub fn search<'a, R: BufRead + 'a>(
+    reader: R,
+    query: &'a Query,
+) -> impl Iterator<Item = Result<Game, Error>> + 'a {
+    PgnReader::new(reader).filter(|game| match game {
+        Ok(game) => query.matches(game),
+        Err(_) => true,
+    })
+}
+
+/// Returns the matching games of a PGN file, skipping games that fail to
+/// parse
+pub fn search_file(filename: &str, query: &Query) -> Result<Vec<Game>, Error> {
+    let reader = BufReader::new(File::open(filename)?);
+    let mut games = Vec::new();
+
+    for game in search(reader, query) {
+        match game {
+            Ok(game) => games.push(game),
+            // reading the file itself failed, so there is nothing more to find
+            Err(e @ Error::Io(_)) => return Err(e),
+            Err(_) => {}
+        }
+    }
+
+    Ok(games)
+}
@@ -0,0 +1,308 @@
+//! Opening repertoire training: the opponent's moves are played from a PGN of
+//! prepared lines and the replies are checked against it. Lines played
+//! without a mistake often enough in a row count as mastered, which is kept
+//! in a progress file next to the PGN between sessions.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    chess::{Board, Color, Error, DEFAULT_BOARD},
+    mover::Move,
+    pgn::PgnReader,
+};
+
+/// Runs in a row without a mistake for a line to count as mastered
+pub const MASTERED: u32 = 3;
+
+/// A prepared line and how well it is known
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub moves: Vec<Move>,
+    /// Runs without a mistake since the last one with a mistake
+    pub streak: u32,
+    pub runs: u32,
+}
+
+impl Line {
+    pub fn is_mastered(&self) -> bool {
+        self.streak >= MASTERED
+    }
+
+    /// Writes the moves in coordinate notation, which names the line in the
+    /// progress file
+    fn key(&self) -> String {
+        let moves: Vec<String> = self.moves.iter().map(|m| m.to_string()).collect();
+        moves.join(" ")
+    }
+}
+
+/// The prepared lines of one color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repertoire {
+    color: Color,
+    lines: Vec<Line>,
+    /// Where progress is kept, for a repertoire loaded from a file
+    progress: Option<String>,
+}
+
+impl Repertoire {
+    /// Reads a PGN holding one line per game. Games that don't start from
+    /// the standard position or leave nothing for `color` to play are
+    /// skipped, as are games that fail to parse.
+    pub fn from_pgn<R: BufRead>(reader: R, color: Color) -> Result<Repertoire, Error> {
+        let mut lines: Vec<Line> = Vec::new();
+
+        for game in PgnReader::new(reader) {
+            let game = match game {
+                Ok(game) => game,
+                Err(e @ Error::Io(_)) => return Err(e),
+                Err(_) => continue,
+            };
+
+            let moves: Vec<Move> = game.history().iter().map(|entry| entry.m).collect();
+            let first = match color {
+                Color::White => 0,
+                Color::Black => 1,
+            };
+
+            if game.start_fen() != DEFAULT_BOARD || moves.len() <= first {
+                continue;
+            }
+            if lines.iter().any(|line| line.moves == moves) {
+                continue;
+            }
+
+            lines.push(Line {
+                moves,
+                streak: 0,
+                runs: 0,
+            });
+        }
+
+        if lines.is_empty() {
+            return Err(Error::InvalidInput);
+        }
+
+        Ok(Repertoire {
+            color,
+            lines,
+            progress: None,
+        })
+    }
+
+    /// Reads a PGN file along with the progress kept for it in
+    /// `<filename>.progress`, when there is any
+    pub fn load(filename: &str, color: Color) -> Result<Repertoire, Error> {
+        let file = File::open(filename)?;
+        let mut repertoire = Repertoire::from_pgn(BufReader::new(file), color)?;
+
+        let progress = format!("{}.progress", filename);
+        if Path::new(&progress).exists() {
+            repertoire.restore(&std::fs::read_to_string(&progress)?)?;
+        }
+        repertoire.progress = Some(progress);
+
+        Ok(repertoire)
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Returns how many of the lines are mastered
+    pub fn mastered(&self) -> usize {
+        self.lines.iter().filter(|line| line.is_mastered()).count()
+    }
+
+    /// Writes the progress on every line that has been played, one per row:
+    /// the moves, a colon, then the streak and the number of runs
+    pub fn to_progress(&self) -> String {
+        let mut out = String::new();
+
+        for line in self.lines.iter().filter(|line| line.runs > 0) {
+            out.push_str(&format!("{}: {} {}\n", line.key(), line.streak, line.runs));
+        }
+
+        out
+    }
+
+    /// Reads progress written by `to_progress`. Rows for lines that are no
+    /// longer in the repertoire are ignored.
+    pub fn restore(&mut self, progress: &str) -> Result<(), Error> {
+        for row in progress
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+        {
+            let (key, counts) = row.split_once(':').ok_or(Error::InvalidInput)?;
+            let counts: Vec<&str> = counts.split_whitespace().collect();
+            let [streak, runs] = counts[..] else {
+                return Err(Error::InvalidInput);
+            };
+
+            if let Some(line) = self.lines.iter_mut().find(|line| line.key() == key.trim()) {
+                line.streak = streak.parse().map_err(|_| Error::InvalidInput)?;
+                line.runs = runs.parse().map_err(|_| Error::InvalidInput)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the progress back to the file it was loaded from. Does
+    /// nothing for a repertoire read from elsewhere.
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(filename) = &self.progress {
+            std::fs::write(filename, self.to_progress())?;
+        }
+        Ok(())
+    }
+
+    /// Picks the line to play next: the least known, the one played the
+    /// fewest times among those
+    fn weakest(&self, lines: impl Iterator<Item = usize>) -> Option<usize> {
+        lines.min_by_key(|&i| (self.lines[i].streak, self.lines[i].runs))
+    }
+}
+
+/// What came of a move played in training
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attempt {
+    /// The prepared move, when another one was played. The prepared move is
+    /// played in its place.
+    pub expected: Option<Move>,
+    /// The opponent's reply, when the line goes on
+    pub reply: Option<Move>,
+    /// Whether the move ended the line
+    pub finished: bool,
+}
+
+/// Runs through the lines of a repertoire one after another, playing the
+/// opponent's side
+pub struct Trainer {
+    repertoire: Repertoire,
+    line: usize,
+    board: Board,
+    ply: usize,
+    mistakes: usize,
+}
+
+impl Trainer {
+    /// Starts training on the weakest line
+    pub fn new(repertoire: Repertoire) -> Result<Trainer, Error> {
+        let mut trainer = Trainer {
+            repertoire,
+            line: 0,
+            board: Board::default_board()?,
+            ply: 0,
+            mistakes: 0,
+        };
+        trainer.next_line()?;
+
+        Ok(trainer)
+    }
+
+    pub fn repertoire(&self) -> &Repertoire {
+        &self.repertoire
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the line being played
+    pub fn line(&self) -> &Line {
+        &self.repertoire.lines[self.line]
+    }
+
+    /// Moves on to the weakest line and plays the opponent's moves up to
+    /// the first one to answer, which are returned
+    pub fn next_line(&mut self) -> Result<Vec<Move>, Error> {
+        let all = 0..self.repertoire.lines.len();
+        self.line = self.repertoire.weakest(all).ok_or(Error::InvalidInput)?;
+        self.board = Board::default_board()?;
+        self.ply = 0;
+        self.mistakes = 0;
+
+        let mut played = Vec::new();
+        if self.repertoire.color == Color::Black {
+            let first = self.line().moves[0];
+            self.advance(first)?;
+            played.push(first);
+        }
+
+        Ok(played)
+    }
+
+    /// Checks a move against the prepared replies, plays it or the prepared
+    /// move in its place, then the opponent's reply. A prepared move of
+    /// another line through the same position counts as correct and
+    /// switches to that line.
+    pub fn play(&mut self, m: Move) -> Result<Attempt, Error> {
+        if self.ply >= self.line().moves.len() {
+            return Err(Error::GameOver);
+        }
+
+        // reject illegal input without counting it as a mistake
+        m.validate(&self.board)?;
+
+        let played = &self.line().moves[..self.ply];
+        let ply = self.ply;
+        let continuing = (0..self.repertoire.lines.len()).filter(|&i| {
+            let moves = &self.repertoire.lines[i].moves;
+            moves.len() > ply && moves[..ply] == *played && moves[ply] == m
+        });
+
+        let expected = match self.line().moves[self.ply] == m {
+            true => None,
+            false => match self.repertoire.weakest(continuing) {
+                Some(line) => {
+                    self.line = line;
+                    None
+                }
+                None => {
+                    self.mistakes += 1;
+                    Some(self.line().moves[self.ply])
+                }
+            },
+        };
+
+        self.advance(expected.unwrap_or(m))?;
+
+        let reply = self.line().moves.get(self.ply).copied();
+        if let Some(reply) = reply {
+            self.advance(reply)?;
+        }
+
+        let finished = self.ply >= self.line().moves.len();
+        if finished {
+            let clean = self.mistakes == 0;
+            let line = &mut self.repertoire.lines[self.line];
+            line.runs += 1;
+            line.streak = match clean {
+                true => line.streak + 1,
+                false => 0,
+            };
+        }
+
+        Ok(Attempt {
+            expected,
+            reply,
+            finished,
+        })
+    }
+
+    fn advance(&mut self, m: Move) -> Result<(), Error> {
+        self.board.move_piece(&m.to_string())?;
+        self.ply += 1;
+        Ok(())
+    }
+}
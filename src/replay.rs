@@ -0,0 +1,113 @@
+use crate::{
+    chess::{Board, Error},
+    command::{Command, Registry},
+    game::Game,
+};
+
+/// Returns the commands understood by `Replay::command`
+pub fn commands() -> Registry {
+    let mut registry = Registry::new();
+
+    registry.register(Command {
+        name: "next",
+        aliases: &["n"],
+        args: &[],
+        help: "Play the next move",
+    });
+    registry.register(Command {
+        name: "prev",
+        aliases: &["p"],
+        args: &[],
+        help: "Take back the last move",
+    });
+    registry.register(Command {
+        name: "goto",
+        aliases: &[],
+        args: &["<ply>"],
+        help: "Show the position after the given number of moves",
+    });
+    registry.register(Command {
+        name: "start",
+        aliases: &[],
+        args: &[],
+        help: "Go back to the first position",
+    });
+    registry.register(Command {
+        name: "end",
+        aliases: &[],
+        args: &[],
+        help: "Go to the last position",
+    });
+
+    registry
+}
+
+/// Steps through a game move by move, starting from its first position
+pub struct Replay {
+    game: Game,
+}
+
+impl Replay {
+    pub fn new(mut game: Game) -> Result<Replay, Error> {
+        game.goto(0)?;
+        Ok(Replay { game })
+    }
+
+    /// Opens a game written by `Board::save`
+    pub fn load(filename: &str) -> Result<Replay, Error> {
+        Replay::new(Game::load(filename)?)
+    }
+
+    pub fn board(&self) -> &Board {
+        self.game.board()
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the number of moves played up to the shown position
+    pub fn ply(&self) -> usize {
+        self.game.ply()
+    }
+
+    /// Returns the number of moves in the game
+    pub fn len(&self) -> usize {
+        self.game.ply() + self.game.undone().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn forward(&mut self) -> Result<(), Error> {
+        self.game.redo()
+    }
+
+    pub fn back(&mut self) -> Result<(), Error> {
+        self.game.undo()
+    }
+
+    /// Shows the position after the given number of moves
+    pub fn goto(&mut self, ply: usize) -> Result<(), Error> {
+        self.game.goto(ply)
+    }
+
+    /// Runs one of the replay `commands`, e.g. `n` or `goto 12`
+    pub fn command(&mut self, input: &str) -> Result<(), Error> {
+        let registry = commands();
+        let invocation = registry.parse(input)?;
+
+        match (invocation.name(), invocation.args.as_slice()) {
+            ("next", []) => self.forward(),
+            ("prev", []) => self.back(),
+            ("start", []) => self.goto(0),
+            ("end", []) => self.goto(self.len()),
+            ("goto", [ply]) => {
+                let ply = ply.parse().map_err(|_| Error::InvalidInput)?;
+                self.goto(ply)
+            }
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
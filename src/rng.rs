@@ -0,0 +1,25 @@
+//! Seeded randomness, so that runs using it can be repeated exactly
+
+/// A small seeded xorshift generator. The same seed always gives the same
+/// numbers, so a failing random game can be replayed from its seed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift never leaves zero
+        Rng(seed ^ 0x9e37_79b9_7f4a_7c15 | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number below `n`, which must not be zero
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{
+    chess::{Board, Color, Square, Status},
+    game::{Game, GameResult},
+    markup::Markup,
+    mover::Move,
+    pgn::san,
+};
+
+/// Width of the board pane, labels included
+const BOARD_WIDTH: usize = 17;
+
+/// Lays out a game for a terminal: the board with the move list beside it,
+/// then a status bar and the input line. A selection and any arrows and
+/// highlights are drawn on the board, which shows no pieces when playing
+/// blindfold and is seen from black's side when flipped.
+pub fn layout(
+    game: &Game,
+    input: &str,
+    message: Option<&str>,
+    selection: Option<&Selection>,
+    markup: &Markup,
+    blindfold: bool,
+    flipped: bool,
+) -> Vec<String> {
+    let board = board_rows(game.board(), selection, markup, blindfold, flipped);
+
+    let mut moves = move_list(game);
+    // keep the latest moves, leaving a row for the heading
+    let shown = board.len() - 1;
+    if moves.len() > shown {
+        moves.drain(..moves.len() - shown);
+    }
+
+    let mut lines = Vec::new();
+
+    for (i, row) in board.iter().enumerate() {
+        let pane = match i {
+            0 => Some("Moves"),
+            _ => moves.get(i - 1).map(String::as_str),
+        };
+
+        lines.push(match pane {
+            Some(pane) => format!("{}    {}", pad(row, BOARD_WIDTH), pane),
+            None => row.clone(),
+        });
+    }
+
+    lines.push(String::new());
+    lines.push(status(game));
+    lines.push(message.unwrap_or_default().to_string());
+    lines.push(format!("> {}", input));
+
+    lines
+}
+
+/// Draws the board like `Board::to_unicode`, with the cursor and the
+/// selected piece in reverse video, its destinations marked and the markup
+/// in color. The squares of the last move get a background and a king in
+/// check a red one. Blindfold, the pieces are left out.
+fn board_rows(
+    board: &Board,
+    selection: Option<&Selection>,
+    markup: &Markup,
+    blindfold: bool,
+    flipped: bool,
+) -> Vec<String> {
+    let targets = selection.map_or(Vec::new(), |s| s.targets(board));
+    let last_move = board.last_move_squares();
+    let checked = board.checked_king();
+    let mut rows = Vec::new();
+
+    for row_index in 0..8 {
+        let rank = if flipped { row_index } else { 7 - row_index };
+        let mut row = (rank + 1).to_string();
+
+        for column in 0..8 {
+            let file = if flipped { 7 - column } else { column };
+            let square = (file, rank);
+            let piece = board.get_piece(file, rank).filter(|_| !blindfold);
+
+            let symbol = match piece {
+                Some(piece) => piece.to_string(),
+                None if targets.contains(&square) => "+".to_string(),
+                None => markup.symbol(square).unwrap_or('·').to_string(),
+            };
+
+            let style = match selection {
+                Some(s) if s.selected == Some(square) => Some("1;7"),
+                Some(s) if s.cursor == square => Some("7"),
+                _ if piece.is_some() && targets.contains(&square) => Some("4"),
+                _ => None,
+            };
+
+            let background = match markup.background(square) {
+                _ if checked == Some(square) => Some("41"),
+                Some(color) => Some(color),
+                None if last_move.is_some_and(|squares| squares.contains(&square)) => Some("100"),
+                None => None,
+            };
+
+            let style = match (style, background) {
+                (Some(style), Some(color)) => Some(format!("{};{}", style, color)),
+                (style, color) => style.or(color).map(str::to_string),
+            };
+
+            row.push(' ');
+            match style {
+                Some(style) => row.push_str(&format!("\x1b[{}m{}\x1b[0m", style, symbol)),
+                None => row.push_str(&symbol),
+            }
+        }
+
+        rows.push(row);
+    }
+
+    rows.push(match flipped {
+        false => "  a b c d e f g h".to_string(),
+        true => "  h g f e d c b a".to_string(),
+    });
+    rows
+}
+
+/// Counts the characters that take up room on the terminal, skipping
+/// escape sequences
+fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        match c {
+            '\x1b' => escaped = true,
+            c if escaped => escaped = !c.is_ascii_alphabetic(),
+            _ => width += 1,
+        }
+    }
+
+    width
+}
+
+fn pad(text: &str, columns: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(columns.saturating_sub(width(text)))
+    )
+}
+
+/// Numbers the moves in SAN, one full move per row
+fn move_list(game: &Game) -> Vec<String> {
+    let Ok(positions) = game.positions() else {
+        return Vec::new();
+    };
+
+    let mut rows: Vec<String> = Vec::new();
+
+    for (board, entry) in positions.iter().zip(game.history()) {
+        let text = san(board, &entry.m).unwrap_or_else(|_| String::from(entry.m));
+        let number = board.fullmove_number();
+
+        match (board.turn(), rows.last_mut()) {
+            (Color::Black, Some(row)) => {
+                row.push(' ');
+                row.push_str(&text);
+            }
+            (Color::Black, None) => rows.push(format!("{}. ... {}", number, text)),
+            (Color::White, _) => rows.push(format!("{}. {}", number, text)),
+        }
+    }
+
+    rows
+}
+
+fn status(game: &Game) -> String {
+    let board = game.board();
+    let mut parts = vec![format!("{} vs {}", game.white(), game.black())];
+
+    match game.result() {
+        GameResult::Ongoing => {
+            parts.push(format!("{} to move", board.turn()));
+            if board.status() == Status::Check {
+                parts.push("check".to_string());
+            }
+        }
+        result => parts.push(result.to_string()),
+    }
+
+    if let Some(clock) = game.clock() {
+        parts.push(clock.to_string());
+    }
+
+    parts.join(" | ")
+}
+
+/// A terminal screen that is redrawn in place. Only the rows that changed
+/// since the last frame are written, so the display doesn't flicker.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Screen {
+    shown: Vec<String>,
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Screen::default()
+    }
+
+    /// Forgets what is on the terminal, so the next frame is drawn in full,
+    /// e.g. after the window was resized
+    pub fn invalidate(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Returns the escape sequences that turn the previous frame into this
+    /// one, leaving the cursor at the end of the last row
+    pub fn draw(&mut self, lines: &[String]) -> String {
+        let mut out = String::new();
+
+        if self.shown.is_empty() {
+            out.push_str("\x1b[2J");
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            if self.shown.get(row) != Some(line) {
+                out.push_str(&format!("\x1b[{};1H{}\x1b[K", row + 1, line));
+            }
+        }
+
+        for row in lines.len()..self.shown.len() {
+            out.push_str(&format!("\x1b[{};1H\x1b[K", row + 1));
+        }
+
+        if let Some(last) = lines.last() {
+            out.push_str(&format!("\x1b[{};{}H", lines.len(), width(last) + 1));
+        }
+
+        self.shown = lines.to_vec();
+        out
+    }
+}
+
+/// Returns the board square under a terminal cell of a frame from `layout`,
+/// with rows and columns counted from 1 as in mouse reports
+pub fn square_at(column: usize, row: usize, flipped: bool) -> Option<Square> {
+    // the rank label and a space come before every square
+    if !(1..=8).contains(&row) || column < 3 || column.is_multiple_of(2) {
+        return None;
+    }
+
+    let file = (column - 3) / 2;
+    match flipped {
+        false => (file < 8).then_some((file, 8 - row)),
+        true => (file < 8).then_some((7 - file, row - 1)),
+    }
+}
+
+/// Reads an SGR mouse report, e.g. `\x1b[<0;5;3M`, returning the column and
+/// row of a left button press
+pub fn parse_click(input: &str) -> Option<(usize, usize)> {
+    let report = input.strip_prefix("\x1b[<")?.strip_suffix('M')?;
+    let mut fields = report.split(';').map(|f| f.parse::<usize>().ok());
+
+    match (
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next(),
+    ) {
+        (Some(0), Some(column), Some(row), None) => Some((column, row)),
+        _ => None,
+    }
+}
+
+/// A key used to pick squares on the board
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+}
+
+/// A move picked on the board. A pawn reaching the last rank is picked
+/// without its promotion piece, which has to be asked for and added with
+/// `Move::with_promotion`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pick {
+    pub m: Move,
+    pub needs_promotion: bool,
+}
+
+/// Picks a move by selecting squares: first the piece, then one of its
+/// legal destinations
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Selection {
+    pub cursor: Square,
+    pub selected: Option<Square>,
+}
+
+impl Default for Selection {
+    fn default() -> Selection {
+        Selection {
+            cursor: (4, 1),
+            selected: None,
+        }
+    }
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    /// Returns the squares the selected piece can legally move to
+    pub fn targets(&self, board: &Board) -> Vec<Square> {
+        let Some(from) = self.selected else {
+            return Vec::new();
+        };
+
+        board
+            .legal_moves()
+            .into_iter()
+            .filter(|m| !m.is_drop() && (m.from_file, m.from_rank) == from)
+            .map(|m| (m.to_file, m.to_rank))
+            .collect()
+    }
+
+    /// Moves the cursor or acts on the square under it, returning the move
+    /// once a destination has been picked. On a flipped board the arrow
+    /// keys move the other way, so they still follow the screen.
+    pub fn key(&mut self, board: &Board, key: Key, flipped: bool) -> Option<Pick> {
+        let (file, rank) = self.cursor;
+
+        let key = match (flipped, key) {
+            (true, Key::Up) => Key::Down,
+            (true, Key::Down) => Key::Up,
+            (true, Key::Left) => Key::Right,
+            (true, Key::Right) => Key::Left,
+            (_, key) => key,
+        };
+
+        match key {
+            Key::Up => self.cursor = (file, (rank + 1).min(7)),
+            Key::Down => self.cursor = (file, rank.saturating_sub(1)),
+            Key::Left => self.cursor = (file.saturating_sub(1), rank),
+            Key::Right => self.cursor = ((file + 1).min(7), rank),
+            Key::Enter => return self.pick(board, self.cursor),
+            Key::Escape => self.selected = None,
+        }
+
+        None
+    }
+
+    /// Selects the square that was clicked, returning the move once a
+    /// destination has been picked
+    pub fn click(&mut self, board: &Board, square: Square) -> Option<Pick> {
+        self.cursor = square;
+        self.pick(board, square)
+    }
+
+    fn pick(&mut self, board: &Board, square: Square) -> Option<Pick> {
+        if let Some(from) = self.selected {
+            if self.targets(board).contains(&square) {
+                self.selected = None;
+
+                let m = Move::new(from.0, from.1, square.0, square.1);
+                return Some(Pick {
+                    m,
+                    needs_promotion: board.is_promotion(&m),
+                });
+            }
+        }
+
+        // picking another piece of the side to move switches to it
+        self.selected = board
+            .get_piece(square.0, square.1)
+            .filter(|piece| piece.color == board.turn())
+            .map(|_| square);
+
+        None
+    }
+}
@@ -0,0 +1,2151 @@
+use std::{
+    io::{BufRead, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    annotate::{annotate_file, annotate_from, Judgement, INACCURACY},
+    announce::Announcer,
+    arena::{load_openings, play_match, Adjudication, Contender, MatchOptions, MatchReport, Sprt},
+    bench,
+    book::Book,
+    chess::{Board, Class, Color, Error, MoveError},
+    command::{Command, Registry},
+    config::{Config, KEYS},
+    engine::{AnalysisOptions, Backend, Engine, Limits, MAX_SKILL},
+    epd,
+    fen::ToFen,
+    game::Game,
+    geometry::SquareGeometry,
+    graph,
+    heatmap::Heatmap,
+    locale::{Locale, Message},
+    manager::GameManager,
+    markup::{Brush, Markup},
+    mover::{parse_square, Move},
+    net::{quote, Connection, Message as NetMessage},
+    notify::Notifier,
+    pgn::Nag,
+    ponder::Ponder,
+    profile::{engine_rating, Profile},
+    puzzle::{self, Attempt, Puzzle, Score, Session},
+    query::{self, Query},
+    repertoire::{Repertoire, Trainer},
+    rng::Rng,
+    see::{see, see_square},
+    tablebase,
+    teach::{explain, Lesson},
+    training::{Blindfold, CoordinateDrill, Drill},
+    tune,
+    uci::UciEngine,
+};
+
+/// Returns the commands understood by `Script::execute`. A line that is not
+/// a command but looks like a move is played as one.
+pub fn commands() -> Registry {
+    let mut registry = Registry::new();
+
+    registry.register(Command {
+        name: "help",
+        aliases: &["?"],
+        args: &["[command]"],
+        help: "List the commands, or tell what one of them does",
+    });
+    registry.register(Command {
+        name: "move",
+        aliases: &["m"],
+        args: &["<move>"],
+        help: "Play a move in coordinate notation or SAN",
+    });
+    registry.register(Command {
+        name: "undo",
+        aliases: &[],
+        args: &[],
+        help: "Take back the last move",
+    });
+    registry.register(Command {
+        name: "redo",
+        aliases: &[],
+        args: &[],
+        help: "Replay the last move taken back",
+    });
+    registry.register(Command {
+        name: "fen",
+        aliases: &[],
+        args: &[],
+        help: "Print the position in FEN",
+    });
+    registry.register(Command {
+        name: "position",
+        aliases: &[],
+        args: &[
+            "<placement>",
+            "[turn]",
+            "[castling]",
+            "[en-passant]",
+            "[halfmove]",
+            "[fullmove]",
+        ],
+        help: "Start a new game from a FEN",
+    });
+    registry.register(Command {
+        name: "setfen",
+        aliases: &[],
+        args: &["<fen...>"],
+        help: "Start editing a position from a FEN",
+    });
+    registry.register(Command {
+        name: "put",
+        aliases: &[],
+        args: &["<piece-square>"],
+        help: "Put a piece on the edited position, e.g. Ke4 or pd7",
+    });
+    registry.register(Command {
+        name: "remove",
+        aliases: &[],
+        args: &["<square>"],
+        help: "Take the piece on a square off the edited position",
+    });
+    registry.register(Command {
+        name: "clear",
+        aliases: &[],
+        args: &[],
+        help: "Empty the edited position",
+    });
+    registry.register(Command {
+        name: "done",
+        aliases: &[],
+        args: &[],
+        help: "Check the edited position and start a new game from it",
+    });
+    registry.register(Command {
+        name: "new",
+        aliases: &[],
+        args: &["[name]"],
+        help: "Start a new game from the default position, alongside the others when named",
+    });
+    registry.register(Command {
+        name: "comment",
+        aliases: &[],
+        args: &["<text...>"],
+        help: "Comment on the last move; leading glyphs such as !? or $14 are attached as such",
+    });
+    registry.register(Command {
+        name: "bookmark",
+        aliases: &[],
+        args: &["[name]"],
+        help: "Remember the position under a name, or list the bookmarks",
+    });
+    registry.register(Command {
+        name: "goto",
+        aliases: &[],
+        args: &["<name|ply>"],
+        help: "Return to a bookmarked position, or to the position after some plies",
+    });
+    registry.register(Command {
+        name: "replay",
+        aliases: &[],
+        args: &["<file>"],
+        help: "Open a saved game at its first position to step through with next and prev",
+    });
+    registry.register(Command {
+        name: "next",
+        aliases: &["n"],
+        args: &[],
+        help: "Step forward one move",
+    });
+    registry.register(Command {
+        name: "prev",
+        aliases: &["p"],
+        args: &[],
+        help: "Step back one move",
+    });
+    registry.register(Command {
+        name: "variations",
+        aliases: &[],
+        args: &[],
+        help: "List the moves played from this position at some point, main line first",
+    });
+    registry.register(Command {
+        name: "promote-line",
+        aliases: &[],
+        args: &[],
+        help: "Make the line to this position the main line",
+    });
+    registry.register(Command {
+        name: "switch",
+        aliases: &[],
+        args: &["<name>"],
+        help: "Continue another of the open games",
+    });
+    registry.register(Command {
+        name: "list",
+        aliases: &["games"],
+        args: &[],
+        help: "List the open games, the current one marked with *",
+    });
+    registry.register(Command {
+        name: "close",
+        aliases: &[],
+        args: &["<name>"],
+        help: "Close one of the open games",
+    });
+    registry.register(Command {
+        name: "moves",
+        aliases: &["legal"],
+        args: &[],
+        help: "List the legal moves",
+    });
+    registry.register(Command {
+        name: "state",
+        aliases: &[],
+        args: &[],
+        help: "Print the whole game state as JSON",
+    });
+    registry.register(Command {
+        name: "claim",
+        aliases: &[],
+        args: &["draw"],
+        help: "Claim a draw by threefold repetition or the fifty-move rule",
+    });
+    registry.register(Command {
+        name: "resign",
+        aliases: &[],
+        args: &[],
+        help: "Resign the game",
+    });
+    registry.register(Command {
+        name: "offer",
+        aliases: &[],
+        args: &["draw"],
+        help: "Offer a draw, which the engine answers at once in a rated game",
+    });
+    registry.register(Command {
+        name: "accept",
+        aliases: &[],
+        args: &[],
+        help: "Accept the draw the opponent offered",
+    });
+    registry.register(Command {
+        name: "decline",
+        aliases: &[],
+        args: &[],
+        help: "Decline the draw the opponent offered",
+    });
+    registry.register(Command {
+        name: "host",
+        aliases: &[],
+        args: &["<port>"],
+        help: "Wait for a player to connect and play white against them over the network",
+    });
+    registry.register(Command {
+        name: "connect",
+        aliases: &[],
+        args: &["<address>"],
+        help: "Join a player hosting a game over the network",
+    });
+    registry.register(Command {
+        name: "result",
+        aliases: &[],
+        args: &[],
+        help: "Print the result of the game",
+    });
+    registry.register(Command {
+        name: "config",
+        aliases: &[],
+        args: &["[get|set]", "[key]", "[value]"],
+        help: "Print the settings, or one of them, or change one and save it to config.toml",
+    });
+    registry.register(Command {
+        name: "rated",
+        aliases: &[],
+        args: &["[white|black]"],
+        help: "Start a rated game against the built-in engine, which counts toward the profile's rating",
+    });
+    registry.register(Command {
+        name: "profile",
+        aliases: &[],
+        args: &["[name|color]", "[value]"],
+        help: "Print the profile's rating and record, or set its name or preferred color",
+    });
+    registry.register(Command {
+        name: "level",
+        aliases: &["skill"],
+        args: &["[1-20]"],
+        help: "Print or set the engine's playing strength",
+    });
+    registry.register(Command {
+        name: "seed",
+        aliases: &[],
+        args: &["[n|off]"],
+        help: "Print or set the seed of the engine's noise and the drills, so runs can be repeated",
+    });
+    registry.register(Command {
+        name: "go",
+        aliases: &[],
+        args: &[],
+        help: "Let the engine play a move",
+    });
+    registry.register(Command {
+        name: "hint",
+        aliases: &[],
+        args: &[],
+        help: "Print the move the engine would play, without playing it",
+    });
+    registry.register(Command {
+        name: "book",
+        aliases: &[],
+        args: &["[file|off]"],
+        help: "Open a Polyglot opening book for the engine to play from, or print its moves for the position",
+    });
+    registry.register(Command {
+        name: "mobility",
+        aliases: &[],
+        args: &["[square]"],
+        help: "Print how many moves each side's pieces have and which are trapped, or where the piece on a square can go",
+    });
+    registry.register(Command {
+        name: "tablebase",
+        aliases: &["tb"],
+        args: &[],
+        help: "Print the result under perfect play of king and queen or rook against king",
+    });
+    registry.register(Command {
+        name: "see",
+        aliases: &[],
+        args: &["<move|square>"],
+        help: "Print what a capture, or starting an exchange on a square, wins in centipawns",
+    });
+    registry.register(Command {
+        name: "analyze",
+        aliases: &["multipv"],
+        args: &["[lines|on|off]"],
+        help: "Print the best few moves for the side to move with their scores and lines, ranked, or with on have the engine in use evaluate every move played",
+    });
+    registry.register(Command {
+        name: "engine",
+        aliases: &[],
+        args: &["[add|use|set]", "[args...]"],
+        help: "List the engines, add an external UCI engine with `add <name> <path>`, pick one with `use <name|builtin>` or `set <option> <value>` on it",
+    });
+    registry.register(Command {
+        name: "suite",
+        aliases: &[],
+        args: &["<file>", "[depth]"],
+        help: "Run the engine on an EPD test suite and count the best moves it finds",
+    });
+    registry.register(Command {
+        name: "bench",
+        aliases: &[],
+        args: &["[perft-depth]", "[search-depth]"],
+        help: "Measure the nodes per second of perft and the search on the standard positions",
+    });
+    registry.register(Command {
+        name: "annotate",
+        aliases: &[],
+        args: &["<file>", "<pgn>"],
+        help: "Judge every move of a saved game with the engine and write it as annotated PGN",
+    });
+    registry.register(Command {
+        name: "tune",
+        aliases: &[],
+        args: &["<samples>", "<params>", "[passes]"],
+        help: "Fit the evaluation weights to the results of a PGN or EPD file, writing them to a file the engine then loads",
+    });
+    registry.register(Command {
+        name: "match",
+        aliases: &[],
+        args: &["<depth>", "<depth>", "<games>", "[suite]", "[sprt...]"],
+        help: "Play the built-in engine at two depths against each other, each opening of an EPD or PGN suite once with either color. `sprt <elo0> <elo1> [alpha] [beta]` stops once a hypothesis is accepted, answering with the standings after every game",
+    });
+    registry.register(Command {
+        name: "arrow",
+        aliases: &[],
+        args: &["<from>", "<to>", "[color]"],
+        help: "Draw an arrow, or take it away again",
+    });
+    registry.register(Command {
+        name: "mark",
+        aliases: &[],
+        args: &["<square>", "[color]"],
+        help: "Highlight a square, or take the highlight away again",
+    });
+    registry.register(Command {
+        name: "unmark",
+        aliases: &[],
+        args: &[],
+        help: "Remove every arrow and highlight",
+    });
+    registry.register(Command {
+        name: "heatmap",
+        aliases: &[],
+        args: &[],
+        help: "Shade every square by which side attacks it more often and print how many squares each controls",
+    });
+    registry.register(Command {
+        name: "teach",
+        aliases: &[],
+        args: &["<on|off>"],
+        help: "Explain rejected moves at length, drawing the pieces involved",
+    });
+    registry.register(Command {
+        name: "search-tree",
+        aliases: &[],
+        args: &["<file>", "[plies]"],
+        help: "Write the top plies of the engine's search of the position to a DOT file, or JSON when the name ends in .json",
+    });
+    registry.register(Command {
+        name: "announce",
+        aliases: &[],
+        args: &["[on|off|command...]"],
+        help: "Tell every move in a sentence, with the answer or piped to a text to speech command",
+    });
+    registry.register(Command {
+        name: "notify",
+        aliases: &[],
+        args: &["[bell|off|command...]"],
+        help: "Ring the bell or run a command whenever the engine moves, with the move in $CHESS_MOVE",
+    });
+    registry.register(Command {
+        name: "ponder",
+        aliases: &[],
+        args: &["[on|off]"],
+        help: "Let the engine think on your time about the reply it expects, or print that reply",
+    });
+    registry.register(Command {
+        name: "autoflip",
+        aliases: &[],
+        args: &["<on|off>"],
+        help: "Turn the board after every move so the side to move is at the bottom",
+    });
+    registry.register(Command {
+        name: "blindfold",
+        aliases: &[],
+        args: &["<on|off>"],
+        help: "Hide the board, so moves are given by notation only",
+    });
+    registry.register(Command {
+        name: "peek",
+        aliases: &[],
+        args: &[],
+        help: "Look at the position while playing blindfold",
+    });
+    registry.register(Command {
+        name: "drill",
+        aliases: &[],
+        args: &["<name|find|stop>", "[seconds]"],
+        help: "Start a coordinate drill, optionally timed, or stop it and print the score",
+    });
+    registry.register(Command {
+        name: "answer",
+        aliases: &["a"],
+        args: &["<square>"],
+        help: "Answer the square asked for in a coordinate drill",
+    });
+    registry.register(Command {
+        name: "repertoire",
+        aliases: &[],
+        args: &["<file|stop>", "[white|black]"],
+        help: "Train the opening lines of a PGN: the opponent's moves are played from it and yours are checked",
+    });
+    registry.register(Command {
+        name: "reply",
+        aliases: &[],
+        args: &["<move>"],
+        help: "Play your prepared reply in repertoire training",
+    });
+    registry.register(Command {
+        name: "puzzle",
+        aliases: &[],
+        args: &["<file|stop>"],
+        help: "Solve the puzzles of a file one after the other, or stop and print the score",
+    });
+    registry.register(Command {
+        name: "solve",
+        aliases: &[],
+        args: &["<move>"],
+        help: "Play a move of the puzzle's solution",
+    });
+    registry.register(Command {
+        name: "stats",
+        aliases: &[],
+        args: &[],
+        help: "Sum up the game: captures, checks, castling, time and material",
+    });
+    registry.register(Command {
+        name: "search",
+        aliases: &[],
+        args: &["<file>", "[query...]"],
+        help: "List the games of a PGN file matching --player, --white, --black, --eco, --result or --fen",
+    });
+    #[cfg(feature = "clipboard")]
+    registry.register(Command {
+        name: "copy",
+        aliases: &[],
+        args: &["<fen|pgn>"],
+        help: "Copy the position or the game to the system clipboard",
+    });
+    #[cfg(feature = "clipboard")]
+    registry.register(Command {
+        name: "paste",
+        aliases: &[],
+        args: &["<fen>"],
+        help: "Set up the position on the system clipboard",
+    });
+    #[cfg(feature = "svg")]
+    registry.register(Command {
+        name: "render-image",
+        aliases: &[],
+        args: &["<file>"],
+        help: "Save the position with its arrows and highlights as an SVG image",
+    });
+    #[cfg(feature = "gif")]
+    registry.register(Command {
+        name: "export-gif",
+        aliases: &[],
+        args: &["<file>", "[seconds]"],
+        help:
+            "Save a replay of the game as an animated GIF, showing each position for some seconds",
+    });
+    registry.register(Command {
+        name: "graph",
+        aliases: &[],
+        args: &[],
+        help: "Analyze the game and draw the evaluation with its largest swings",
+    });
+
+    registry
+}
+
+/// Writes the moves of a line from a position in SAN
+fn san_line(locale: Locale, board: &Board, moves: &[Move]) -> Result<String, Error> {
+    let mut sans = Vec::new();
+    let mut next: Option<Board> = None;
+
+    for m in moves {
+        let current = next.as_ref().unwrap_or(board);
+        sans.push(locale.san(current, m)?);
+
+        let after = current.with_move(m)?;
+        next = Some(after);
+    }
+
+    Ok(sans.join(" "))
+}
+
+/// Starts the next line of repertoire training, returning the opponent's
+/// first move or a prompt to play when there is none
+fn opponent_moves(locale: Locale, trainer: &mut Trainer) -> Result<String, Error> {
+    let played = trainer.next_line()?;
+    if played.is_empty() {
+        return Ok(locale.text(Message::YourMove, &[]));
+    }
+
+    san_line(locale, &Board::default_board()?, &played)
+}
+
+/// Whether a line that names no command is meant as a move: a single word
+/// with a rank or ICCF digit in it, or castling
+fn looks_like_move(line: &str) -> bool {
+    !line.contains(char::is_whitespace)
+        && (line.contains(|c: char| c.is_ascii_digit()) || line.starts_with("O-O"))
+}
+
+/// Reads an optional color argument, green when left out
+fn brush_of(name: Option<&&str>) -> Result<Brush, Error> {
+    name.map_or(Ok(Brush::default()), |name| Brush::try_from(*name))
+}
+
+/// A game against the built-in engine that counts toward the profile
+struct Rated {
+    /// The name of the game, see `new <name>`
+    game: String,
+    /// The color the player has
+    color: Color,
+    opponent: i32,
+}
+
+/// The puzzles of a file being solved one after the other, see `puzzle`
+struct Puzzles {
+    puzzles: Vec<Puzzle>,
+    /// The puzzle being solved
+    index: usize,
+    session: Session,
+    score: Score,
+}
+
+impl Puzzles {
+    /// Starts solving the puzzle at the given index, moves the engine finds
+    /// as good as the solution's counting as well
+    fn start(&mut self, index: usize, engine: Engine) -> Result<(), Error> {
+        let mut session = Session::new(self.puzzles[index].clone())?;
+        session.verify_with(engine, INACCURACY);
+
+        self.index = index;
+        self.session = session;
+        Ok(())
+    }
+
+    fn prompt(&self) -> String {
+        format!(
+            "puzzle {} of {}: {}",
+            self.index + 1,
+            self.puzzles.len(),
+            self.session.puzzle().fen
+        )
+    }
+}
+
+/// The built-in engine and any external ones
+#[derive(Default)]
+struct Engines {
+    builtin: Engine,
+    /// External engines by name, and the one playing instead of the
+    /// built-in engine, if any
+    external: Vec<(String, UciEngine)>,
+    active: Option<usize>,
+    /// The built-in engine's search of the reply it expects to its last
+    /// move, see `ponder`
+    pondering: Option<Ponder>,
+}
+
+impl Engines {
+    /// Returns the engine that plays and analyzes
+    fn active_mut(&mut self) -> &mut dyn Backend {
+        match self.active {
+            Some(i) => &mut self.external[i].1,
+            None => &mut self.builtin,
+        }
+    }
+}
+
+/// Drives a game from lines of text without prompts or screen handling,
+/// answering every line with exactly one line: `ok [value]` or
+/// `error <message>`, or a JSON object in JSON mode
+pub struct Script {
+    games: GameManager,
+    engines: Engines,
+    markup: Markup,
+    /// Whether rejected moves are explained, see `teach`
+    teach: bool,
+    /// Whether the board turns to the side to move, for two players
+    /// sharing a screen
+    autoflip: bool,
+    /// Whether the built-in engine thinks on the opponent's time
+    ponder: bool,
+    lesson: Option<Lesson>,
+    blindfold: Option<Blindfold>,
+    drill: Option<CoordinateDrill>,
+    repertoire: Option<Trainer>,
+    /// Opened on first use, so sessions that don't need it leave the
+    /// config directory alone
+    profile: Option<Profile>,
+    rated: Option<Rated>,
+    /// Told about every move the engine makes, see `notify`
+    notifier: Option<Notifier>,
+    /// Where moves are told in sentences, see `announce`
+    announcer: Option<Announcer>,
+    /// A pawn move waiting for the piece it promotes to, see `play`
+    promotion: Option<Move>,
+    /// The position being set up, see `setfen` and `done`
+    editor: Option<Board>,
+    puzzles: Option<Puzzles>,
+    /// The opening book the engine plays from, see `book`
+    book: Option<Book>,
+    /// The peer of a network game, which is played as the current game
+    connection: Option<Connection>,
+    /// What the engine's noise and the drills are seeded with, none to
+    /// seed them from the time
+    seed: Option<u64>,
+    /// Opened on first use, since a session without a display has none
+    #[cfg(feature = "clipboard")]
+    clipboard: Option<crate::clipboard::Clipboard>,
+    config: Config,
+    json: bool,
+}
+
+impl Script {
+    /// Creates a runner with the defaults of `config.toml` in the config
+    /// directory
+    pub fn new(json: bool) -> Result<Script, Error> {
+        Script::with_config(json, Config::open()?)
+    }
+
+    pub fn with_config(json: bool, config: Config) -> Result<Script, Error> {
+        Ok(Script {
+            games: GameManager::new("main", config.new_game()?),
+            engines: Engines {
+                builtin: Engine::new(config.depth),
+                ..Default::default()
+            },
+            markup: Markup::new(),
+            teach: false,
+            autoflip: false,
+            ponder: false,
+            lesson: None,
+            blindfold: None,
+            drill: None,
+            repertoire: None,
+            profile: None,
+            rated: None,
+            notifier: None,
+            announcer: None,
+            promotion: None,
+            editor: None,
+            puzzles: None,
+            book: None,
+            connection: None,
+            seed: None,
+            #[cfg(feature = "clipboard")]
+            clipboard: None,
+            config,
+            json,
+        })
+    }
+
+    /// Sets what happens when the engine moves, or nothing for `None`
+    pub fn set_notifier(&mut self, notifier: Option<Notifier>) {
+        self.notifier = notifier;
+    }
+
+    /// Returns the current game
+    pub fn game(&self) -> &Game {
+        self.games.current()
+    }
+
+    /// Runs a line of input and returns the line to answer with. Blank lines
+    /// and lines starting with `#` are skipped.
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        // the answer to the promotion prompt finishes the pawn's move, while
+        // anything else drops it
+        if let Some(m) = self.promotion.take() {
+            if let Some(class) = self.config.locale.piece(line) {
+                let outcome = self.play(&m.with_promotion(class).to_string());
+                let lesson = self.lesson.take();
+                return Some(self.answer(Some("move"), outcome, lesson));
+            }
+        }
+
+        let (name, outcome) = match commands().parse(line) {
+            Ok(invocation) => {
+                let name = invocation.name();
+                (Some(name), self.run(name, &invocation.args))
+            }
+            Err(Error::UnknownCommand { .. }) if looks_like_move(line) => {
+                (Some("move"), self.play(line))
+            }
+            Err(e) => (None, Err(e)),
+        };
+
+        let lesson = self.lesson.take();
+        Some(self.answer(name, outcome, lesson))
+    }
+
+    /// Executes every line of the input, writing the answers to the output
+    pub fn run_all<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> Result<(), Error> {
+        for line in input.lines() {
+            if let Some(answer) = self.execute(&line?) {
+                writeln!(output, "{}", answer)?;
+            }
+        }
+
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Returns every open game, see `new <name>`
+    pub fn games(&self) -> &GameManager {
+        &self.games
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engines.builtin
+    }
+
+    /// Returns the arrows and highlights drawn with `arrow` and `mark`
+    pub fn markup(&self) -> &Markup {
+        &self.markup
+    }
+
+    /// Returns true while the board is hidden, see `blindfold`
+    pub fn is_blindfold(&self) -> bool {
+        self.blindfold.is_some()
+    }
+
+    pub fn drill(&self) -> Option<&CoordinateDrill> {
+        self.drill.as_ref()
+    }
+
+    /// Returns true when the board should be drawn from black's side, which
+    /// with `autoflip` is whenever black is to move
+    pub fn flipped(&self) -> bool {
+        self.autoflip && self.games.current().board().turn() == Color::Black
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn clipboard(&mut self) -> Result<&mut crate::clipboard::Clipboard, Error> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(crate::clipboard::Clipboard::new()?);
+        }
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    fn profile(&mut self) -> Result<&mut Profile, Error> {
+        if self.profile.is_none() {
+            self.profile = Some(Profile::open()?);
+        }
+        Ok(self.profile.as_mut().unwrap())
+    }
+
+    /// Rates the rated game once it is over, returning the new rating
+    fn settle_rated(&mut self) -> Result<Option<String>, Error> {
+        let Some(rated) = self
+            .rated
+            .take_if(|rated| self.games.get(&rated.game).is_some_and(Game::is_over))
+        else {
+            return Ok(None);
+        };
+
+        let game = self.games.get(&rated.game).ok_or(Error::InvalidInput)?;
+        let profile = match self.profile.as_mut() {
+            Some(profile) => profile,
+            None => self.profile.insert(Profile::open()?),
+        };
+        let change = profile.rate(game, rated.color, rated.opponent)?;
+
+        Ok(Some(format!("rating {} ({:+})", profile.rating, change)))
+    }
+
+    /// Returns the seed set with `seed`, or one from the time
+    fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_nanos() as u64)
+        })
+    }
+
+    /// Returns the position being edited, starting from the current one
+    fn editing(&mut self) -> &mut Board {
+        let board = self.games.current().board();
+        self.editor.get_or_insert_with(|| board.clone())
+    }
+
+    /// Tells how far a replay has got: the moves played of all, and the
+    /// position
+    fn replay_position(&self) -> String {
+        let game = self.games.current();
+        format!(
+            "{}/{} {}",
+            game.ply(),
+            game.ply() + game.undone().count(),
+            game.board().to_fen()
+        )
+    }
+
+    /// Makes a network game the current game and waits for the peer's
+    /// first move when the peer plays white
+    fn start_network(&mut self, connection: Connection) -> Result<Option<String>, Error> {
+        let mut game = Game::new()?;
+        game.set_players(connection.game().white(), connection.game().black());
+
+        let color = connection.color();
+        let opponent = match color {
+            Color::White => game.black(),
+            Color::Black => game.white(),
+        }
+        .to_string();
+
+        *self.games.current_mut() = game;
+        self.markup.clear();
+        self.connection = Some(connection);
+
+        let events = self.wait_for_peer()?;
+        Ok(Some(format!("{} against {}{}", color, opponent, events)))
+    }
+
+    /// Waits for the peer of a network game until the player is to move or
+    /// to answer a draw offer, doing on the current game what the peer
+    /// does. Returns what the peer did, each after a `; `. The connection
+    /// is closed once the game is over.
+    fn wait_for_peer(&mut self) -> Result<String, Error> {
+        let mut events = String::new();
+
+        while let Some(connection) = self.connection.as_mut() {
+            let color = connection.color();
+            let game = connection.game();
+
+            if game.is_over() {
+                self.connection = None;
+                break;
+            }
+            match game.draw_offer() {
+                Some(offer) if offer != color => break,
+                // the peer is to answer the player's offer
+                Some(_) => {}
+                None if connection.is_my_turn() => break,
+                None => {}
+            }
+
+            let message = connection.receive()?;
+            let peer = color.opponent();
+            let game = self.games.current_mut();
+
+            let event = match message {
+                NetMessage::Move(m) => {
+                    game.move_piece(&m)?;
+                    m
+                }
+                NetMessage::Resign => {
+                    game.resign(peer)?;
+                    format!("{} resigns", peer)
+                }
+                NetMessage::OfferDraw => {
+                    game.offer_draw(peer)?;
+                    "draw offered".to_string()
+                }
+                NetMessage::AcceptDraw => {
+                    game.accept_draw(peer)?;
+                    "draw accepted".to_string()
+                }
+                NetMessage::DeclineDraw => {
+                    game.decline_draw(peer)?;
+                    "draw declined".to_string()
+                }
+                NetMessage::Error(e) => return Err(Error::Network(e)),
+                NetMessage::Hello { .. } => continue,
+            };
+            events.push_str(&format!("; {}", event));
+        }
+
+        Ok(events)
+    }
+
+    /// Returns the rated game when it is the current game
+    fn rated_current(&self) -> Option<&Rated> {
+        self.rated
+            .as_ref()
+            .filter(|rated| rated.game == self.games.current_name())
+    }
+
+    /// Returns the color played over the network or in the rated game when
+    /// it is the current game, and the side to move otherwise, who resigns
+    /// or offers a draw
+    fn player(&self) -> Color {
+        if let Some(connection) = &self.connection {
+            return connection.color();
+        }
+
+        match self.rated_current() {
+            Some(rated) => rated.color,
+            None => self.games.current().board().turn(),
+        }
+    }
+
+    /// Returns who answers a draw offer: the player, or the opponent of
+    /// whoever offered it when two players share the game
+    fn answering(&self) -> Color {
+        let shared = self.connection.is_none() && self.rated_current().is_none();
+
+        match self.games.current().draw_offer() {
+            Some(offer) if shared => offer.opponent(),
+            _ => self.player(),
+        }
+    }
+
+    /// Returns the result of a game that just ended, with the new rating
+    /// when it was the rated game
+    fn finished(&mut self) -> Result<Option<String>, Error> {
+        let result = self.games.current().result().to_string();
+        Ok(Some(match self.settle_rated()? {
+            Some(rating) => format!("{}, {}", result, rating),
+            None => result,
+        }))
+    }
+
+    fn run(&mut self, name: &str, args: &[&str]) -> Result<Option<String>, Error> {
+        match (name, args) {
+            ("help", []) => {
+                let usages: Vec<String> = commands().commands().iter().map(|c| c.usage()).collect();
+                Ok(Some(usages.join(", ")))
+            }
+            // the help of one command comes in lines, which are joined into one
+            ("help", [command]) => {
+                let help = commands().help(Some(command))?;
+                Ok(Some(
+                    help.lines().map(str::trim).collect::<Vec<_>>().join("; "),
+                ))
+            }
+            ("move", [m]) => self.play(m),
+            ("undo", []) => self.games.current_mut().undo().map(|_| None),
+            ("redo", []) => self.games.current_mut().redo().map(|_| None),
+            // blindfold, asking for the position is peeking
+            ("fen" | "peek", []) => match self.blindfold.as_mut() {
+                Some(blindfold) => Ok(Some(blindfold.peek(self.games.current().board()))),
+                None => Ok(Some(self.games.current().board().to_fen())),
+            },
+            ("position", fen) => {
+                let board = Board::try_from_fen(&fen.join(" "))?;
+                *self.games.current_mut() = Game::from_board(board);
+                self.markup.clear();
+                Ok(None)
+            }
+            ("setfen", fen) => {
+                self.editor = Some(Board::try_from_fen(&fen.join(" "))?);
+                Ok(None)
+            }
+            ("put", [piece]) => self.editing().put(piece).map(|_| None),
+            ("remove", [square]) => self.editing().remove(square).map(|_| None),
+            ("clear", []) => self.editing().clear().map(|_| None),
+            ("done", []) => {
+                let board = self.editor.as_ref().ok_or(Error::InvalidInput)?;
+                board.validate_position()?;
+
+                // set up afresh, so the game starts from the position alone
+                let fen = board.to_fen();
+                let mut start = Board::start(board.variant())?;
+                start.from_fen(&fen)?;
+
+                *self.games.current_mut() = Game::from_board(start);
+                self.editor = None;
+                self.markup.clear();
+                Ok(Some(fen))
+            }
+            ("replay", [file]) => {
+                let mut game = Game::load(file)?;
+                game.goto(0)?;
+
+                *self.games.current_mut() = game;
+                self.markup.clear();
+                Ok(Some(self.replay_position()))
+            }
+            ("next", []) => {
+                self.games.current_mut().redo()?;
+                Ok(Some(self.replay_position()))
+            }
+            ("prev", []) => {
+                self.games.current_mut().undo()?;
+                Ok(Some(self.replay_position()))
+            }
+            ("new", []) => {
+                *self.games.current_mut() = self.config.new_game()?;
+                self.markup.clear();
+                Ok(None)
+            }
+            ("new", [name]) => {
+                self.games.open(name, self.config.new_game()?)?;
+                self.markup.clear();
+                Ok(None)
+            }
+            ("comment", words) => {
+                let game = self.games.current_mut();
+
+                let mut words = words.iter().peekable();
+                while let Some(nag) = words.peek().and_then(|w| Nag::try_from(**w).ok()) {
+                    game.add_nag(nag)?;
+                    words.next();
+                }
+
+                let text: Vec<&str> = words.copied().collect();
+                if !text.is_empty() {
+                    game.annotate(&text.join(" "))?;
+                }
+                Ok(None)
+            }
+            ("bookmark", []) => {
+                let bookmarks: Vec<String> = self
+                    .games
+                    .current()
+                    .bookmarks()
+                    .iter()
+                    .map(|b| format!("{}@{}", b.name, b.ply()))
+                    .collect();
+                Ok(Some(bookmarks.join(" ")))
+            }
+            ("bookmark", [name]) => {
+                self.games.current_mut().bookmark(name);
+                Ok(None)
+            }
+            ("goto", [target]) => {
+                match target.parse::<usize>() {
+                    Ok(ply) => self.games.current_mut().goto(ply)?,
+                    Err(_) => self.games.current_mut().goto_bookmark(target)?,
+                }
+                self.markup.clear();
+                Ok(None)
+            }
+            ("variations", []) => {
+                let game = self.games.current();
+                let moves = game
+                    .variations()
+                    .iter()
+                    .map(|node| self.config.locale.san(game.board(), &node.entry.m))
+                    .collect::<Result<Vec<String>, Error>>()?;
+                Ok(Some(moves.join(" ")))
+            }
+            ("promote-line", []) => self.games.current_mut().promote_line().map(|_| None),
+            ("switch", [name]) => {
+                self.games.switch(name)?;
+                self.markup.clear();
+                Ok(None)
+            }
+            ("list", []) => {
+                let current = self.games.current_name();
+                let names: Vec<String> = self
+                    .games
+                    .names()
+                    .map(|name| match name == current {
+                        true => format!("{}*", name),
+                        false => name.to_string(),
+                    })
+                    .collect();
+                Ok(Some(names.join(" ")))
+            }
+            ("close", [name]) => {
+                let current = self.games.current_name() == *name;
+                self.games.close(name)?;
+                if current {
+                    self.markup.clear();
+                }
+                Ok(None)
+            }
+            ("moves", []) => {
+                let moves: Vec<String> = self
+                    .games
+                    .current()
+                    .board()
+                    .legal_moves()
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                Ok(Some(moves.join(" ")))
+            }
+            ("state", []) => Ok(Some(self.games.current().to_json())),
+            ("claim", ["draw"]) => {
+                let result = self.games.current_mut().claim_draw()?.to_string();
+                Ok(Some(match self.settle_rated()? {
+                    Some(rating) => format!("{}, {}", result, rating),
+                    None => result,
+                }))
+            }
+            ("resign", []) => {
+                if let Some(connection) = self.connection.as_mut() {
+                    connection.resign()?;
+                    self.connection = None;
+                }
+
+                let player = self.player();
+                self.games.current_mut().resign(player)?;
+                self.finished()
+            }
+            ("offer", ["draw"]) => {
+                let player = self.player();
+                if let Some(connection) = self.connection.as_mut() {
+                    connection.offer_draw()?;
+                    self.games.current_mut().offer_draw(player)?;
+
+                    let events = self.wait_for_peer()?;
+                    return Ok(Some(events.trim_start_matches("; ").to_string()));
+                }
+                self.games.current_mut().offer_draw(player)?;
+
+                if self.rated_current().is_none() {
+                    return Ok(None);
+                }
+
+                // the engine takes the draw unless it stands better
+                let score = self
+                    .engines
+                    .builtin
+                    .evaluate(self.games.current().board())
+                    .score;
+                let engine = player.opponent();
+                let standing = match engine {
+                    Color::White => score,
+                    Color::Black => -score,
+                };
+
+                if standing > 0 {
+                    self.games.current_mut().decline_draw(engine)?;
+                    return Ok(Some("declined".to_string()));
+                }
+
+                self.games.current_mut().accept_draw(engine)?;
+                Ok(self
+                    .finished()?
+                    .map(|result| format!("accepted, {}", result)))
+            }
+            ("accept", []) => {
+                let player = self.answering();
+                if let Some(connection) = self.connection.as_mut() {
+                    connection.accept_draw()?;
+                    self.connection = None;
+                }
+
+                self.games.current_mut().accept_draw(player)?;
+                self.finished()
+            }
+            ("decline", []) => {
+                let player = self.answering();
+                if let Some(connection) = self.connection.as_mut() {
+                    connection.decline_draw()?;
+                }
+
+                self.games.current_mut().decline_draw(player)?;
+                let events = self.wait_for_peer()?;
+                Ok(Some(events.trim_start_matches("; ").to_string()).filter(|e| !e.is_empty()))
+            }
+            ("host", [port]) => {
+                let port = port.parse().map_err(|_| Error::InvalidInput)?;
+                let name = self.profile()?.name.clone();
+                self.start_network(Connection::host(port, &name)?)
+            }
+            ("connect", [address]) => {
+                let name = self.profile()?.name.clone();
+                self.start_network(Connection::connect(address, &name)?)
+            }
+            ("result", []) => Ok(Some(self.games.current().result().to_string())),
+            ("stats", []) => Ok(Some(self.games.current().statistics()?.to_comment())),
+            ("search", [file, query @ ..]) => {
+                let games = query::search_file(file, &Query::from_args(query)?)?;
+
+                let mut found = vec![format!("{} found", games.len())];
+                found.extend(
+                    games.iter().map(|game| {
+                        format!("{} - {} {}", game.white(), game.black(), game.result())
+                    }),
+                );
+                Ok(Some(found.join("; ")))
+            }
+            #[cfg(feature = "clipboard")]
+            ("copy", ["fen"]) => {
+                let fen = self.games.current().board().to_fen();
+                self.clipboard()?.copy(&fen)?;
+                Ok(None)
+            }
+            #[cfg(feature = "clipboard")]
+            ("copy", ["pgn"]) => {
+                let pgn = self.games.current().to_pgn()?;
+                self.clipboard()?.copy(&pgn)?;
+                Ok(None)
+            }
+            #[cfg(feature = "clipboard")]
+            ("paste", ["fen"]) => {
+                let fen = self.clipboard()?.paste()?;
+                *self.games.current_mut() = Game::from_board(Board::try_from_fen(fen.trim())?);
+                self.markup.clear();
+                Ok(None)
+            }
+            #[cfg(feature = "svg")]
+            ("render-image", [file]) => {
+                let (light, dark) = self.config.theme.colors();
+                let options = crate::svg::SvgOptions {
+                    light: light.to_string(),
+                    dark: dark.to_string(),
+                    flipped: self.flipped(),
+                    ..Default::default()
+                };
+                crate::svg::save(self.games.current().board(), &self.markup, &options, file)?;
+                Ok(None)
+            }
+            #[cfg(feature = "gif")]
+            ("export-gif", [file, seconds @ ..]) => {
+                let mut options = crate::gif::GifOptions::default();
+                if let Some(seconds) = seconds.first() {
+                    let seconds: f64 = seconds.parse().map_err(|_| Error::InvalidInput)?;
+                    options.delay =
+                        Duration::try_from_secs_f64(seconds).map_err(|_| Error::InvalidInput)?;
+                }
+
+                crate::gif::save(self.games.current(), &options, file)?;
+                Ok(None)
+            }
+            ("graph", []) => {
+                let moves: Vec<Move> = self
+                    .games
+                    .current()
+                    .history()
+                    .iter()
+                    .map(|entry| entry.m)
+                    .collect();
+                let start = Board::try_from_fen(self.games.current().start_fen())?;
+                let report = annotate_from(start, &moves, self.engines.active_mut())?;
+
+                Ok(Some(format!(
+                    "{} {}",
+                    graph::sparkline(&report.scores()),
+                    report.swings(3).join(", ")
+                )))
+            }
+            ("config", []) => {
+                let settings: Result<Vec<String>, Error> = KEYS
+                    .iter()
+                    .map(|key| Ok(format!("{}={}", key, self.config.get(key)?)))
+                    .collect();
+                Ok(Some(settings?.join(" ")))
+            }
+            ("config", ["get", key]) => Ok(Some(self.config.get(key)?)),
+            ("config", ["set", key, value]) => {
+                self.config.set(key, value)?;
+                self.config.save()?;
+
+                if *key == "engine.depth" {
+                    self.engines.builtin.set_depth(self.config.depth);
+                }
+                Ok(None)
+            }
+            ("rated", color) if color.len() <= 1 => {
+                let profile = self.profile()?;
+                let color = match color.first() {
+                    Some(&"white") => Color::White,
+                    Some(&"black") => Color::Black,
+                    Some(_) => return Err(Error::InvalidInput),
+                    None => profile.color.unwrap_or(Color::White),
+                };
+                let name = profile.name.clone();
+
+                let skill = self.engines.builtin.skill();
+                let engine = format!("chess-lib level {}", skill);
+                let mut game = self.config.new_game()?;
+                match color {
+                    Color::White => game.set_players(&name, &engine),
+                    Color::Black => game.set_players(&engine, &name),
+                }
+                game.set_tag("Event", "Rated game");
+
+                *self.games.current_mut() = game;
+                self.markup.clear();
+                self.rated = Some(Rated {
+                    game: self.games.current_name().to_string(),
+                    color,
+                    opponent: engine_rating(skill),
+                });
+
+                Ok(Some(format!(
+                    "{} as {} against level {} ({})",
+                    name,
+                    color,
+                    skill,
+                    engine_rating(skill)
+                )))
+            }
+            ("profile", []) => {
+                let profile = self.profile()?;
+                let (won, drawn, lost) = profile.record();
+                let mut answer = format!(
+                    "{} {}, {} games: {} won {} drawn {} lost",
+                    profile.name,
+                    profile.rating,
+                    profile.games.len(),
+                    won,
+                    drawn,
+                    lost
+                );
+                if let Some(color) = profile.color {
+                    answer.push_str(&format!(", prefers {}", color));
+                }
+
+                Ok(Some(answer))
+            }
+            ("profile", ["name", name]) => {
+                let profile = self.profile()?;
+                profile.name = name.to_string();
+                profile.save()?;
+                Ok(None)
+            }
+            ("profile", ["color", color]) => {
+                let profile = self.profile()?;
+                profile.color = Some(match *color {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(Error::InvalidInput),
+                });
+                profile.save()?;
+                Ok(None)
+            }
+            ("level", []) => Ok(Some(self.engines.builtin.skill().to_string())),
+            ("level", [skill]) => {
+                let level: u8 = skill.parse().map_err(|_| Error::InvalidInput)?;
+                if !(1..=MAX_SKILL).contains(&level) {
+                    return Err(Error::InvalidInput);
+                }
+
+                self.engines.active_mut().set_option("Skill Level", skill)?;
+                Ok(None)
+            }
+            ("seed", []) => Ok(Some(
+                self.seed.map_or("off".to_string(), |seed| seed.to_string()),
+            )),
+            ("seed", [seed]) => {
+                self.seed = match *seed {
+                    "off" => None,
+                    seed => Some(seed.parse().map_err(|_| Error::InvalidInput)?),
+                };
+                self.engines.builtin.set_seed(self.seed);
+                Ok(None)
+            }
+            ("go", []) => {
+                let board = self.games.current().board();
+
+                // a book move when the book knows the position
+                let booked = self
+                    .book
+                    .as_ref()
+                    .and_then(|book| book.pick(board, &mut Rng::new(self.seed())));
+                if let Some(m) = booked {
+                    return self.play(&String::from(m));
+                }
+
+                // on a ponder hit the answer is ready, or nearly so
+                let hit = self
+                    .engines
+                    .pondering
+                    .take()
+                    .filter(|ponder| self.engines.active.is_none() && ponder.is_hit(board))
+                    .and_then(Ponder::finish);
+                let evaluation = match hit {
+                    Some(evaluation) => evaluation,
+                    None => self.engines.active_mut().evaluate(board)?,
+                };
+
+                let m = evaluation.line.first().ok_or(Error::InvalidInput)?;
+                let answer = self.play(&String::from(*m))?;
+
+                if let Some(notifier) = self.notifier.as_mut() {
+                    notifier.notify(&String::from(*m))?;
+                }
+
+                let game = self.games.current();
+                if let Some(&reply) = evaluation.line.get(1) {
+                    if self.ponder && self.engines.active.is_none() && !game.is_over() {
+                        let engine = self.engines.builtin;
+                        self.engines.pondering =
+                            Some(Ponder::start(engine, game.board(), reply, engine.limits())?);
+                    }
+                }
+
+                Ok(answer)
+            }
+            ("see", [target]) => {
+                let board = self.games.current().board();
+                let value = match parse_square(target) {
+                    Ok(square) => see_square(board, square),
+                    Err(_) => {
+                        let m = match Move::parse(board, target) {
+                            Ok(m) => m,
+                            Err(_) => self.config.locale.parse_san(board, target)?,
+                        };
+                        see(board, &m)
+                    }
+                };
+
+                Ok(Some(value.to_string()))
+            }
+            ("mobility", []) => {
+                let board = self.games.current().board();
+                let mut answer = format!(
+                    "white {} black {}",
+                    board.mobility(Color::White),
+                    board.mobility(Color::Black)
+                );
+
+                let trapped: Vec<String> = [Color::White, Color::Black]
+                    .into_iter()
+                    .flat_map(|color| board.trapped(color))
+                    .map(|square| square.name())
+                    .collect();
+                if !trapped.is_empty() {
+                    answer.push_str(&format!(", trapped {}", trapped.join(" ")));
+                }
+
+                Ok(Some(answer))
+            }
+            ("mobility", [square]) => {
+                let board = self.games.current().board();
+                let squares: Vec<String> = board
+                    .destinations(parse_square(square)?)
+                    .into_iter()
+                    .map(|at| at.name())
+                    .collect();
+
+                Ok(Some(squares.join(" ")))
+            }
+            ("tablebase", []) => {
+                let evaluation =
+                    tablebase::evaluate(self.games.current().board()).ok_or(Error::InvalidInput)?;
+
+                Ok(Some(match evaluation.mate_in() {
+                    Some(moves) if moves > 0 => format!("white mates in {}", moves),
+                    Some(moves) => format!("black mates in {}", -moves),
+                    None => "draw".to_string(),
+                }))
+            }
+            ("hint", []) => {
+                let board = self.games.current().board();
+                let m = self
+                    .engines
+                    .active_mut()
+                    .best_move(board)?
+                    .ok_or(Error::InvalidInput)?;
+
+                Ok(Some(self.config.locale.san(board, &m)?))
+            }
+            ("analyze", ["on"]) => {
+                // an external engine in use plays, so another one of it
+                // analyzes
+                let engine: Box<dyn Backend> = match self.engines.active {
+                    Some(i) => {
+                        let playing = &self.engines.external[i].1;
+                        let mut engine = UciEngine::spawn(playing.path())?;
+                        engine.set_limits(playing.limits());
+                        Box::new(engine)
+                    }
+                    None => Box::new(self.engines.builtin),
+                };
+
+                self.games.current_mut().set_analysis(Some(engine));
+                Ok(None)
+            }
+            ("analyze", ["off"]) => {
+                self.games.current_mut().set_analysis(None);
+                Ok(None)
+            }
+            ("book", []) => {
+                let book = self.book.as_ref().ok_or(Error::InvalidInput)?;
+                let board = self.games.current().board();
+
+                let moves = book.probe(board);
+                let total: u32 = moves.iter().map(|(_, weight)| u32::from(*weight)).sum();
+                let moves = moves
+                    .iter()
+                    .map(|(m, weight)| {
+                        Ok(format!(
+                            "{} {}%",
+                            self.config.locale.san(board, m)?,
+                            u32::from(*weight) * 100 / total.max(1)
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(Some(moves.join(", ")))
+            }
+            ("book", ["off"]) => {
+                self.book = None;
+                Ok(None)
+            }
+            ("book", [file]) => {
+                let book = Book::open(file)?;
+                let entries = book.len();
+                self.book = Some(book);
+                Ok(Some(format!("{} entries", entries)))
+            }
+            ("bench", depths) if depths.len() <= 2 => {
+                let depth = |i: usize, default: u32| match depths.get(i) {
+                    Some(depth) => match depth.parse() {
+                        Ok(depth) if depth > 0 => Ok(depth),
+                        _ => Err(Error::InvalidInput),
+                    },
+                    None => Ok(default),
+                };
+
+                let report = bench::run(depth(0, 3)?, depth(1, 4)?)?;
+                Ok(Some(report.to_string().replace('\n', "; ")))
+            }
+            ("annotate", [file, pgn]) => {
+                let report = annotate_file(file, self.engines.active_mut())?;
+                report.save(pgn)?;
+
+                let counts = [Color::White, Color::Black].map(|color| {
+                    format!(
+                        "{} {} inaccuracies {} mistakes {} blunders",
+                        color,
+                        report.count(color, Judgement::Inaccuracy),
+                        report.count(color, Judgement::Mistake),
+                        report.count(color, Judgement::Blunder)
+                    )
+                });
+                Ok(Some(counts.join(", ")))
+            }
+            ("tune", [samples, params, passes @ ..]) if passes.len() <= 1 => {
+                let passes = match passes.first() {
+                    Some(passes) => passes.parse().map_err(|_| Error::InvalidInput)?,
+                    None => 10,
+                };
+                let samples = tune::load_samples(samples)?;
+                if samples.is_empty() {
+                    return Err(Error::InvalidInput);
+                }
+
+                let start = self.engines.builtin.params();
+                let k = tune::best_k(&samples, start);
+                let tuned = tune::tune(&samples, start, passes);
+                tuned.save(params)?;
+                self.engines.builtin.load_params(params)?;
+
+                Ok(Some(format!(
+                    "{} positions, error {:.5} to {:.5}",
+                    samples.len(),
+                    tune::error(&samples, start, k),
+                    tune::error(&samples, &tuned, k)
+                )))
+            }
+            ("analyze", lines) if lines.len() <= 1 => {
+                let lines = match lines.first() {
+                    Some(n) => n
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or(Error::InvalidInput)?,
+                    None => AnalysisOptions::default().lines,
+                };
+                let engine = self.engines.active_mut();
+                let options = AnalysisOptions {
+                    lines,
+                    limits: engine.limits(),
+                };
+
+                let board = self.games.current().board();
+                let ranked = engine
+                    .analyze_lines(board, options)?
+                    .iter()
+                    .map(|line| {
+                        Ok(format!(
+                            "{}) {} {}",
+                            line.rank,
+                            line.evaluation,
+                            san_line(self.config.locale, board, &line.evaluation.line)?
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                Ok(Some(ranked.join("; ")))
+            }
+            ("search-tree", [file, plies @ ..]) if plies.len() <= 1 => {
+                let plies = match plies.first() {
+                    Some(n) => n.parse().map_err(|_| Error::InvalidInput)?,
+                    None => 2,
+                };
+
+                let engine = self.engines.builtin;
+                let board = self.games.current().board();
+                let (evaluation, tree) = engine.search_tree(board, &engine.limits(), plies);
+
+                tree.ok_or(Error::InvalidInput)?.save(file)?;
+                Ok(Some(evaluation.to_string()))
+            }
+            ("engine", []) => {
+                let mut names = vec!["builtin".to_string()];
+                names.extend(self.engines.external.iter().map(|(name, _)| name.clone()));
+
+                let active = self.engines.active.map_or(0, |i| i + 1);
+                names[active].push('*');
+                Ok(Some(names.join(" ")))
+            }
+            ("engine", ["add", name, path]) => {
+                if *name == "builtin" || self.engines.external.iter().any(|(n, _)| n == name) {
+                    return Err(Error::InvalidInput);
+                }
+
+                let engine = UciEngine::spawn(path)?;
+                let id = engine.name().to_string();
+                self.engines.external.push((name.to_string(), engine));
+                self.engines.active = Some(self.engines.external.len() - 1);
+                Ok(Some(id))
+            }
+            ("engine", ["use", "builtin"]) => {
+                self.engines.active = None;
+                Ok(None)
+            }
+            ("engine", ["use", name]) => {
+                let i = self
+                    .engines
+                    .external
+                    .iter()
+                    .position(|(n, _)| n == name)
+                    .ok_or(Error::InvalidInput)?;
+                self.engines.active = Some(i);
+                Ok(None)
+            }
+            // option names may have spaces, so the value is the last word
+            ("engine", ["set", option @ .., value]) if !option.is_empty() => {
+                let i = self.engines.active.ok_or(Error::InvalidInput)?;
+                self.engines.external[i]
+                    .1
+                    .set_option(&option.join(" "), value)?;
+                Ok(None)
+            }
+            ("suite", [file, depth @ ..]) if depth.len() <= 1 => {
+                let records = epd::load(file)?;
+                let engine = self.engines.active_mut();
+                let limits = match depth.first() {
+                    Some(depth) => match depth.parse() {
+                        Ok(depth) if depth > 0 => Limits::depth(depth),
+                        _ => return Err(Error::InvalidInput),
+                    },
+                    None => engine.limits(),
+                };
+
+                Ok(Some(epd::run_suite(&records, engine, &limits)?.to_string()))
+            }
+            ("match", [first, second, games, rest @ ..]) => {
+                let depth = |depth: &str| match depth.parse::<u32>() {
+                    Ok(depth) if depth > 0 => Ok(depth),
+                    _ => Err(Error::InvalidInput),
+                };
+                let first = depth(first)?;
+                let second = depth(second)?;
+                let games: usize = games.parse().map_err(|_| Error::InvalidInput)?;
+
+                let (suite, sprt) = match rest {
+                    [suite, sprt @ ..] if *suite != "sprt" => (Some(*suite), sprt),
+                    sprt => (None, sprt),
+                };
+                let number = |value: &str| value.parse::<f64>().map_err(|_| Error::InvalidInput);
+                let sprt = match sprt {
+                    [] => None,
+                    ["sprt", elo0, elo1, errors @ ..] if errors.len() <= 2 => {
+                        let mut sprt = Sprt::new(number(elo0)?, number(elo1)?);
+                        if let Some(alpha) = errors.first() {
+                            sprt.alpha = number(alpha)?;
+                        }
+                        if let Some(beta) = errors.get(1) {
+                            sprt.beta = number(beta)?;
+                        }
+                        let rate = |rate: f64| rate > 0.0 && rate < 0.5;
+                        if !rate(sprt.alpha) || !rate(sprt.beta) {
+                            return Err(Error::InvalidInput);
+                        }
+                        Some(sprt)
+                    }
+                    _ => return Err(Error::InvalidInput),
+                };
+
+                let openings = match suite {
+                    Some(suite) => load_openings(suite)?,
+                    None => Vec::new(),
+                };
+                let openings: Vec<&str> = openings.iter().map(String::as_str).collect();
+
+                // under a test the answer follows the standings game by game
+                let mut standings = Vec::new();
+                let mut progress = |report: &MatchReport| {
+                    if report.sprt.is_some() {
+                        standings.push(format!("game {}: {}", report.games.len(), report));
+                    }
+                };
+                let report = play_match(
+                    &Contender::new(&format!("depth {}", first), Engine::new(first)),
+                    &Contender::new(&format!("depth {}", second), Engine::new(second)),
+                    games,
+                    MatchOptions {
+                        openings: &openings,
+                        adjudication: Some(Adjudication::default()),
+                        sprt,
+                        progress: Some(&mut progress),
+                    },
+                )?;
+
+                // the standings after the last game are the final ones
+                if standings.is_empty() {
+                    standings.push(report.to_string());
+                }
+                Ok(Some(standings.join("; ")))
+            }
+            ("arrow", [from, to, brush @ ..]) => {
+                let brush = brush_of(brush.first())?;
+                self.markup
+                    .arrow(parse_square(from)?, parse_square(to)?, brush)?;
+                Ok(None)
+            }
+            ("mark", [square, brush @ ..]) => {
+                let brush = brush_of(brush.first())?;
+                self.markup.mark(parse_square(square)?, brush);
+                Ok(None)
+            }
+            ("unmark", []) => {
+                self.markup.clear();
+                Ok(None)
+            }
+            ("heatmap", []) => {
+                let heatmap = Heatmap::new(self.games.current().board());
+                self.markup = heatmap.to_markup();
+
+                Ok(Some(format!(
+                    "white {} black {}",
+                    heatmap.controlled(Color::White).len(),
+                    heatmap.controlled(Color::Black).len()
+                )))
+            }
+            ("teach", ["on"]) => {
+                self.teach = true;
+                Ok(None)
+            }
+            ("teach", ["off"]) => {
+                self.teach = false;
+                Ok(None)
+            }
+            ("announce", []) => Ok(Some(match &self.announcer {
+                None => "off".to_string(),
+                Some(Announcer::Print) => "on".to_string(),
+                Some(Announcer::Command(command)) => command.clone(),
+            })),
+            ("announce", ["on"]) => {
+                self.announcer = Some(Announcer::Print);
+                Ok(None)
+            }
+            ("announce", ["off"]) => {
+                self.announcer = None;
+                Ok(None)
+            }
+            ("announce", command) => {
+                self.announcer = Some(Announcer::Command(command.join(" ")));
+                Ok(None)
+            }
+            ("notify", []) => Ok(Some(match &self.notifier {
+                None => "off".to_string(),
+                Some(Notifier::Bell) => "bell".to_string(),
+                Some(Notifier::Command(command)) => command.clone(),
+                Some(Notifier::Callback(_)) => "callback".to_string(),
+            })),
+            ("notify", ["off"]) => {
+                self.notifier = None;
+                Ok(None)
+            }
+            ("notify", ["bell"]) => {
+                self.notifier = Some(Notifier::Bell);
+                Ok(None)
+            }
+            ("notify", command) => {
+                self.notifier = Some(Notifier::Command(command.join(" ")));
+                Ok(None)
+            }
+            ("ponder", []) => Ok(self.engines.pondering.as_ref().and_then(|ponder| {
+                let board = self.games.current().board();
+                self.config.locale.san(board, &ponder.expected()).ok()
+            })),
+            ("ponder", ["on"]) => {
+                self.ponder = true;
+                Ok(None)
+            }
+            ("ponder", ["off"]) => {
+                self.ponder = false;
+                self.engines.pondering = None;
+                Ok(None)
+            }
+            ("autoflip", ["on"]) => {
+                self.autoflip = true;
+                Ok(None)
+            }
+            ("autoflip", ["off"]) => {
+                self.autoflip = false;
+                Ok(None)
+            }
+            ("blindfold", ["on"]) => {
+                self.blindfold.get_or_insert_with(Blindfold::new);
+                Ok(None)
+            }
+            ("blindfold", ["off"]) => match self.blindfold.take() {
+                Some(blindfold) => Ok(Some(format!("{} peeks", blindfold.peeks()))),
+                None => Ok(None),
+            },
+            ("drill", ["stop"]) => {
+                let drill = self.drill.take().ok_or(Error::InvalidInput)?;
+                self.markup.clear();
+                Ok(Some(drill.score().to_string()))
+            }
+            ("drill", [kind, seconds @ ..]) => {
+                let limit = match seconds.first() {
+                    Some(seconds) => {
+                        let seconds: f64 = seconds.parse().map_err(|_| Error::InvalidInput)?;
+                        Some(
+                            Duration::try_from_secs_f64(seconds)
+                                .map_err(|_| Error::InvalidInput)?,
+                        )
+                    }
+                    None => None,
+                };
+
+                let drill = CoordinateDrill::new(Drill::try_from(*kind)?, limit, self.seed());
+                self.markup = drill.markup();
+                let prompt = drill.prompt();
+                self.drill = Some(drill);
+
+                Ok(Some(prompt))
+            }
+            ("answer", [square]) => {
+                let drill = self.drill.as_mut().ok_or(Error::InvalidInput)?;
+                let answer = drill.answer_name(square)?;
+                self.markup = drill.markup();
+
+                Ok(Some(format!("{}; {}", answer, drill.prompt())))
+            }
+            ("repertoire", ["stop"]) => {
+                let trainer = self.repertoire.take().ok_or(Error::InvalidInput)?;
+                let repertoire = trainer.repertoire();
+                repertoire.save()?;
+
+                Ok(Some(format!(
+                    "{}/{} lines mastered",
+                    repertoire.mastered(),
+                    repertoire.lines().len()
+                )))
+            }
+            ("repertoire", [filename, color]) => {
+                let color = match *color {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(Error::InvalidInput),
+                };
+
+                let mut trainer = Trainer::new(Repertoire::load(filename, color)?)?;
+                let repertoire = trainer.repertoire();
+                let status = format!(
+                    "{} lines, {} mastered",
+                    repertoire.lines().len(),
+                    repertoire.mastered()
+                );
+                let opening = opponent_moves(self.config.locale, &mut trainer)?;
+                self.repertoire = Some(trainer);
+
+                Ok(Some(format!("{}; {}", status, opening)))
+            }
+            ("reply", [m]) => {
+                let trainer = self.repertoire.as_mut().ok_or(Error::InvalidInput)?;
+                let board = trainer.board();
+                let m = match Move::parse(board, m) {
+                    Ok(m) => m,
+                    Err(_) => self.config.locale.parse_san(board, m)?,
+                };
+
+                let board = board.clone();
+                let attempt = trainer.play(m)?;
+
+                let played = attempt.expected.unwrap_or(m);
+                let mut answer = match attempt.expected {
+                    Some(expected) => format!(
+                        "wrong, prepared {}",
+                        self.config.locale.san(&board, &expected)?
+                    ),
+                    None => "correct".to_string(),
+                };
+
+                if let Some(reply) = attempt.reply {
+                    let after = board.with_move(&played)?;
+                    answer.push_str(&format!("; {}", self.config.locale.san(&after, &reply)?));
+                }
+
+                if attempt.finished {
+                    let repertoire = trainer.repertoire();
+                    repertoire.save()?;
+                    answer.push_str(&format!(
+                        "; line done, {}/{} mastered; next line: {}",
+                        repertoire.mastered(),
+                        repertoire.lines().len(),
+                        opponent_moves(self.config.locale, trainer)?
+                    ));
+                }
+
+                Ok(Some(answer))
+            }
+            ("puzzle", ["stop"]) => {
+                let puzzles = self.puzzles.take().ok_or(Error::InvalidInput)?;
+                Ok(Some(puzzles.score.to_string()))
+            }
+            ("puzzle", [file]) => {
+                let puzzles = puzzle::load(file)?;
+                let first = puzzles.first().ok_or(Error::InvalidInput)?.clone();
+
+                let mut puzzles = Puzzles {
+                    session: Session::new(first)?,
+                    puzzles,
+                    index: 0,
+                    score: Score::default(),
+                };
+                puzzles.start(0, self.engines.builtin)?;
+
+                let prompt = puzzles.prompt();
+                self.puzzles = Some(puzzles);
+                Ok(Some(prompt))
+            }
+            ("solve", [m]) => {
+                let engine = self.engines.builtin;
+                let puzzles = self.puzzles.as_mut().ok_or(Error::InvalidInput)?;
+
+                let attempt = puzzles.session.try_move(m)?;
+                puzzles.score.record(attempt);
+
+                let answer = match attempt {
+                    Attempt::Correct(reply) => return Ok(Some(format!("correct, {}", reply))),
+                    Attempt::Solved => "solved".to_string(),
+                    Attempt::Failed => {
+                        let solution: Vec<String> = puzzles
+                            .session
+                            .puzzle()
+                            .solution
+                            .iter()
+                            .map(|m| m.to_string())
+                            .collect();
+                        format!("failed, the solution is {}", solution.join(" "))
+                    }
+                };
+
+                // on to the next puzzle, or the score after the last one
+                let next = puzzles.index + 1;
+                if next < puzzles.puzzles.len() {
+                    puzzles.start(next, engine)?;
+                    return Ok(Some(format!("{}; {}", answer, puzzles.prompt())));
+                }
+
+                let score = puzzles.score;
+                self.puzzles = None;
+                Ok(Some(format!("{}; {}", answer, score)))
+            }
+            _ => Err(Error::InvalidInput),
+        }
+    }
+
+    /// Plays a move in coordinate, long algebraic or ICCF notation, falling
+    /// back to SAN. A pawn move to the last rank without a promotion piece
+    /// asks for one. In teaching mode a rejected move is explained and drawn
+    /// in place of the markup.
+    fn play(&mut self, m: &str) -> Result<Option<String>, Error> {
+        let locale = self.config.locale;
+        let board = self.games.current().board();
+        let m = match Move::parse(board, m) {
+            Ok(m) => m,
+            Err(_) => match locale.parse_san(board, m) {
+                // SAN leaving out the promotion piece asks for it as well
+                Err(Error::InvalidMove(MoveError::PromotionRequired)) => {
+                    let queen = format!("{}=Q", m.trim_end_matches(['+', '#', '!', '?']));
+                    Move {
+                        promotion: None,
+                        ..locale.parse_san(board, &queen)?
+                    }
+                }
+                m => m?,
+            },
+        };
+
+        let game = self.games.current();
+        if m.promotion.is_none()
+            && !game.is_over()
+            && game.board().is_promotion(&m)
+            && game.board().is_legal(&m.with_promotion(Class::Queen))
+        {
+            self.promotion = Some(m);
+
+            let letters = self.config.locale.letters();
+            let choices = [3, 2, 1, 0].map(|i| letters[i].to_ascii_lowercase().to_string());
+            return Ok(Some(
+                self.config
+                    .locale
+                    .text(Message::PromoteTo, &[&choices.join("/")]),
+            ));
+        }
+
+        if self.teach && !self.games.current().is_over() {
+            match explain(self.games.current().board(), &m) {
+                Some(lesson) => {
+                    self.markup = lesson.markup.clone();
+                    self.lesson = Some(lesson);
+                }
+                // the drawing of the last lesson goes with the next move
+                None => self.markup.clear(),
+            }
+        }
+
+        // the peer checks the move first, which it rejects when it is illegal
+        if let Some(connection) = self.connection.as_mut() {
+            connection.send_move(&String::from(m))?;
+        }
+        let outcome = self.games.current_mut().move_piece(&String::from(m))?;
+
+        if let Some(path) = &self.config.autosave {
+            std::fs::write(path, self.games.current().to_pgn()?)?;
+        }
+
+        // any other move than the expected one makes pondering useless
+        let board = self.games.current().board();
+        if let Some(ponder) = self
+            .engines
+            .pondering
+            .take_if(|ponder| !ponder.is_hit(board))
+        {
+            ponder.cancel();
+        }
+
+        let mut answer = String::from(m);
+        answer.extend(outcome.check_suffix());
+        let game = self.games.current();
+        if let Some(analysis) = game.history().last().and_then(|m| m.analysis.as_ref()) {
+            answer.push_str(&format!(
+                "; {} {}",
+                analysis.after,
+                san_line(self.config.locale, game.board(), &analysis.after.line)?
+            ));
+            if analysis.is_blunder() {
+                answer.push_str(", blunder");
+            }
+        }
+        if let Some(rating) = self.settle_rated()? {
+            answer.push_str(&format!(", {}", rating));
+        }
+        if let Some(announcer) = &self.announcer {
+            if let Some(sentence) = announcer.announce(&outcome)? {
+                answer.push_str(&format!("; {}", sentence));
+            }
+        }
+
+        if self.connection.is_some() {
+            answer.push_str(&self.wait_for_peer()?);
+        }
+
+        Ok(Some(answer))
+    }
+
+    /// Formats the outcome of a command, which is unnamed when the line
+    /// could not be parsed, along with the lesson on a rejected move
+    fn answer(
+        &self,
+        name: Option<&str>,
+        outcome: Result<Option<String>, Error>,
+        lesson: Option<Lesson>,
+    ) -> String {
+        match (self.json, outcome) {
+            (false, Ok(Some(value))) => format!("ok {}", value),
+            (false, Ok(None)) => "ok".to_string(),
+            (false, Err(e)) => match lesson {
+                Some(lesson) => format!("error {}. {}", self.config.locale.error(&e), lesson.text),
+                None => format!("error {}", self.config.locale.error(&e)),
+            },
+            (true, Ok(value)) => {
+                let value = match value {
+                    // the state already is JSON, so it goes in as an object
+                    Some(state) if name == Some("state") => state,
+                    Some(value) => quote(&value),
+                    None => "null".to_string(),
+                };
+
+                format!(
+                    "{{\"command\":{},\"ok\":true,\"value\":{}}}",
+                    name.map_or("null".to_string(), quote),
+                    value
+                )
+            }
+            (true, Err(e)) => format!(
+                "{{\"command\":{},\"ok\":false,\"error\":{}{}}}",
+                name.map_or("null".to_string(), quote),
+                quote(&self.config.locale.error(&e)),
+                lesson.map_or(String::new(), |l| format!(
+                    ",\"explanation\":{}",
+                    quote(&l.text)
+                ))
+            ),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Static exchange evaluation: what a series of captures on one square wins
+//! or loses when both sides recapture with their least valuable piece and
+//! stop as soon as going on would cost them
+
+use crate::{
+    chess::{Board, Class, Color, Square, KNIGHT_OFFSETS},
+    geometry::{ray, Direction, SquareGeometry},
+    mover::Move,
+};
+
+/// The pieces on the board by file and rank, which the exchange takes off
+/// one by one
+type Grid = [[Option<(Color, Class)>; 8]; 8];
+
+/// Values of the pieces in centipawns. The king is worth more than any
+/// exchange, so it only captures when nothing can take it back.
+pub fn value(class: Class) -> i32 {
+    match class {
+        Class::Pawn => 100,
+        Class::Knight => 320,
+        Class::Bishop => 330,
+        Class::Rook => 500,
+        Class::Queen => 900,
+        Class::King => 10_000,
+    }
+}
+
+fn grid(board: &Board) -> Grid {
+    let mut grid = [[None; 8]; 8];
+
+    for (file, column) in grid.iter_mut().enumerate() {
+        for (rank, square) in column.iter_mut().enumerate() {
+            *square = board
+                .get_piece(file, rank)
+                .map(|piece| (piece.color, piece.class));
+        }
+    }
+
+    grid
+}
+
+/// Returns what the move wins for the side playing it, in centipawns, once
+/// all the captures worth making on its square are made. A quiet move
+/// scores what it loses by standing on an attacked square.
+pub fn see(board: &Board, m: &Move) -> i32 {
+    let mut grid = grid(board);
+    let to = (m.to_file, m.to_rank);
+    let side = board.turn();
+
+    let mut class = match m.drop {
+        Some(class) => class,
+        None => match grid[m.from_file][m.from_rank].take() {
+            Some((_, class)) => class,
+            None => return 0,
+        },
+    };
+
+    let mut gain = grid[to.0][to.1].map_or(0, |(_, victim)| value(victim));
+
+    // en passant takes a pawn beside the square moved to
+    if class == Class::Pawn && m.drop.is_none() && m.from_file != m.to_file && gain == 0 {
+        grid[m.to_file][m.from_rank] = None;
+        gain = value(Class::Pawn);
+    }
+
+    if let Some(promotion) = m.promotion {
+        gain += value(promotion) - value(Class::Pawn);
+        class = promotion;
+    }
+
+    grid[to.0][to.1] = Some((side, class));
+    gain - exchange(&mut grid, to, side.opponent())
+}
+
+/// Returns what the side to move wins by starting an exchange on the
+/// square, which is nothing when the square is empty, holds one of its own
+/// pieces or capturing would lose material
+pub fn see_square(board: &Board, square: Square) -> i32 {
+    let mut grid = grid(board);
+
+    match grid[square.0][square.1] {
+        Some((color, _)) if color != board.turn() => exchange(&mut grid, square, board.turn()),
+        _ => 0,
+    }
+}
+
+/// What `side` gains by capturing on the square, taking into account every
+/// recapture after it, or nothing when it is better off not capturing
+fn exchange(grid: &mut Grid, square: Square, side: Color) -> i32 {
+    let Some((from, class)) = least_attacker(grid, square, side) else {
+        return 0;
+    };
+    let Some((_, victim)) = grid[square.0][square.1] else {
+        return 0;
+    };
+
+    grid[from.0][from.1] = None;
+    grid[square.0][square.1] = Some((side, class));
+
+    (value(victim) - exchange(grid, square, side.opponent())).max(0)
+}
+
+/// Returns the least valuable piece of a color attacking the square, seeing
+/// through the pieces the exchange already took off
+fn least_attacker(grid: &Grid, square: Square, by: Color) -> Option<(Square, Class)> {
+    let is = |at: Square, class: Class| grid[at.0][at.1] == Some((by, class));
+    let mut best: Option<(Square, Class)> = None;
+    let mut consider = |at: Square, class: Class| {
+        if best.is_none_or(|(_, least)| value(class) < value(least)) {
+            best = Some((at, class));
+        }
+    };
+
+    // pawns attack diagonally forward, so look one rank behind the square
+    let behind = match by {
+        Color::White => [Direction::SouthWest, Direction::SouthEast],
+        Color::Black => [Direction::NorthWest, Direction::NorthEast],
+    };
+    for direction in behind {
+        if let Some(at) = square.step(direction).filter(|at| is(*at, Class::Pawn)) {
+            consider(at, Class::Pawn);
+        }
+    }
+
+    for (df, dr) in KNIGHT_OFFSETS {
+        let (f, r) = (square.0 as i32 + df, square.1 as i32 + dr);
+        if (0..8).contains(&f) && (0..8).contains(&r) && is((f as usize, r as usize), Class::Knight)
+        {
+            consider((f as usize, r as usize), Class::Knight);
+        }
+    }
+
+    for direction in Direction::ALL {
+        let Some(at) = ray(square, direction)
+            .into_iter()
+            .find(|at| grid[at.0][at.1].is_some())
+        else {
+            continue;
+        };
+        let Some((_, class)) = grid[at.0][at.1].filter(|(color, _)| *color == by) else {
+            continue;
+        };
+
+        let slides = match direction.is_diagonal() {
+            true => [Class::Bishop, Class::Queen].contains(&class),
+            false => [Class::Rook, Class::Queen].contains(&class),
+        };
+        let adjacent = square.chebyshev_distance(at) == 1;
+
+        if slides || (adjacent && class == Class::King) {
+            consider(at, class);
+        }
+    }
+
+    best
+}
@@ -0,0 +1,205 @@
+//! A summary of a game: what each side did and how the material went
+
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    chess::{Board, Color, Error},
+    game::Game,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Castled {
+    Kingside,
+    Queenside,
+}
+
+impl Display for Castled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Castled::Kingside => write!(f, "O-O"),
+            Castled::Queenside => write!(f, "O-O-O"),
+        }
+    }
+}
+
+/// What one side did over a game
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SideStatistics {
+    pub moves: usize,
+    pub captures: usize,
+    /// Moves giving check
+    pub checks: usize,
+    pub promotions: usize,
+    pub castled: Option<Castled>,
+    /// Time spent on the moves timed by a clock
+    pub time: Duration,
+    pub timed_moves: usize,
+    /// The ply and duration of the longest think
+    pub longest_think: Option<(usize, Duration)>,
+}
+
+impl SideStatistics {
+    /// Returns the average time spent per move, when the moves were timed
+    pub fn average_time(&self) -> Option<Duration> {
+        match self.timed_moves {
+            0 => None,
+            n => Some(self.time / n as u32),
+        }
+    }
+}
+
+/// A summary of a game, see `Game::statistics`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub white: SideStatistics,
+    pub black: SideStatistics,
+    /// White's material minus black's in pawns, for the start position and
+    /// after every move
+    pub material: Vec<i32>,
+}
+
+fn balance(board: &Board) -> i32 {
+    board.material(Color::White).value() as i32 - board.material(Color::Black).value() as i32
+}
+
+impl Statistics {
+    /// Replays a game to sum it up
+    pub fn of(game: &Game) -> Result<Statistics, Error> {
+        let mut board = Board::new()?;
+        board.set_variant(game.board().variant());
+        board.from_fen(game.start_fen())?;
+
+        let mut statistics = Statistics {
+            white: SideStatistics::default(),
+            black: SideStatistics::default(),
+            material: vec![balance(&board)],
+        };
+
+        for (ply, entry) in game.history().iter().enumerate() {
+            let outcome = board.move_piece(&entry.m.to_string())?;
+            let side = statistics.side_mut(entry.color);
+
+            side.moves += 1;
+            side.captures += outcome.is_capture() as usize;
+            side.checks += outcome.check as usize;
+            side.promotions += outcome.promotion.is_some() as usize;
+
+            if outcome.castled {
+                side.castled = Some(match entry.m.to_file {
+                    6 => Castled::Kingside,
+                    _ => Castled::Queenside,
+                });
+            }
+
+            if let Some(think) = entry.think {
+                side.time += think;
+                side.timed_moves += 1;
+
+                if side
+                    .longest_think
+                    .is_none_or(|(_, longest)| think > longest)
+                {
+                    side.longest_think = Some((ply + 1, think));
+                }
+            }
+
+            statistics.material.push(balance(&board));
+        }
+
+        Ok(statistics)
+    }
+
+    pub fn side(&self, color: Color) -> &SideStatistics {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    fn side_mut(&mut self, color: Color) -> &mut SideStatistics {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    /// Returns the largest lead in material each side had, white's first
+    pub fn largest_leads(&self) -> (i32, i32) {
+        let white = self.material.iter().copied().max().unwrap_or(0).max(0);
+        let black = -self.material.iter().copied().min().unwrap_or(0).min(0);
+        (white, black)
+    }
+
+    /// Writes the summary on one line, to go in a PGN comment
+    pub fn to_comment(&self) -> String {
+        let side = |color: Color| {
+            let s = self.side(color);
+            let mut parts = vec![
+                format!("{} captures", s.captures),
+                format!("{} checks", s.checks),
+            ];
+
+            if let Some(castled) = s.castled {
+                parts.push(castled.to_string());
+            }
+            if let Some(average) = s.average_time() {
+                parts.push(format!("{:.1}s per move", average.as_secs_f64()));
+            }
+
+            format!("{}: {}", color, parts.join(", "))
+        };
+
+        format!(
+            "{}; {}; material {:+}",
+            side(Color::White),
+            side(Color::Black),
+            self.material.last().copied().unwrap_or(0)
+        )
+    }
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for color in [Color::White, Color::Black] {
+            let s = self.side(color);
+
+            writeln!(
+                f,
+                "{}: {} moves, {} captures, {} checks, {} promotions, castled {}",
+                color,
+                s.moves,
+                s.captures,
+                s.checks,
+                s.promotions,
+                s.castled.map_or("never".to_string(), |c| c.to_string())
+            )?;
+
+            if let (Some(average), Some((ply, longest))) = (s.average_time(), s.longest_think) {
+                writeln!(
+                    f,
+                    "  {:.1}s per move, longest think {:.1}s at ply {}",
+                    average.as_secs_f64(),
+                    longest.as_secs_f64(),
+                    ply
+                )?;
+            }
+        }
+
+        // the trajectory only lists the plies where the balance changed
+        let mut changes = vec![format!("{:+}", self.material.first().copied().unwrap_or(0))];
+        for (ply, pair) in self.material.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                changes.push(format!("{:+} at ply {}", pair[1], ply + 1));
+            }
+        }
+
+        let (white, black) = self.largest_leads();
+        write!(
+            f,
+            "material: {} (largest lead white {}, black {})",
+            changes.join(", "),
+            white,
+            black
+        )
+    }
+}
@@ -0,0 +1,236 @@
+//! Board images in SVG, for embedding positions in web pages and chats
+
+use crate::{
+    chess::{Board, Class, Color, Error, Piece},
+    markup::{Brush, Markup},
+};
+
+/// How the pieces are drawn
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum PieceSet {
+    /// The chess symbols of the viewer's font
+    #[default]
+    Unicode,
+    /// Piece letters in a disc of the piece's color, which look the same
+    /// with every font
+    Letters,
+}
+
+impl TryFrom<&str> for PieceSet {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<PieceSet, Error> {
+        match name {
+            "unicode" => Ok(PieceSet::Unicode),
+            "letters" => Ok(PieceSet::Letters),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Colors of the light and dark squares, in any form SVG accepts
+    pub light: String,
+    pub dark: String,
+    /// Width of a square in pixels
+    pub square_size: u32,
+    pub pieces: PieceSet,
+    /// Whether the files and ranks are labeled along the edges
+    pub coordinates: bool,
+    /// Draws the board from black's side
+    pub flipped: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            light: "#f0d9b5".to_string(),
+            dark: "#b58863".to_string(),
+            square_size: 45,
+            pieces: PieceSet::default(),
+            coordinates: true,
+            flipped: false,
+        }
+    }
+}
+
+fn brush_color(brush: Brush) -> &'static str {
+    match brush {
+        Brush::Green => "#15781b",
+        Brush::Red => "#882020",
+        Brush::Yellow => "#e68f00",
+        Brush::Blue => "#003088",
+    }
+}
+
+fn piece_letter(class: Class) -> char {
+    match class {
+        Class::Pawn => 'P',
+        Class::Knight => 'N',
+        Class::Bishop => 'B',
+        Class::Rook => 'R',
+        Class::Queen => 'Q',
+        Class::King => 'K',
+    }
+}
+
+/// Draws the board, with any arrows and highlights, as an SVG document
+pub fn render(board: &Board, markup: &Markup, options: &SvgOptions) -> String {
+    let size = options.square_size as f64;
+    let board_size = size * 8.0;
+
+    // the top left corner of a square, in pixels
+    let corner = |(file, rank): (usize, usize)| match options.flipped {
+        false => (file as f64 * size, (7 - rank) as f64 * size),
+        true => ((7 - file) as f64 * size, rank as f64 * size),
+    };
+    let center = |square| {
+        let (x, y) = corner(square);
+        (x + size / 2.0, y + size / 2.0)
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+        board_size
+    );
+
+    svg.push_str("<defs><marker id=\"head\" viewBox=\"0 0 4 4\" refX=\"2\" refY=\"2\" markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\"><path d=\"M0,0 L4,2 L0,4 z\" fill=\"context-stroke\"/></marker></defs>\n");
+
+    for rank in 0..8 {
+        for file in 0..8 {
+            let (x, y) = corner((file, rank));
+            let color = match (file + rank) % 2 {
+                0 => &options.dark,
+                _ => &options.light,
+            };
+
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x, y, size, size, color
+            ));
+        }
+    }
+
+    for highlight in markup.highlights() {
+        let (x, y) = corner(highlight.square);
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.5\"/>\n",
+            x,
+            y,
+            size,
+            size,
+            brush_color(highlight.brush)
+        ));
+    }
+
+    if options.coordinates {
+        let font = size / 4.0;
+        for i in 0..8 {
+            let (x, _) = corner((i, 0));
+            let (_, y) = corner((0, i));
+            let text = |square: (usize, usize)| match (square.0 + square.1) % 2 {
+                0 => &options.light,
+                _ => &options.dark,
+            };
+
+            let bottom = if options.flipped { 7 } else { 0 };
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" fill=\"{}\">{}</text>\n",
+                x + size - font,
+                board_size - font / 4.0,
+                font,
+                text((i, bottom)),
+                (b'a' + i as u8) as char
+            ));
+
+            let left = if options.flipped { 7 } else { 0 };
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" fill=\"{}\">{}</text>\n",
+                font / 4.0,
+                y + font,
+                font,
+                text((left, i)),
+                i + 1
+            ));
+        }
+    }
+
+    for rank in 0..8 {
+        for file in 0..8 {
+            if let Some(piece) = board.get_piece(file, rank) {
+                svg.push_str(&piece_svg(
+                    &piece,
+                    center((file, rank)),
+                    size,
+                    options.pieces,
+                ));
+            }
+        }
+    }
+
+    for arrow in markup.arrows() {
+        let (x1, y1) = center(arrow.from);
+        let (x2, y2) = center(arrow.to);
+
+        // stop short of the center so the head doesn't cover the square
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let shorten = (size / 3.0) / length;
+        let (x2, y2) = (x2 - (x2 - x1) * shorten, y2 - (y2 - y1) * shorten);
+
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-opacity=\"0.7\" stroke-linecap=\"round\" marker-end=\"url(#head)\"/>\n",
+            x1,
+            y1,
+            x2,
+            y2,
+            brush_color(arrow.brush),
+            size / 6.0
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn piece_svg(piece: &Piece, (x, y): (f64, f64), size: f64, pieces: PieceSet) -> String {
+    match pieces {
+        PieceSet::Unicode => format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+            x,
+            y,
+            size * 0.8,
+            piece
+        ),
+        PieceSet::Letters => {
+            let (fill, ink) = match piece.color {
+                Color::White => ("#ffffff", "#000000"),
+                Color::Black => ("#000000", "#ffffff"),
+            };
+
+            format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#000000\"/>\n<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" font-weight=\"bold\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>\n",
+                x,
+                y,
+                size * 0.4,
+                fill,
+                x,
+                y,
+                size * 0.5,
+                ink,
+                piece_letter(piece.class)
+            )
+        }
+    }
+}
+
+/// Writes the board as an SVG file
+pub fn save(
+    board: &Board,
+    markup: &Markup,
+    options: &SvgOptions,
+    filename: &str,
+) -> Result<(), Error> {
+    std::fs::write(filename, render(board, markup, options))?;
+    Ok(())
+}
@@ -0,0 +1,395 @@
+//! Perfect play for the simplest endings, king and queen or king and rook
+//! against a lone king, worked out backwards from every mate when first
+//! needed instead of read from tablebase files
+
+use std::{collections::VecDeque, sync::OnceLock};
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    engine::{Evaluation, MATE},
+    mover::Move,
+    variant::Variant,
+};
+
+/// The value of a position for the side to move under perfect play
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    /// Mates in this many plies
+    Win(u32),
+    /// Gets mated in this many plies, 0 when already mated
+    Loss(u32),
+    Draw,
+}
+
+impl Outcome {
+    /// The outcome for the other side
+    fn reverse(&self) -> Outcome {
+        match *self {
+            Outcome::Win(plies) => Outcome::Loss(plies),
+            Outcome::Loss(plies) => Outcome::Win(plies),
+            Outcome::Draw => Outcome::Draw,
+        }
+    }
+
+    /// Orders outcomes from the worst to the best for the side to move:
+    /// slower losses beat quicker ones and quicker wins beat slower ones
+    fn rank(&self) -> i64 {
+        match *self {
+            Outcome::Loss(plies) => -1000 + plies as i64,
+            Outcome::Draw => 0,
+            Outcome::Win(plies) => 1000 - plies as i64,
+        }
+    }
+}
+
+/// Marks positions whose outcome isn't known yet, which are draws once the
+/// table is done
+const UNKNOWN: u8 = u8::MAX;
+
+const KING_STEPS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const ROOK_LINES: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const QUEEN_LINES: [(i32, i32); 8] = KING_STEPS;
+
+fn file(square: usize) -> i32 {
+    (square % 8) as i32
+}
+
+fn rank(square: usize) -> i32 {
+    (square / 8) as i32
+}
+
+/// The square a step away, if it is on the board
+fn step(square: usize, (df, dr): (i32, i32)) -> Option<usize> {
+    let (f, r) = (file(square) + df, rank(square) + dr);
+    match (0..8).contains(&f) && (0..8).contains(&r) {
+        true => Some((r * 8 + f) as usize),
+        false => None,
+    }
+}
+
+fn adjacent(a: usize, b: usize) -> bool {
+    a != b && (file(a) - file(b)).abs() <= 1 && (rank(a) - rank(b)).abs() <= 1
+}
+
+/// Every drawn, won and lost position with a king and a queen or rook
+/// against a king, whichever color has the piece
+pub struct Tablebase {
+    class: Class,
+    /// Plies to mate by side to move, strong king, weak king and piece
+    plies: Vec<u8>,
+}
+
+/// A position with the stronger side's king and piece, the weaker side's
+/// king and whether the stronger side is to move
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Position {
+    strong_to_move: bool,
+    king: usize,
+    lone: usize,
+    piece: usize,
+}
+
+impl Position {
+    fn index(&self) -> usize {
+        ((self.strong_to_move as usize * 64 + self.king) * 64 + self.lone) * 64 + self.piece
+    }
+
+    fn from_index(index: usize) -> Position {
+        Position {
+            strong_to_move: index / (64 * 64 * 64) == 1,
+            king: index / (64 * 64) % 64,
+            lone: index / 64 % 64,
+            piece: index % 64,
+        }
+    }
+}
+
+impl Tablebase {
+    /// Works out the table for king and `class` against king, which may be
+    /// a queen or a rook. Takes a moment, so it is best done once.
+    pub fn generate(class: Class) -> Result<Tablebase, Error> {
+        if ![Class::Queen, Class::Rook].contains(&class) {
+            return Err(Error::InvalidInput);
+        }
+
+        let mut table = Tablebase {
+            class,
+            plies: vec![UNKNOWN; 2 * 64 * 64 * 64],
+        };
+        table.solve();
+
+        Ok(table)
+    }
+
+    /// Returns the table for an ending, worked out on first use and kept for
+    /// the rest of the program
+    pub fn shared(class: Class) -> Option<&'static Tablebase> {
+        static QUEEN: OnceLock<Tablebase> = OnceLock::new();
+        static ROOK: OnceLock<Tablebase> = OnceLock::new();
+
+        let cell = match class {
+            Class::Queen => &QUEEN,
+            Class::Rook => &ROOK,
+            _ => return None,
+        };
+        Some(cell.get_or_init(|| Tablebase::generate(class).unwrap()))
+    }
+
+    pub fn class(&self) -> Class {
+        self.class
+    }
+
+    fn lines(&self) -> &'static [(i32, i32)] {
+        match self.class {
+            Class::Queen => &QUEEN_LINES,
+            _ => &ROOK_LINES,
+        }
+    }
+
+    /// Whether the piece attacks the square, with the strong king in the way
+    fn piece_attacks(&self, piece: usize, target: usize, king: usize) -> bool {
+        self.lines().iter().any(|&line| {
+            let mut square = piece;
+            while let Some(next) = step(square, line) {
+                if next == target {
+                    return true;
+                }
+                if next == king {
+                    return false;
+                }
+                square = next;
+            }
+            false
+        })
+    }
+
+    fn is_valid(&self, p: &Position) -> bool {
+        let distinct = p.king != p.lone && p.king != p.piece && p.lone != p.piece;
+
+        // the side not to move can't be in check
+        distinct
+            && !adjacent(p.king, p.lone)
+            && !(p.strong_to_move && self.piece_attacks(p.piece, p.lone, p.king))
+    }
+
+    /// The squares the lone king can move to, taking the piece when the
+    /// strong king doesn't guard it
+    fn lone_moves(&self, p: &Position) -> Vec<usize> {
+        KING_STEPS
+            .iter()
+            .filter_map(|&offset| step(p.lone, offset))
+            .filter(|&to| {
+                !adjacent(to, p.king)
+                    && to != p.king
+                    && (to == p.piece || !self.piece_attacks(p.piece, to, p.king))
+            })
+            .collect()
+    }
+
+    /// Marks every mate, then walks back from the lost positions to the
+    /// won ones before them and from those to the positions where every
+    /// move of the lone king loses
+    fn solve(&mut self) {
+        let mut remaining = vec![0u8; 64 * 64 * 64];
+        let mut queue = VecDeque::new();
+
+        for (index, remaining) in remaining.iter_mut().enumerate() {
+            let p = Position::from_index(index);
+            if !self.is_valid(&p) {
+                continue;
+            }
+
+            let moves = self.lone_moves(&p).len() as u8;
+            *remaining = moves;
+
+            if moves == 0 && self.piece_attacks(p.piece, p.lone, p.king) {
+                self.plies[index] = 0;
+                queue.push_back(index);
+            }
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let p = Position::from_index(index);
+            let plies = self.plies[index] + 1;
+
+            if p.strong_to_move {
+                // the lone king's moves that led here
+                for from in KING_STEPS.iter().filter_map(|&offset| step(p.lone, offset)) {
+                    let before = Position {
+                        strong_to_move: false,
+                        lone: from,
+                        ..p
+                    };
+                    let i = before.index();
+
+                    if !self.is_valid(&before) || self.plies[i] != UNKNOWN {
+                        continue;
+                    }
+
+                    remaining[i] -= 1;
+                    if remaining[i] == 0 {
+                        self.plies[i] = plies;
+                        queue.push_back(i);
+                    }
+                }
+            } else {
+                // the moves of the strong king or the piece that led here
+                let mut befores = Vec::new();
+                for from in KING_STEPS.iter().filter_map(|&offset| step(p.king, offset)) {
+                    befores.push(Position {
+                        strong_to_move: true,
+                        king: from,
+                        ..p
+                    });
+                }
+                for &line in self.lines() {
+                    let mut square = p.piece;
+                    while let Some(from) = step(square, line) {
+                        if from == p.king || from == p.lone {
+                            break;
+                        }
+                        befores.push(Position {
+                            strong_to_move: true,
+                            piece: from,
+                            ..p
+                        });
+                        square = from;
+                    }
+                }
+
+                for before in befores {
+                    let i = before.index();
+                    if self.is_valid(&before) && self.plies[i] == UNKNOWN {
+                        self.plies[i] = plies;
+                        queue.push_back(i);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Looks up a position, or returns `None` when it isn't this ending
+    pub fn probe(&self, board: &Board) -> Option<Outcome> {
+        if board.variant() != Variant::Standard {
+            return None;
+        }
+
+        let mut kings = [None; 2];
+        let mut piece = None;
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let Some(p) = board.get_piece(file, rank) else {
+                    continue;
+                };
+                let square = rank * 8 + file;
+
+                match p.class {
+                    Class::King => kings[p.color as usize] = Some(square),
+                    class if class == self.class && piece.is_none() => {
+                        piece = Some((square, p.color))
+                    }
+                    _ => return None,
+                }
+            }
+        }
+
+        let (piece, strong) = piece?;
+        let position = Position {
+            strong_to_move: board.turn() == strong,
+            king: kings[strong as usize]?,
+            lone: kings[strong.opponent() as usize]?,
+            piece,
+        };
+
+        Some(
+            match (self.plies[position.index()], position.strong_to_move) {
+                (UNKNOWN, _) => Outcome::Draw,
+                (plies, true) => Outcome::Win(plies as u32),
+                (plies, false) => Outcome::Loss(plies as u32),
+            },
+        )
+    }
+
+    /// Returns the move keeping the best outcome, the quickest mate for the
+    /// winning side and the longest defence for the losing one
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        board
+            .legal_moves()
+            .into_iter()
+            .filter_map(|m| {
+                let next = board.with_move(&m).ok()?;
+                // taking the piece leaves two bare kings
+                let outcome = self.probe(&next).map_or(Outcome::Draw, |o| o.reverse());
+                Some((m, outcome.rank()))
+            })
+            .max_by_key(|(_, rank)| *rank)
+            .map(|(m, _)| m)
+    }
+}
+
+/// Scores a position of one of the endings from the tables, with the line
+/// of perfect play to the mate
+pub fn evaluate(board: &Board) -> Option<Evaluation> {
+    // the tables are built on first use, which no full board should wait for
+    let pieces = board.material(Color::White).total() + board.material(Color::Black).total();
+    if pieces != 3 {
+        return None;
+    }
+
+    let table = [Class::Queen, Class::Rook]
+        .into_iter()
+        .filter(|class| has_piece(board, *class))
+        .find_map(Tablebase::shared)?;
+    let outcome = table.probe(board)?;
+
+    let mut line = Vec::new();
+    let mut next: Option<Board> = None;
+    let plies = match outcome {
+        Outcome::Win(plies) | Outcome::Loss(plies) => plies,
+        Outcome::Draw => 0,
+    };
+    // a drawn line goes on forever, so it only gets the next move
+    while line.len() < plies.max(1) as usize {
+        let current = next.as_ref().unwrap_or(board);
+        let Some(m) = table.best_move(current) else {
+            break;
+        };
+
+        let after = current.with_move(&m).ok()?;
+        line.push(m);
+        next = Some(after);
+    }
+
+    let mover = match outcome {
+        Outcome::Win(plies) => MATE - plies as i32,
+        Outcome::Loss(plies) => -(MATE - plies as i32),
+        Outcome::Draw => 0,
+    };
+
+    Some(Evaluation {
+        score: match board.turn() {
+            Color::White => mover,
+            Color::Black => -mover,
+        },
+        line,
+        depth: plies,
+        nodes: 0,
+    })
+}
+
+fn has_piece(board: &Board, class: Class) -> bool {
+    [Color::White, Color::Black]
+        .iter()
+        .any(|color| board.material(*color).get(class) > 0)
+}
@@ -0,0 +1,212 @@
+//! Explanations of rejected moves for players learning the rules, with the
+//! pieces and lines involved drawn on the board
+
+use crate::{
+    chess::{Board, Class, Square},
+    geometry::{ray, Direction, SquareGeometry},
+    markup::{Brush, Markup},
+    mover::{IllegalReason, Move},
+};
+
+/// Why a move can't be played, told at length and shown on the board. The
+/// text goes beyond the reason, which it doesn't repeat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lesson {
+    pub reason: IllegalReason,
+    pub text: String,
+    pub markup: Markup,
+}
+
+fn name(class: Class) -> String {
+    format!("{:?}", class).to_lowercase()
+}
+
+/// How a piece moves, in a sentence
+fn rule(class: Class) -> &'static str {
+    match class {
+        Class::Pawn => "A pawn moves one square straight ahead, or two from its starting square, and captures one square diagonally forward.",
+        Class::Knight => "A knight jumps in an L shape: two squares one way and one square to the side.",
+        Class::Bishop => "A bishop moves any number of squares diagonally.",
+        Class::Rook => "A rook moves any number of squares along a rank or file.",
+        Class::Queen => "A queen moves any number of squares along a rank, file or diagonal.",
+        Class::King => "A king moves one square in any direction, or two towards a rook when castling.",
+    }
+}
+
+/// Describes the piece on a square, e.g. `the black rook on e8`
+fn piece_on(board: &Board, at: Square) -> String {
+    match board.get_piece(at.0, at.1) {
+        Some(piece) => format!("the {} {} on {}", piece.color, name(piece.class), at.name()),
+        None => at.name(),
+    }
+}
+
+/// Finds an opponent's piece sliding along the line beyond a square, which
+/// is what pins a piece on that square or keeps a king from stepping back
+fn slider_behind(board: &Board, from: Square, direction: Direction) -> Option<Square> {
+    let at = ray(from, direction)
+        .into_iter()
+        .find(|at| board.get_piece(at.0, at.1).is_some())?;
+    let piece = board.get_piece(at.0, at.1)?;
+
+    let slides = match piece.class {
+        Class::Queen => true,
+        Class::Rook => !direction.is_diagonal(),
+        Class::Bishop => direction.is_diagonal(),
+        _ => false,
+    };
+
+    (piece.color != board.turn() && slides).then_some(at)
+}
+
+/// Explains why a move is illegal, or returns `None` when it is legal
+pub fn explain(board: &Board, m: &Move) -> Option<Lesson> {
+    let reason = board.why_illegal(m)?;
+
+    let from = (m.from_file, m.from_rank);
+    let to = (m.to_file, m.to_rank);
+    let king = board.king_square(board.turn());
+    let opponent = board.turn().opponent();
+
+    let mut markup = Markup::new();
+    let mut text = String::new();
+
+    match reason {
+        IllegalReason::NoPiece => {
+            markup.mark(from, Brush::Red);
+            text.push_str(" Pick a square holding one of your pieces.");
+        }
+        IllegalReason::WrongTurn => {
+            markup.mark(from, Brush::Red);
+            text.push_str(&format!(
+                " It is {} to move; the players take turns, one move each.",
+                board.turn()
+            ));
+        }
+        IllegalReason::OwnPiece { at } => {
+            markup.mark(at, Brush::Red);
+            text.push_str(" A piece can only capture pieces of the other color.");
+        }
+        IllegalReason::Blocked { at } => {
+            let _ = markup.arrow(from, to, Brush::Yellow);
+            markup.mark(at, Brush::Red);
+            text.push_str(&format!(
+                " Only knights jump over pieces, and {} is in the way.",
+                piece_on(board, at)
+            ));
+        }
+        IllegalReason::InvalidPath(class) => {
+            let _ = markup.arrow(from, to, Brush::Red);
+            for m in board.legal_moves_from(m.from_file, m.from_rank) {
+                markup.mark((m.to_file, m.to_rank), Brush::Green);
+            }
+            text.push_str(&format!(
+                " {} The squares it can reach are marked.",
+                rule(class)
+            ));
+        }
+        IllegalReason::CastlingOutOfCheck | IllegalReason::KingInCheck => {
+            let checkers = board.checkers();
+
+            for checker in &checkers {
+                if let Some(king) = king {
+                    let _ = markup.arrow(*checker, king, Brush::Red);
+                }
+            }
+
+            let names: Vec<String> = checkers.iter().map(|c| piece_on(board, *c)).collect();
+            text.push_str(&format!(
+                " Your king is attacked by {}.",
+                names.join(" and ")
+            ));
+
+            text.push_str(match (reason, checkers.len()) {
+                (IllegalReason::CastlingOutOfCheck, _) => {
+                    " A king in check may not castle; deal with the check first."
+                }
+                (_, 1) => {
+                    " Every move has to end the check: capture the checking piece, put a piece in between, or move the king."
+                }
+                _ => " In a double check only a king move can end the check.",
+            });
+        }
+        IllegalReason::CastlingThroughCheck => {
+            let passing = ((m.from_file + m.to_file) / 2, m.from_rank);
+            markup.mark(passing, Brush::Red);
+            for attacker in board.attackers(passing, opponent) {
+                let _ = markup.arrow(attacker, passing, Brush::Red);
+            }
+            text.push_str(&format!(
+                " The king may not pass over a square the opponent attacks, and {} is attacked.",
+                passing.name()
+            ));
+        }
+        IllegalReason::MovesIntoCheck => {
+            // a king stepping along a line away from a slider is still on
+            // that line, which the slider only reaches once the king left
+            let mut attackers = board.attackers(to, opponent);
+            if attackers.is_empty() {
+                attackers.extend(
+                    Direction::towards(to, from).and_then(|d| slider_behind(board, from, d)),
+                );
+            }
+
+            markup.mark(to, Brush::Red);
+            for attacker in &attackers {
+                let _ = markup.arrow(*attacker, to, Brush::Red);
+            }
+
+            let names: Vec<String> = attackers.iter().map(|a| piece_on(board, *a)).collect();
+            text.push_str(&format!(
+                " A king may never move onto an attacked square, and {} is covered by {}.",
+                to.name(),
+                names.join(" and ")
+            ));
+        }
+        IllegalReason::Pinned => {
+            let pinner = king
+                .and_then(|king| Direction::towards(king, from))
+                .and_then(|d| slider_behind(board, from, d));
+
+            if let (Some(pinner), Some(king)) = (pinner, king) {
+                let _ = markup.arrow(pinner, king, Brush::Red);
+                markup.mark(from, Brush::Yellow);
+                text.push_str(&format!(
+                    " The piece on {} stands between your king and {}; moving it off that line would put your own king in check.",
+                    from.name(),
+                    piece_on(board, pinner)
+                ));
+            }
+        }
+        IllegalReason::CaptureRequired => {
+            for m in board.captures() {
+                let _ = markup.arrow(
+                    (m.from_file, m.from_rank),
+                    (m.to_file, m.to_rank),
+                    Brush::Green,
+                );
+            }
+            text.push_str(" In this variant capturing is compulsory; the captures are shown.");
+        }
+        IllegalReason::PromotionRequired => {
+            markup.mark(to, Brush::Green);
+            text.push_str(" A pawn reaching the last rank becomes a queen, rook, bishop or knight, e.g. e7e8q.");
+        }
+        IllegalReason::InvalidPromotion(_) => {
+            text.push_str(" A pawn can't stay a pawn or become a king.");
+        }
+        _ => {}
+    }
+
+    // fall back on the reason itself when there is nothing more to say
+    let text = match text.trim_start() {
+        "" => format!("{}.", reason),
+        text => text.to_string(),
+    };
+
+    Some(Lesson {
+        reason,
+        text,
+        markup,
+    })
+}
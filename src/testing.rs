@@ -0,0 +1,235 @@
+//! Random games for shaking out rule bugs: every position reached is checked
+//! against invariants that any legal game must keep. Only built for the
+//! tests.
+
+use std::fmt::Display;
+
+use crate::{
+    chess::{Board, Class, Color, Error},
+    fen::ToFen,
+    game::Game,
+    rng::Rng,
+};
+
+/// Plays random legal moves from the default position until the game ends
+/// or `max_plies` moves were made
+pub(crate) fn random_playout(seed: u64, max_plies: usize) -> Result<Game, Error> {
+    let mut rng = Rng::new(seed);
+    let mut game = Game::new()?;
+
+    for _ in 0..max_plies {
+        let moves = game.board().legal_moves();
+        if moves.is_empty() || game.is_over() {
+            break;
+        }
+
+        let m = moves[rng.below(moves.len())];
+        game.move_piece(&m.to_string())?;
+    }
+
+    Ok(game)
+}
+
+/// An invariant found broken during a random game
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Violation {
+    pub seed: u64,
+    pub ply: usize,
+    /// The position the invariant was broken in
+    pub fen: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "seed {} ply {}: {} in {}",
+            self.seed, self.ply, self.message, self.fen
+        )
+    }
+}
+
+/// Checks a position on its own: one king per side, the FEN reading back
+/// the same, the cached material and checks agreeing with the board
+pub(crate) fn check_position(board: &Board) -> Result<(), String> {
+    let fen = board.to_fen();
+
+    for color in [Color::White, Color::Black] {
+        let mut count = [0u8; 6];
+        for file in 0..8 {
+            for rank in 0..8 {
+                if let Some(p) = board.get_piece(file, rank).filter(|p| p.color == color) {
+                    count[p.class as usize] += 1;
+                }
+            }
+        }
+
+        if count[Class::King as usize] != 1 {
+            return Err(format!(
+                "{} has {} kings",
+                color,
+                count[Class::King as usize]
+            ));
+        }
+
+        let material = board.material(color);
+        let classes = [
+            Class::Pawn,
+            Class::Knight,
+            Class::Bishop,
+            Class::Rook,
+            Class::Queen,
+            Class::King,
+        ];
+        if classes
+            .iter()
+            .any(|c| material.get(*c) != count[*c as usize])
+        {
+            return Err(format!("material count of {} is off", color));
+        }
+    }
+
+    match Board::try_from_fen(&fen) {
+        Ok(copy) if copy.to_fen() == fen => {}
+        Ok(copy) => return Err(format!("FEN reads back as {}", copy.to_fen())),
+        Err(e) => return Err(format!("FEN doesn't read back: {}", e)),
+    }
+
+    if board.is_check() != board.is_in_check(board.turn()) {
+        return Err("cached check disagrees with the board".to_string());
+    }
+
+    Ok(())
+}
+
+/// Plays a random game like `random_playout`, checking every position and
+/// that taking back each move restores the position before it
+pub(crate) fn checked_playout(seed: u64, max_plies: usize) -> Result<Game, Violation> {
+    let violation = |game: &Game, message: String| Violation {
+        seed,
+        ply: game.history().len(),
+        fen: game.board().to_fen(),
+        message,
+    };
+
+    let mut rng = Rng::new(seed);
+    let mut game = Game::new().map_err(|e| Violation {
+        seed,
+        ply: 0,
+        fen: String::new(),
+        message: e.to_string(),
+    })?;
+
+    for _ in 0..max_plies {
+        let moves = game.board().legal_moves();
+        if moves.is_empty() || game.is_over() {
+            break;
+        }
+
+        let before = game.board().to_fen();
+        let m = moves[rng.below(moves.len())];
+
+        game.move_piece(&m.to_string())
+            .map_err(|e| violation(&game, format!("legal move {} failed: {}", m, e)))?;
+        check_position(game.board()).map_err(|message| violation(&game, message))?;
+
+        let after = game.board().to_fen();
+
+        game.undo()
+            .map_err(|e| violation(&game, format!("undo failed: {}", e)))?;
+        if game.board().to_fen() != before {
+            return Err(violation(
+                &game,
+                format!("undoing {} gave another position", m),
+            ));
+        }
+
+        game.redo()
+            .map_err(|e| violation(&game, format!("redo failed: {}", e)))?;
+        if game.board().to_fen() != after {
+            return Err(violation(
+                &game,
+                format!("redoing {} gave another position", m),
+            ));
+        }
+    }
+
+    Ok(game)
+}
+
+/// Plays `games` checked random games with the seeds 1 to `games`,
+/// returning the number of moves checked or the first violation
+pub(crate) fn fuzz(games: u64, max_plies: usize) -> Result<usize, Violation> {
+    let mut plies = 0;
+
+    for seed in 1..=games {
+        plies += checked_playout(seed, max_plies)?.history().len();
+    }
+
+    Ok(plies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::perft;
+
+    #[test]
+    fn random_games_keep_the_invariants() {
+        if let Err(violation) = fuzz(4, 60) {
+            panic!("{}", violation);
+        }
+    }
+
+    #[test]
+    fn playouts_repeat_from_their_seed() {
+        let moves = |seed| -> Vec<String> {
+            let game = random_playout(seed, 60).unwrap();
+            game.history().iter().map(|m| m.m.to_string()).collect()
+        };
+
+        assert_eq!(moves(7), moves(7));
+        assert_ne!(moves(7), moves(8));
+    }
+
+    /// Node counts from the standard perft positions
+    #[test]
+    fn perft_matches_the_reference_counts() {
+        let positions: [(&str, &[u64]); 5] = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                &[20, 400, 8902],
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                &[48, 2039],
+            ),
+            (
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                &[14, 191, 2812],
+            ),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                &[6, 264, 9467],
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                &[44, 1486],
+            ),
+        ];
+
+        for (fen, counts) in positions {
+            let board = Board::try_from_fen(fen).unwrap();
+            for (depth, count) in counts.iter().enumerate() {
+                assert_eq!(
+                    perft(&board, depth as u32 + 1),
+                    *count,
+                    "{} depth {}",
+                    fen,
+                    depth + 1
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Dumps of the engine's search tree as DOT or JSON, for seeing why it
+//! chose a move or missed one
+
+use std::fmt::Display;
+
+use crate::{chess::Error, engine::MATE, mover::Move, net::quote};
+
+/// Why the search stopped looking at the moves of a position early
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Cut {
+    /// The transposition table already held a good enough result
+    Table,
+    /// Passing the move still failed high
+    NullMove,
+    /// A move was good enough that the others needn't be searched
+    Beta,
+    /// Standing pat was good enough in the quiescence search
+    StandPat,
+}
+
+impl Display for Cut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Cut::Table => "table",
+            Cut::NullMove => "null move",
+            Cut::Beta => "beta cutoff",
+            Cut::StandPat => "stand pat",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A position the search visited
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    /// The move leading here, `None` at the root and after a null move
+    pub m: Option<Move>,
+    /// Plies left to search, 0 in the quiescence search
+    pub depth: u32,
+    /// The window the position was searched with, for the side to move
+    pub alpha: i32,
+    pub beta: i32,
+    /// The score found, for the side to move
+    pub score: i32,
+    pub cut: Option<Cut>,
+    /// How many moves a beta cutoff left unsearched
+    pub pruned: usize,
+    pub children: Vec<TreeNode>,
+}
+
+/// The top plies of the last iteration a search completed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTree {
+    pub root: TreeNode,
+    /// How many plies deep the tree was recorded
+    pub plies: u32,
+}
+
+/// Writes a search bound, the widest of which are infinite
+fn bound(value: i32) -> String {
+    match value {
+        v if v > MATE => "inf".to_string(),
+        v if v < -MATE => "-inf".to_string(),
+        v => v.to_string(),
+    }
+}
+
+impl SearchTree {
+    /// Writes the tree as a Graphviz digraph. Edges along the best line are
+    /// bold and positions that were cut short are shaded.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        dot.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+        let mut next = 0;
+        dot_node(&self.root, &mut next, &mut dot);
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"plies\":{},\"root\":{}}}",
+            self.plies,
+            json_node(&self.root)
+        )
+    }
+
+    /// Writes the tree to a file, as JSON when the name ends in `.json` and
+    /// as DOT otherwise
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        let contents = match filename.ends_with(".json") {
+            true => self.to_json(),
+            false => self.to_dot(),
+        };
+
+        std::fs::write(filename, contents)?;
+        Ok(())
+    }
+}
+
+fn dot_label(node: &TreeNode) -> String {
+    let mut label = format!(
+        "depth {} [{}, {}]\\nscore {}",
+        node.depth,
+        bound(node.alpha),
+        bound(node.beta),
+        node.score
+    );
+
+    if let Some(cut) = node.cut {
+        label.push_str(&format!("\\n{}", cut));
+        if node.pruned > 0 {
+            label.push_str(&format!(", {} pruned", node.pruned));
+        }
+    }
+
+    label
+}
+
+/// Writes a node and everything below it, returning its id
+fn dot_node(node: &TreeNode, next: &mut usize, dot: &mut String) -> usize {
+    let id = *next;
+    *next += 1;
+
+    let style = match node.cut {
+        Some(_) => ", style=filled, fillcolor=\"#dddddd\"",
+        None => "",
+    };
+    dot.push_str(&format!(
+        "  n{} [label=\"{}\"{}];\n",
+        id,
+        dot_label(node),
+        style
+    ));
+
+    // the move the score came from, for the side to move the best
+    let best = node
+        .children
+        .iter()
+        .position(|child| -child.score == node.score);
+
+    for (i, child) in node.children.iter().enumerate() {
+        let child_id = dot_node(child, next, dot);
+        let name = child.m.map_or("null".to_string(), String::from);
+        let bold = match Some(i) == best {
+            true => ", style=bold",
+            false => "",
+        };
+
+        dot.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\"{}];\n",
+            id, child_id, name, bold
+        ));
+    }
+
+    id
+}
+
+fn json_node(node: &TreeNode) -> String {
+    let m = node
+        .m
+        .map_or("null".to_string(), |m| quote(&String::from(m)));
+    let cut = node
+        .cut
+        .map_or("null".to_string(), |cut| quote(&cut.to_string()));
+    let children: Vec<String> = node.children.iter().map(json_node).collect();
+
+    format!(
+        "{{\"move\":{},\"depth\":{},\"alpha\":{},\"beta\":{},\"score\":{},\"cut\":{},\"pruned\":{},\"children\":[{}]}}",
+        m,
+        node.depth,
+        node.alpha,
+        node.beta,
+        node.score,
+        cut,
+        node.pruned,
+        children.join(",")
+    )
+}
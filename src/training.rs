@@ -0,0 +1,243 @@
+//! Training modes: playing blindfold and drilling the names of the squares
+//! against the clock
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    chess::{Board, Error, Square},
+    fen::ToFen,
+    geometry::SquareGeometry,
+    markup::{Brush, Markup},
+    mover::parse_square,
+    rng::Rng,
+};
+
+/// Tracks the answers given in a drill
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Score {
+    pub correct: usize,
+    pub wrong: usize,
+    /// Correct answers in a row, up to the last one
+    pub streak: usize,
+    pub best_streak: usize,
+    /// Time taken over all answers
+    pub time: Duration,
+}
+
+impl Score {
+    pub fn record(&mut self, correct: bool, time: Duration) {
+        self.time += time;
+
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.wrong += 1;
+            self.streak = 0;
+        }
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.correct + self.wrong
+    }
+
+    /// Returns the share of correct answers, from 0 to 1
+    pub fn accuracy(&self) -> f64 {
+        self.correct as f64 / self.attempts().max(1) as f64
+    }
+
+    pub fn average_time(&self) -> Option<Duration> {
+        match self.attempts() {
+            0 => None,
+            n => Some(self.time / n as u32),
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} correct ({:.0}%), best streak {}",
+            self.correct,
+            self.attempts(),
+            self.accuracy() * 100.0,
+            self.best_streak
+        )?;
+
+        match self.average_time() {
+            Some(time) => write!(f, ", {:.1}s per answer", time.as_secs_f64()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The kinds of coordinate drill
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Drill {
+    /// A square is highlighted and has to be named
+    Name,
+    /// A square is named and has to be found on the board
+    Find,
+}
+
+impl TryFrom<&str> for Drill {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Drill, Error> {
+        match name {
+            "name" => Ok(Drill::Name),
+            "find" => Ok(Drill::Find),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+/// The verdict on one answer of a drill
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Answer {
+    pub correct: bool,
+    /// The answer came after the time limit, which counts as wrong
+    pub late: bool,
+    pub expected: Square,
+    pub time: Duration,
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = self.time.as_secs_f64();
+
+        match (self.correct, self.late) {
+            (true, _) => write!(f, "correct ({:.1}s)", time),
+            (false, true) => write!(
+                f,
+                "too slow ({:.1}s), it was {}",
+                time,
+                self.expected.name()
+            ),
+            (false, false) => write!(f, "wrong, it was {}", self.expected.name()),
+        }
+    }
+}
+
+/// A coordinate drill asking one square after another, optionally with a
+/// time limit per square
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoordinateDrill {
+    drill: Drill,
+    limit: Option<Duration>,
+    rng: Rng,
+    target: Square,
+    asked: Instant,
+    score: Score,
+}
+
+impl CoordinateDrill {
+    /// Starts a drill, asking the first square right away
+    pub fn new(drill: Drill, limit: Option<Duration>, seed: u64) -> CoordinateDrill {
+        let mut rng = Rng::new(seed);
+        let target = (rng.below(8), rng.below(8));
+
+        CoordinateDrill {
+            drill,
+            limit,
+            rng,
+            target,
+            asked: Instant::now(),
+            score: Score::default(),
+        }
+    }
+
+    pub fn drill(&self) -> Drill {
+        self.drill
+    }
+
+    /// Returns the square asked for
+    pub fn target(&self) -> Square {
+        self.target
+    }
+
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    /// Returns the time left for the current square, if there is a limit
+    pub fn remaining(&self) -> Option<Duration> {
+        self.limit
+            .map(|limit| limit.saturating_sub(self.asked.elapsed()))
+    }
+
+    pub fn prompt(&self) -> String {
+        match self.drill {
+            Drill::Name => "Name the highlighted square".to_string(),
+            Drill::Find => format!("Find {}", self.target.name()),
+        }
+    }
+
+    /// Returns the highlight to draw for the current square, which is
+    /// nothing when the square has to be found
+    pub fn markup(&self) -> Markup {
+        let mut markup = Markup::new();
+
+        if self.drill == Drill::Name {
+            markup.mark(self.target, Brush::Yellow);
+        }
+
+        markup
+    }
+
+    /// Answers with a square, named or clicked, and asks the next one
+    pub fn answer(&mut self, square: Square) -> Answer {
+        let time = self.asked.elapsed();
+        let late = self.limit.is_some_and(|limit| time > limit);
+
+        let answer = Answer {
+            correct: square == self.target && !late,
+            late,
+            expected: self.target,
+            time,
+        };
+        self.score.record(answer.correct, time);
+
+        // never ask the same square twice in a row
+        let previous = self.target;
+        while self.target == previous {
+            self.target = (self.rng.below(8), self.rng.below(8));
+        }
+        self.asked = Instant::now();
+
+        answer
+    }
+
+    /// Answers with the name of a square, e.g. `e4`
+    pub fn answer_name(&mut self, name: &str) -> Result<Answer, Error> {
+        Ok(self.answer(parse_square(name)?))
+    }
+}
+
+/// Blindfold play: the board stays hidden and moves are given in notation
+/// only, though the position may be peeked at
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blindfold {
+    peeks: usize,
+}
+
+impl Blindfold {
+    pub fn new() -> Blindfold {
+        Blindfold::default()
+    }
+
+    /// Returns the position in FEN, counting the peek
+    pub fn peek(&mut self, board: &Board) -> String {
+        self.peeks += 1;
+        board.to_fen()
+    }
+
+    pub fn peeks(&self) -> usize {
+        self.peeks
+    }
+}
@@ -0,0 +1,196 @@
+//! Texel tuning: the evaluation weights are fitted so that a logistic curve
+//! over the static evaluation predicts the results of real games.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::{
+    chess::{Board, Error},
+    epd::Epd,
+    eval::{self, Params},
+    game::GameResult,
+    pgn::PgnReader,
+};
+
+/// Plies at the start of every game that are skipped, as book moves say
+/// little about the evaluation
+const OPENING_PLIES: usize = 8;
+
+/// A position and the result of the game it came from, 1 being a white win,
+/// 0.5 a draw and 0 a black win
+pub struct Sample {
+    pub board: Board,
+    pub result: f64,
+}
+
+fn result_value(result: GameResult) -> Option<f64> {
+    match result {
+        GameResult::WhiteWins => Some(1.0),
+        GameResult::Draw => Some(0.5),
+        GameResult::BlackWins => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Collects the positions of every finished game in a PGN database, leaving
+/// out the opening and positions in check. Games that fail to parse are
+/// skipped.
+pub fn samples_from_pgn<R: BufRead>(reader: R) -> Result<Vec<Sample>, Error> {
+    let mut samples = Vec::new();
+
+    for game in PgnReader::new(reader) {
+        let game = match game {
+            Ok(game) => game,
+            Err(e @ Error::Io(_)) => return Err(e),
+            Err(_) => continue,
+        };
+
+        let Some(result) = result_value(game.result()) else {
+            continue;
+        };
+
+        let Ok(positions) = game.positions() else {
+            continue;
+        };
+
+        for board in positions.into_iter().skip(OPENING_PLIES) {
+            if !board.is_check() {
+                samples.push(Sample { board, result });
+            }
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Reads EPD records whose result is given by a `c9` operation, e.g.
+/// `c9 "1-0";`, the convention of the common tuning sets
+pub fn samples_from_epd<R: BufRead>(reader: R) -> Result<Vec<Sample>, Error> {
+    let mut samples = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let epd = Epd::parse(&line)?;
+        let result = epd
+            .get("c9")
+            .and_then(|op| op.operands.first())
+            .and_then(|r| GameResult::try_from(r.as_str()).ok())
+            .and_then(result_value)
+            .ok_or(Error::InvalidInput)?;
+
+        samples.push(Sample {
+            board: epd.board()?,
+            result,
+        });
+    }
+
+    Ok(samples)
+}
+
+/// Reads samples from a `.pgn` or `.epd` file
+pub fn load_samples(filename: &str) -> Result<Vec<Sample>, Error> {
+    let reader = BufReader::new(File::open(filename)?);
+
+    if filename.ends_with(".epd") {
+        samples_from_epd(reader)
+    } else {
+        samples_from_pgn(reader)
+    }
+}
+
+fn sigmoid(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// The mean squared difference between the results and the results the
+/// evaluation predicts
+pub fn error(samples: &[Sample], params: &Params, k: f64) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|s| (s.result - sigmoid(eval::evaluate(&s.board, params), k)).powi(2))
+        .sum();
+
+    total / samples.len().max(1) as f64
+}
+
+/// Finds the scaling constant that fits the evaluation to the results best,
+/// which has to be fixed before the weights are tuned
+pub fn best_k(samples: &[Sample], params: &Params) -> f64 {
+    let mut best = (1.0, error(samples, params, 1.0));
+    let mut step = 0.1;
+
+    for _ in 0..4 {
+        let center = best.0;
+
+        for i in -10..=10 {
+            let k = center + i as f64 * step;
+            if k <= 0.0 {
+                continue;
+            }
+
+            let e = error(samples, params, k);
+            if e < best.1 {
+                best = (k, e);
+            }
+        }
+
+        step /= 10.0;
+    }
+
+    best.0
+}
+
+/// Improves the weights by local search: every weight is nudged up and
+/// down, keeping changes that lower the error, with the step halving once a
+/// pass finds nothing. Stops after `passes` passes or when the step reaches
+/// zero.
+pub fn tune(samples: &[Sample], start: &Params, passes: usize) -> Params {
+    let mut params = start.clone();
+    let k = best_k(samples, &params);
+    let mut best = error(samples, &params, k);
+    let mut step = 8;
+
+    for _ in 0..passes {
+        let mut improved = false;
+        let count = params.weights_mut().len();
+
+        for index in 0..count {
+            for phase in 0..2 {
+                for delta in [step, -step] {
+                    let mut candidate = params.clone();
+                    {
+                        let mut weights = candidate.weights_mut();
+                        let score = &mut weights[index].1;
+                        match phase {
+                            0 => score.mg += delta,
+                            _ => score.eg += delta,
+                        }
+                    }
+
+                    let e = error(samples, &candidate, k);
+                    if e < best {
+                        best = e;
+                        params = candidate;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !improved {
+            step /= 2;
+            if step == 0 {
+                break;
+            }
+        }
+    }
+
+    params
+}
@@ -0,0 +1,349 @@
+//! A client for external engines speaking UCI, run as a subprocess and
+//! driven through its standard input and output
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    chess::{Board, Color, Error},
+    engine::{Backend, Evaluation, Limits, StopToken, MATE},
+    fen::ToFen,
+    mover::Move,
+};
+
+/// How long an engine may take to answer `uci` and `isready`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a search checks whether it was stopped
+const STOP_POLL: Duration = Duration::from_millis(10);
+
+fn engine_error(e: impl std::fmt::Display) -> Error {
+    Error::Engine(e.to_string())
+}
+
+/// An option an engine offers, as announced during the handshake
+#[derive(Debug, Clone, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    /// The type, e.g. `spin`, `check` or `string`
+    pub kind: String,
+    pub default: Option<String>,
+}
+
+impl UciOption {
+    /// Reads an `option name ... type ... default ...` line
+    fn parse(line: &str) -> Option<UciOption> {
+        let rest = line.strip_prefix("option name ")?;
+        let (name, rest) = rest.split_once(" type ")?;
+
+        let mut words = rest.split_whitespace();
+        let kind = words.next()?.to_string();
+
+        // the default may be several words, up to the next keyword
+        let words: Vec<&str> = words.collect();
+        let default = words.iter().position(|w| *w == "default").map(|start| {
+            words[start + 1..]
+                .iter()
+                .take_while(|w| !["min", "max", "var"].contains(*w))
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+
+        Some(UciOption {
+            name: name.trim().to_string(),
+            kind,
+            default,
+        })
+    }
+}
+
+/// An external engine. It is told to quit when dropped.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines from the engine, read on a thread of their own so waiting for
+    /// an answer can time out
+    lines: Receiver<String>,
+    name: String,
+    path: String,
+    options: Vec<UciOption>,
+    limits: Limits,
+}
+
+impl UciEngine {
+    /// Starts the engine at `path` and goes through the UCI handshake
+    pub fn spawn(path: &str) -> Result<UciEngine, Error> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or_else(|| engine_error("no input"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| engine_error("no output"))?;
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            child,
+            stdin,
+            lines,
+            name: path.to_string(),
+            path: path.to_string(),
+            options: Vec::new(),
+            limits: Limits::movetime(Duration::from_secs(1)),
+        };
+
+        engine.send("uci")?;
+        loop {
+            let line = engine.receive(HANDSHAKE_TIMEOUT)?;
+
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if let Some(option) = UciOption::parse(&line) {
+                engine.options.push(option);
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.sync()?;
+
+        Ok(engine)
+    }
+
+    /// Returns the name the engine gave itself
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path the engine was started from
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    /// Sets the limits `evaluate` and `best_move` search with, which are
+    /// one second per move unless set otherwise
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), Error> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    fn receive(&self, timeout: Duration) -> Result<String, Error> {
+        self.lines.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => engine_error("the engine stopped answering"),
+            RecvTimeoutError::Disconnected => engine_error("the engine exited"),
+        })
+    }
+
+    /// Waits until the engine has processed everything sent so far
+    fn sync(&mut self) -> Result<(), Error> {
+        self.send("isready")?;
+        while self.receive(HANDSHAKE_TIMEOUT)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Sets one of the engine's options, e.g. `Threads` or `Skill Level`
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        if !self
+            .options
+            .iter()
+            .any(|o| o.name.eq_ignore_ascii_case(name))
+        {
+            return Err(Error::InvalidInput);
+        }
+
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.sync()
+    }
+
+    /// Searches a position within the limits, reading the score and line
+    /// from the last `info` the engine sent before its `bestmove`. Stopping
+    /// the token tells the engine to stop and answer right away.
+    pub fn search(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+    ) -> Result<Evaluation, Error> {
+        self.send(&format!("position fen {}", board.to_fen()))?;
+        self.send(&go_command(limits))?;
+        let mut stopping = false;
+
+        let mut evaluation = Evaluation {
+            score: 0,
+            line: Vec::new(),
+            depth: 0,
+            nodes: 0,
+        };
+
+        loop {
+            if !stopping && stop.is_stopped() {
+                self.send("stop")?;
+                stopping = true;
+            }
+
+            let line = match self.lines.recv_timeout(STOP_POLL) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(engine_error("the engine exited"))
+                }
+            };
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("info") => read_info(&mut evaluation, words, board.turn()),
+                Some("bestmove") => {
+                    let best = words.next().ok_or_else(|| engine_error("no best move"))?;
+
+                    // an engine without legal moves answers `(none)` or `0000`
+                    match Move::try_from(best) {
+                        Ok(m) if evaluation.line.first() != Some(&m) => evaluation.line = vec![m],
+                        Ok(_) => {}
+                        Err(_) => evaluation.line.clear(),
+                    }
+
+                    return Ok(evaluation);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Backend for UciEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    fn analyze(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        stop: &StopToken,
+    ) -> Result<Evaluation, Error> {
+        self.search(board, limits, stop)
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        UciEngine::set_option(self, name, value)
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        // give the engine a moment to exit on its own
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn go_command(limits: &Limits) -> String {
+    let mut go = "go".to_string();
+
+    if let Some(depth) = limits.depth {
+        go.push_str(&format!(" depth {}", depth));
+    }
+
+    let times = [
+        ("movetime", limits.movetime),
+        ("wtime", limits.wtime),
+        ("btime", limits.btime),
+        ("winc", limits.winc),
+        ("binc", limits.binc),
+    ];
+    for (name, time) in times {
+        if let Some(time) = time {
+            go.push_str(&format!(" {} {}", name, time.as_millis()));
+        }
+    }
+
+    go
+}
+
+/// Takes the depth, nodes, score and line from an `info` line. Scores come
+/// from the point of view of the side to move and are turned to white's.
+fn read_info<'a>(
+    evaluation: &mut Evaluation,
+    mut words: impl Iterator<Item = &'a str>,
+    turn: Color,
+) {
+    let sign = match turn {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+
+    while let Some(word) = words.next() {
+        match word {
+            "depth" => {
+                if let Some(depth) = words.next().and_then(|w| w.parse().ok()) {
+                    evaluation.depth = depth;
+                }
+            }
+            "nodes" => {
+                if let Some(nodes) = words.next().and_then(|w| w.parse().ok()) {
+                    evaluation.nodes = nodes;
+                }
+            }
+            "score" => {
+                let kind = words.next();
+                let Some(value) = words.next().and_then(|w| w.parse::<i32>().ok()) else {
+                    continue;
+                };
+
+                evaluation.score = sign
+                    * match kind {
+                        // mating in n moves takes 2n - 1 plies
+                        Some("mate") if value > 0 => MATE - (2 * value - 1),
+                        Some("mate") => -(MATE + 2 * value),
+                        _ => value,
+                    };
+            }
+            // the line is always the last field
+            "pv" => {
+                evaluation.line = words.filter_map(|w| Move::try_from(w).ok()).collect();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
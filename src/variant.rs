@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use crate::{
+    chess::{Class, Color, Error, Piece, Status, DEFAULT_BOARD},
+    fen::ToFen,
+    game::GameResult,
+};
+
+/// The rule set a board is played under. The board asks its variant
+/// whenever a rule differs between variants, so adding one means extending
+/// the methods here rather than the move code.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Captured pieces change sides and can be dropped back onto the board
+    Crazyhouse,
+    /// Captures are forced and the first side to run out of pieces or moves
+    /// wins
+    Antichess,
+}
+
+impl Variant {
+    /// Returns the FEN of the position games of this variant start from
+    pub fn start_position(&self) -> &'static str {
+        match self {
+            Variant::Standard => DEFAULT_BOARD,
+            Variant::Crazyhouse => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+        }
+    }
+
+    /// Returns the classes a pawn may promote to
+    pub fn promotions(&self) -> &'static [Class] {
+        match self {
+            Variant::Antichess => &[
+                Class::Queen,
+                Class::Rook,
+                Class::Bishop,
+                Class::Knight,
+                Class::King,
+            ],
+            _ => &[Class::Queen, Class::Rook, Class::Bishop, Class::Knight],
+        }
+    }
+
+    /// Returns the class that goes into the capturing side's pocket when the
+    /// piece is captured, if any. Promoted pieces go back as pawns.
+    pub fn on_capture(&self, piece: &Piece) -> Option<Class> {
+        match self {
+            Variant::Crazyhouse if piece.promoted => Some(Class::Pawn),
+            Variant::Crazyhouse => Some(piece.class),
+            _ => None,
+        }
+    }
+
+    /// Decides the game once the side to move has reached the given status
+    pub fn result(&self, status: Status, turn: Color) -> GameResult {
+        match (self, status) {
+            // running out of pieces or moves wins in antichess
+            (Variant::Antichess, Status::Checkmate | Status::Stalemate) => GameResult::win(turn),
+            (_, Status::Checkmate) => GameResult::win(turn.opponent()),
+            (_, Status::Stalemate) => GameResult::Draw,
+            _ => GameResult::Ongoing,
+        }
+    }
+
+    /// Whether the aim is to get rid of one's own pieces, so material counts
+    /// against its owner
+    pub fn wins_without_pieces(&self) -> bool {
+        *self == Variant::Antichess
+    }
+
+    /// Whether pieces can be dropped from a pocket
+    pub fn has_drops(&self) -> bool {
+        *self == Variant::Crazyhouse
+    }
+
+    /// Whether the king can be checked and mated, rather than being an
+    /// ordinary piece
+    pub fn has_royal_king(&self) -> bool {
+        *self != Variant::Antichess
+    }
+
+    /// Whether a capture has to be made when one is available
+    pub fn forces_captures(&self) -> bool {
+        *self == Variant::Antichess
+    }
+
+    pub fn has_castling(&self) -> bool {
+        *self != Variant::Antichess
+    }
+}
+
+impl TryFrom<&str> for Variant {
+    type Error = crate::chess::Error;
+
+    fn try_from(name: &str) -> Result<Variant, Error> {
+        match name.trim().to_lowercase().as_str() {
+            "standard" | "chess" => Ok(Variant::Standard),
+            "crazyhouse" | "zh" => Ok(Variant::Crazyhouse),
+            "antichess" | "losing" | "giveaway" => Ok(Variant::Antichess),
+            _ => Err(Error::InvalidInput),
+        }
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "standard"),
+            Variant::Crazyhouse => write!(f, "crazyhouse"),
+            Variant::Antichess => write!(f, "antichess"),
+        }
+    }
+}
+
+/// The pieces that can be held in a pocket, most valuable first
+pub const DROPPABLE: [Class; 5] = [
+    Class::Queen,
+    Class::Rook,
+    Class::Bishop,
+    Class::Knight,
+    Class::Pawn,
+];
+
+/// Captured pieces a player holds in hand, waiting to be dropped
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Pocket {
+    counts: [u8; 5],
+}
+
+impl Pocket {
+    fn index(class: Class) -> Option<usize> {
+        DROPPABLE.iter().position(|c| *c == class)
+    }
+
+    pub fn count(&self, class: Class) -> u8 {
+        Pocket::index(class).map_or(0, |i| self.counts[i])
+    }
+
+    /// Adds a piece to the pocket. Kings are never captured, so they are
+    /// ignored.
+    pub fn add(&mut self, class: Class) {
+        if let Some(i) = Pocket::index(class) {
+            self.counts[i] = self.counts[i].saturating_add(1);
+        }
+    }
+
+    /// Removes a piece from the pocket, returning false if there is none
+    pub fn take(&mut self, class: Class) -> bool {
+        match Pocket::index(class) {
+            Some(i) if self.counts[i] > 0 => {
+                self.counts[i] -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|c| *c == 0)
+    }
+
+    /// Returns the classes with at least one piece in the pocket
+    pub fn classes(&self) -> Vec<Class> {
+        DROPPABLE
+            .into_iter()
+            .filter(|class| self.count(*class) > 0)
+            .collect()
+    }
+
+    /// Writes the pocket as FEN piece letters in the case of the color
+    pub fn fen(&self, color: Color) -> String {
+        let mut fen = String::new();
+
+        for class in DROPPABLE {
+            for _ in 0..self.count(class) {
+                fen.push_str(&Piece::new(class, color).to_fen());
+            }
+        }
+
+        fen
+    }
+}
@@ -0,0 +1,149 @@
+//! The moves of a game as a tree, so a line taken back and replaced by a
+//! different move stays around as a variation
+
+use crate::{
+    chess::{Board, Error},
+    game::GameMove,
+    mover::Move,
+    pgn::{san, PgnMove},
+};
+
+/// A move with the moves played after it, the main continuation first
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub entry: GameMove,
+    pub children: Vec<Node>,
+}
+
+/// Every line played in a game. At each branch point the first move is
+/// the main line and the others are variations, in the order they were
+/// played.
+#[derive(Debug, Clone, Default)]
+pub struct MoveTree {
+    roots: Vec<Node>,
+}
+
+impl MoveTree {
+    pub fn new() -> MoveTree {
+        MoveTree::default()
+    }
+
+    /// Returns the first moves of the game, main line first
+    pub fn roots(&self) -> &[Node] {
+        &self.roots
+    }
+
+    /// Returns the moves played after a line, main continuation first, or
+    /// nothing when the line isn't in the tree
+    pub fn children(&self, line: &[Move]) -> &[Node] {
+        let mut nodes = &self.roots[..];
+
+        for m in line {
+            match nodes.iter().find(|node| node.entry.m == *m) {
+                Some(node) => nodes = &node.children,
+                None => return &[],
+            }
+        }
+
+        nodes
+    }
+
+    /// Adds a line, following the moves already in the tree and branching
+    /// off where it differs. Moves that were already there take the
+    /// entries of the line, so annotations stay up to date.
+    pub fn insert(&mut self, line: &[GameMove]) {
+        let mut nodes = &mut self.roots;
+
+        for entry in line {
+            let index = match nodes.iter().position(|node| node.entry.m == entry.m) {
+                Some(index) => {
+                    nodes[index].entry = entry.clone();
+                    index
+                }
+                None => {
+                    nodes.push(Node {
+                        entry: entry.clone(),
+                        children: Vec::new(),
+                    });
+                    nodes.len() - 1
+                }
+            };
+
+            nodes = &mut nodes[index].children;
+        }
+    }
+
+    /// Returns the main continuation after a line: the first move at every
+    /// branch point from there on
+    pub fn main_line(&self, line: &[Move]) -> Vec<&GameMove> {
+        let mut moves = Vec::new();
+        let mut nodes = self.children(line);
+
+        while let Some(node) = nodes.first() {
+            moves.push(&node.entry);
+            nodes = &node.children;
+        }
+
+        moves
+    }
+
+    /// Makes a line the main line at every branch point along it
+    pub fn promote(&mut self, line: &[Move]) -> Result<(), Error> {
+        let mut nodes = &mut self.roots;
+
+        for m in line {
+            let index = nodes
+                .iter()
+                .position(|node| node.entry.m == *m)
+                .ok_or(Error::InvalidInput)?;
+
+            let node = nodes.remove(index);
+            nodes.insert(0, node);
+            nodes = &mut nodes[0].children;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the tree from the start position of the game as PGN moves,
+    /// with the variations of every move beside it
+    pub fn to_pgn_moves(&self, start: Board) -> Result<Vec<PgnMove>, Error> {
+        pgn_line(start, &self.roots)
+    }
+}
+
+/// Follows the main continuation of `nodes`, writing the other moves at
+/// every branch point as variations
+fn pgn_line(mut board: Board, nodes: &[Node]) -> Result<Vec<PgnMove>, Error> {
+    let mut moves = Vec::new();
+    let mut nodes = nodes;
+
+    while let Some(main) = nodes.first() {
+        let mut variations = Vec::new();
+        for variation in &nodes[1..] {
+            let mut line = vec![PgnMove {
+                san: san(&board, &variation.entry.m)?,
+                nags: variation.entry.nags.clone(),
+                comment: variation.entry.annotation.clone(),
+                variations: Vec::new(),
+            }];
+            line.extend(pgn_line(
+                board.with_move(&variation.entry.m)?,
+                &variation.children,
+            )?);
+            variations.push(line);
+        }
+
+        moves.push(PgnMove {
+            san: san(&board, &main.entry.m)?,
+            nags: main.entry.nags.clone(),
+            comment: main.entry.annotation.clone(),
+            variations,
+        });
+
+        board = board.with_move(&main.entry.m)?;
+        nodes = &main.children;
+    }
+
+    Ok(moves)
+}
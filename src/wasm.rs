@@ -0,0 +1,81 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    chess::{Board, Error},
+    fen::ToFen,
+    mover::parse_square,
+};
+
+fn to_js(e: Error) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// A board exposed to JavaScript, with moves and squares as strings
+#[wasm_bindgen(js_name = Board)]
+pub struct WasmBoard {
+    board: Board,
+}
+
+#[wasm_bindgen(js_class = Board)]
+impl WasmBoard {
+    /// Creates a board with the default starting position
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmBoard, JsError> {
+        let board = Board::default_board().map_err(to_js)?;
+        Ok(WasmBoard { board })
+    }
+
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<WasmBoard, JsError> {
+        let mut board = Board::new().map_err(to_js)?;
+        board.from_fen(fen).map_err(to_js)?;
+        Ok(WasmBoard { board })
+    }
+
+    #[wasm_bindgen(js_name = loadFen)]
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), JsError> {
+        self.board.from_fen(fen).map_err(to_js)
+    }
+
+    pub fn fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    /// Returns "white" or "black"
+    pub fn turn(&self) -> String {
+        self.board.turn().to_string()
+    }
+
+    #[wasm_bindgen(js_name = isCheck)]
+    pub fn is_check(&self) -> bool {
+        self.board.is_check()
+    }
+
+    /// Returns every legal move in coordinate notation
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.board
+            .legal_moves()
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+
+    /// Returns the legal moves of the piece on a square such as "e2"
+    #[wasm_bindgen(js_name = legalMovesFrom)]
+    pub fn legal_moves_from(&self, square: &str) -> Result<Vec<String>, JsError> {
+        let (file, rank) = parse_square(square).map_err(to_js)?;
+
+        Ok(self
+            .board
+            .legal_moves_from(file, rank)
+            .iter()
+            .map(|m| m.to_string())
+            .collect())
+    }
+
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, m: &str) -> Result<(), JsError> {
+        self.board.move_piece(m).map(|_| ()).map_err(to_js)
+    }
+}